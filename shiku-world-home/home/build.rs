@@ -0,0 +1,132 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Diesel's own setup only installs postgres helper functions.
+const POSTGRES_SETUP_MIGRATION: &str = "00000000000000_diesel_initial_setup";
+const POSTGRES_ONLY: &str = "-- postgres only";
+const SQLITE_ONLY: &str = "-- sqlite: ";
+
+/// The SQLite backend runs the migrations in `migrations/` as well, translated here.
+/// Most of the postgres dialect maps over directly. Where it does not, a statement after a
+/// `-- postgres only` line is left out and lines starting with `-- sqlite: ` are uncommented.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    if env::var_os("CARGO_FEATURE_SQLITE").is_none() {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap()).join("migrations_sqlite");
+    if out_dir.exists() {
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+    for entry in fs::read_dir("migrations").unwrap() {
+        let migration = entry.unwrap().path();
+        let name = migration.file_name().unwrap().to_string_lossy().to_string();
+        if !migration.is_dir() || name == POSTGRES_SETUP_MIGRATION {
+            continue;
+        }
+        let sqlite_migration = out_dir.join(&name);
+        fs::create_dir_all(&sqlite_migration).unwrap();
+        for file in ["up.sql", "down.sql"] {
+            let sql = fs::read_to_string(migration.join(file)).unwrap();
+            fs::write(sqlite_migration.join(file), to_sqlite(&sql)).unwrap();
+        }
+        copy_if_exists(
+            &migration.join("metadata.toml"),
+            &sqlite_migration.join("metadata.toml"),
+        );
+    }
+
+    fs::write(
+        PathBuf::from(env::var("OUT_DIR").unwrap()).join("sqlite_migrations.rs"),
+        format!(
+            "diesel_migrations::embed_migrations!({:?})",
+            out_dir.to_string_lossy()
+        ),
+    )
+    .unwrap();
+}
+
+fn copy_if_exists(from: &Path, to: &Path) {
+    if from.exists() {
+        fs::copy(from, to).unwrap();
+    }
+}
+
+fn to_sqlite(sql: &str) -> String {
+    let mut statements = Vec::new();
+    let mut statement: Vec<String> = Vec::new();
+    let mut skip_statement = false;
+    for line in sql.lines() {
+        if line.trim() == POSTGRES_ONLY {
+            skip_statement = true;
+            continue;
+        }
+        if let Some(sqlite_line) = line.strip_prefix(SQLITE_ONLY) {
+            statement.push(sqlite_line.to_string());
+        } else if !skip_statement {
+            statement.push(translate_line(line));
+        }
+        if line.trim_end().ends_with(';') {
+            if !skip_statement {
+                statements.push(split_alter_table(&drop_primary_key_constraint(statement)));
+            }
+            statement = Vec::new();
+            skip_statement = false;
+        }
+    }
+    statements.push(statement.join("\n"));
+    statements.join("\n")
+}
+
+fn translate_line(line: &str) -> String {
+    let lowercase = line.to_lowercase();
+    if lowercase.trim_start().starts_with("id serial") || lowercase.contains("serial primary key")
+    {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let comma = if line.trim_end().ends_with(',') { "," } else { "" };
+        return format!("{}id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL{}", indent, comma);
+    }
+    line.replace("ALTER TABLE IF EXISTS", "ALTER TABLE")
+        .replace("jsonb", "TEXT")
+        .replace("NOW()", "CURRENT_TIMESTAMP")
+}
+
+/// The id column already is the primary key once translated.
+fn drop_primary_key_constraint(mut lines: Vec<String>) -> Vec<String> {
+    if let Some(position) = lines
+        .iter()
+        .position(|line| line.trim().trim_end_matches(',') == "PRIMARY KEY (id)")
+    {
+        if !lines.remove(position).trim_end().ends_with(',') {
+            if let Some(previous) = lines[..position]
+                .iter_mut()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+            {
+                *previous = previous.trim_end().trim_end_matches(',').to_string();
+            }
+        }
+    }
+    lines
+}
+
+/// SQLite only takes one action per `ALTER TABLE`.
+fn split_alter_table(lines: &[String]) -> String {
+    let Some(first_action) = lines.iter().position(|line| !line.trim().is_empty()) else {
+        return lines.join("\n");
+    };
+    let head = lines[first_action].trim();
+    let is_bare_alter_table =
+        head.starts_with("ALTER TABLE") && head.split_whitespace().count() == 3;
+    if !is_bare_alter_table {
+        return lines.join("\n");
+    }
+    let actions = lines[first_action + 1..].join("\n");
+    let mut split: Vec<String> = lines[..first_action].to_vec();
+    for action in actions.trim().trim_end_matches(';').split(",\n") {
+        split.push(format!("{} {};", head, action.trim()));
+    }
+    split.join("\n")
+}
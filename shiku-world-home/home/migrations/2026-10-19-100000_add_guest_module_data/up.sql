@@ -0,0 +1,15 @@
+CREATE TABLE guest_module_data
+(
+    id serial NOT NULL,
+    persisted_guest_state_id integer NOT NULL,
+    module_id character varying(64) NOT NULL,
+    key character varying(64) NOT NULL,
+    value jsonb NOT NULL,
+    updated_at timestamp NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT guest_module_data_owner FOREIGN KEY (persisted_guest_state_id)
+        REFERENCES persisted_guest_states (id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    CONSTRAINT guest_module_data_key_once_per_module UNIQUE (persisted_guest_state_id, module_id, key)
+);
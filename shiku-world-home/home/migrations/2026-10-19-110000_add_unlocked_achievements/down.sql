@@ -0,0 +1 @@
+DROP TABLE unlocked_achievements;
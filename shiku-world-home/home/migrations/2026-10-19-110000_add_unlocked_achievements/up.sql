@@ -0,0 +1,14 @@
+CREATE TABLE unlocked_achievements
+(
+    id serial NOT NULL,
+    persisted_guest_state_id integer NOT NULL,
+    module_id character varying(64) NOT NULL,
+    achievement_id character varying(64) NOT NULL,
+    unlocked_at timestamp NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT unlocked_achievements_owner FOREIGN KEY (persisted_guest_state_id)
+        REFERENCES persisted_guest_states (id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    CONSTRAINT achievement_only_unlocked_once_per_guest UNIQUE (persisted_guest_state_id, module_id, achievement_id)
+);
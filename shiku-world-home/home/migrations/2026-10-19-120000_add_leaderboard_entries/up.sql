@@ -0,0 +1,18 @@
+CREATE TABLE leaderboard_entries
+(
+    id serial NOT NULL,
+    persisted_guest_state_id integer NOT NULL,
+    module_id character varying(64) NOT NULL,
+    leaderboard_id character varying(64) NOT NULL,
+    period_start timestamp NOT NULL,
+    score double precision NOT NULL,
+    updated_at timestamp NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT leaderboard_entries_owner FOREIGN KEY (persisted_guest_state_id)
+        REFERENCES persisted_guest_states (id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    CONSTRAINT leaderboard_entry_once_per_period UNIQUE (persisted_guest_state_id, module_id, leaderboard_id, period_start)
+);
+
+CREATE INDEX leaderboard_entries_board ON leaderboard_entries (module_id, leaderboard_id, period_start);
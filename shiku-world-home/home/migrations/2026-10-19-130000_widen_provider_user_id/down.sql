@@ -0,0 +1,2 @@
+-- postgres only
+ALTER TABLE persisted_guest_states ALTER COLUMN twitch_id TYPE VARCHAR(20);
@@ -0,0 +1,3 @@
+-- SQLite does not enforce varchar lengths.
+-- postgres only
+ALTER TABLE persisted_guest_states ALTER COLUMN twitch_id TYPE VARCHAR(255);
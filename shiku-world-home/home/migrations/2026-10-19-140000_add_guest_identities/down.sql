@@ -0,0 +1,50 @@
+-- sqlite: PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+-- postgres only
+ALTER TABLE persisted_guest_states ADD COLUMN twitch_id VARCHAR(255) NOT NULL DEFAULT '';
+
+-- postgres only
+UPDATE persisted_guest_states
+SET twitch_id = COALESCE(
+    (SELECT provider_user_id FROM guest_identities
+     WHERE guest_identities.persisted_guest_state_id = persisted_guest_states.id
+     ORDER BY guest_identities.id LIMIT 1),
+    'missing:' || persisted_guest_states.id
+);
+
+-- postgres only
+ALTER TABLE persisted_guest_states ADD CONSTRAINT persisted_guest_states_twitch_id_key UNIQUE (twitch_id);
+
+-- sqlite: CREATE TABLE persisted_guest_states_new (
+-- sqlite:     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+-- sqlite:     twitch_id VARCHAR(255) UNIQUE NOT NULL DEFAULT '',
+-- sqlite:     display_name VARCHAR(15) NOT NULL DEFAULT 'BROKEN',
+-- sqlite:     is_observer BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     is_tester BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     last_time_joined TIMESTAMP,
+-- sqlite:     times_joined INTEGER NOT NULL DEFAULT 0,
+-- sqlite:     is_discord_admin BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     is_discord_booster BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     slime_skin_name VARCHAR(20) NOT NULL DEFAULT ''
+-- sqlite: );
+-- sqlite: INSERT INTO persisted_guest_states_new
+-- sqlite: SELECT id,
+-- sqlite:        COALESCE(
+-- sqlite:            (SELECT provider_user_id FROM guest_identities
+-- sqlite:             WHERE guest_identities.persisted_guest_state_id = persisted_guest_states.id
+-- sqlite:             ORDER BY guest_identities.id LIMIT 1),
+-- sqlite:            'missing:' || persisted_guest_states.id
+-- sqlite:        ),
+-- sqlite:        display_name, is_observer, is_tester, last_time_joined, times_joined,
+-- sqlite:        is_discord_admin, is_discord_booster, slime_skin_name
+-- sqlite: FROM persisted_guest_states;
+-- sqlite: DROP TABLE persisted_guest_states;
+-- sqlite: ALTER TABLE persisted_guest_states_new RENAME TO persisted_guest_states;
+
+DROP TABLE guest_identities;
+
+COMMIT;
+
+-- sqlite: PRAGMA foreign_keys = ON;
@@ -0,0 +1,42 @@
+-- SQLite cannot drop a UNIQUE column, so on SQLite persisted_guest_states is rebuilt without it.
+-- sqlite: PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE guest_identities
+(
+    id SERIAL PRIMARY KEY,
+    persisted_guest_state_id INTEGER NOT NULL
+        REFERENCES persisted_guest_states (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    provider_user_id VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT identity_belongs_to_one_guest UNIQUE (provider_user_id)
+);
+
+INSERT INTO guest_identities (persisted_guest_state_id, provider_user_id)
+SELECT id, twitch_id FROM persisted_guest_states;
+
+-- postgres only
+ALTER TABLE persisted_guest_states DROP COLUMN twitch_id;
+
+-- sqlite: CREATE TABLE persisted_guest_states_new (
+-- sqlite:     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+-- sqlite:     display_name VARCHAR(15) NOT NULL DEFAULT 'BROKEN',
+-- sqlite:     is_observer BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     is_tester BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     last_time_joined TIMESTAMP,
+-- sqlite:     times_joined INTEGER NOT NULL DEFAULT 0,
+-- sqlite:     is_discord_admin BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     is_discord_booster BOOLEAN NOT NULL DEFAULT FALSE,
+-- sqlite:     slime_skin_name VARCHAR(20) NOT NULL DEFAULT ''
+-- sqlite: );
+-- sqlite: INSERT INTO persisted_guest_states_new
+-- sqlite: SELECT id, display_name, is_observer, is_tester, last_time_joined, times_joined,
+-- sqlite:        is_discord_admin, is_discord_booster, slime_skin_name
+-- sqlite: FROM persisted_guest_states;
+-- sqlite: DROP TABLE persisted_guest_states;
+-- sqlite: ALTER TABLE persisted_guest_states_new RENAME TO persisted_guest_states;
+
+COMMIT;
+
+-- sqlite: PRAGMA foreign_keys = ON;
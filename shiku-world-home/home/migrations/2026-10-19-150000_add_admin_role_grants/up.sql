@@ -0,0 +1,11 @@
+CREATE TABLE admin_role_grants
+(
+    id serial NOT NULL,
+    provider_user_id character varying(255) NOT NULL,
+    role character varying(32) NOT NULL,
+    module_id character varying(64),
+    granted_at timestamp NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX admin_role_grants_provider_user ON admin_role_grants (provider_user_id);
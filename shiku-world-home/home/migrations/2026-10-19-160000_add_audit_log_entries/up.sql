@@ -0,0 +1,23 @@
+CREATE TABLE audit_log_entries
+(
+    id serial NOT NULL,
+    provider_user_id character varying(255) NOT NULL,
+    action character varying(64) NOT NULL,
+    module_ids text NOT NULL,
+    resource text,
+    diff text NOT NULL,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX audit_log_entries_provider_user ON audit_log_entries (provider_user_id);
+
+-- postgres only
+CREATE RULE audit_log_entries_no_update AS ON UPDATE TO audit_log_entries DO INSTEAD NOTHING;
+-- postgres only
+CREATE RULE audit_log_entries_no_delete AS ON DELETE TO audit_log_entries DO INSTEAD NOTHING;
+
+-- sqlite: CREATE TRIGGER audit_log_entries_no_update BEFORE UPDATE ON audit_log_entries
+-- sqlite: BEGIN SELECT RAISE(IGNORE); END;
+-- sqlite: CREATE TRIGGER audit_log_entries_no_delete BEFORE DELETE ON audit_log_entries
+-- sqlite: BEGIN SELECT RAISE(IGNORE); END;
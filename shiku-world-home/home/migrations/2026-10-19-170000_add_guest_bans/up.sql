@@ -0,0 +1,10 @@
+CREATE TABLE guest_bans
+(
+    id serial NOT NULL,
+    provider_user_id character varying(255) NOT NULL,
+    reason text NOT NULL,
+    banned_until timestamp,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT one_ban_per_provider_user UNIQUE (provider_user_id)
+);
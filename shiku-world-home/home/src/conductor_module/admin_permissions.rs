@@ -0,0 +1,453 @@
+use std::collections::HashSet;
+use std::env;
+
+use log::warn;
+
+use crate::conductor_module::def::ResourceToModuleMap;
+use crate::core::blueprint::def::{JsonResource, ModuleId, ResourcePath};
+use crate::core::guest::{AdminRole, AdminRoleGrant, ProviderUserId};
+use crate::core::module::{AdminToSystemEvent, SceneNodeUpdate};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminPermission {
+    View,
+    EditScripts,
+    EditContent,
+    Moderate,
+    Manage,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PermissionScope {
+    Any,
+    Global,
+    Modules(Vec<ModuleId>),
+    /// Reads only need one of the modules, a resource can be shared between modules.
+    AnyOfModules(Vec<ModuleId>),
+}
+
+impl AdminRole {
+    pub fn has_permission(&self, permission: AdminPermission) -> bool {
+        match self {
+            AdminRole::Owner => true,
+            AdminRole::Editor => matches!(
+                permission,
+                AdminPermission::View | AdminPermission::EditScripts | AdminPermission::EditContent
+            ),
+            AdminRole::Scripter => matches!(
+                permission,
+                AdminPermission::View | AdminPermission::EditScripts
+            ),
+            AdminRole::Moderator => {
+                matches!(
+                    permission,
+                    AdminPermission::View | AdminPermission::Moderate
+                )
+            }
+        }
+    }
+}
+
+/// Owners come from `ADMIN_OWNER_PROVIDER_IDS`, they are the ones handing out the persisted
+/// grants. Without any, grants have to be added to the database by hand.
+pub struct AdminPermissions {
+    owner_provider_ids: HashSet<ProviderUserId>,
+    grants: Vec<AdminRoleGrant>,
+}
+
+impl AdminPermissions {
+    pub fn new(
+        owner_provider_ids: HashSet<ProviderUserId>,
+        grants: Vec<AdminRoleGrant>,
+    ) -> AdminPermissions {
+        AdminPermissions {
+            owner_provider_ids,
+            grants,
+        }
+    }
+
+    pub fn from_env(grants: Vec<AdminRoleGrant>) -> AdminPermissions {
+        let owner_provider_ids: HashSet<ProviderUserId> = env::var("ADMIN_OWNER_PROVIDER_IDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|provider_user_id| provider_user_id.trim().to_string())
+            .filter(|provider_user_id| !provider_user_id.is_empty())
+            .collect();
+        if owner_provider_ids.is_empty() {
+            warn!("ADMIN_OWNER_PROVIDER_IDS is not set, nobody can hand out admin roles.");
+        }
+
+        AdminPermissions::new(owner_provider_ids, grants)
+    }
+
+    pub fn can_login(&self, provider_user_id: &ProviderUserId) -> bool {
+        self.owner_provider_ids.contains(provider_user_id)
+            || self
+                .grants
+                .iter()
+                .any(|grant| &grant.provider_user_id == provider_user_id)
+    }
+
+    pub fn is_allowed(
+        &self,
+        provider_user_id: &ProviderUserId,
+        permission: AdminPermission,
+        scope: &PermissionScope,
+    ) -> bool {
+        if self.owner_provider_ids.contains(provider_user_id) {
+            return true;
+        }
+
+        let grants: Vec<&AdminRoleGrant> = self
+            .grants
+            .iter()
+            .filter(|grant| {
+                &grant.provider_user_id == provider_user_id && grant.role.has_permission(permission)
+            })
+            .collect();
+        let is_allowed_in = |module_id: Option<&ModuleId>| {
+            grants
+                .iter()
+                .any(|grant| grant.module_id.is_none() || grant.module_id.as_ref() == module_id)
+        };
+
+        match scope {
+            PermissionScope::Any => !grants.is_empty(),
+            PermissionScope::Global => is_allowed_in(None),
+            PermissionScope::Modules(module_ids) | PermissionScope::AnyOfModules(module_ids)
+                if module_ids.is_empty() =>
+            {
+                is_allowed_in(None)
+            }
+            PermissionScope::Modules(module_ids) => module_ids
+                .iter()
+                .all(|module_id| is_allowed_in(Some(module_id))),
+            PermissionScope::AnyOfModules(module_ids) => module_ids
+                .iter()
+                .any(|module_id| is_allowed_in(Some(module_id))),
+        }
+    }
+
+    pub fn grant(&mut self, grant: AdminRoleGrant) -> bool {
+        if self.grants.contains(&grant) {
+            return false;
+        }
+        self.grants.push(grant);
+        true
+    }
+
+    pub fn revoke(&mut self, grant: &AdminRoleGrant) -> bool {
+        let grants_before = self.grants.len();
+        self.grants.retain(|existing_grant| existing_grant != grant);
+        grants_before != self.grants.len()
+    }
+
+    pub fn check_event(
+        &self,
+        provider_user_id: &ProviderUserId,
+        event: &AdminToSystemEvent,
+        resource_to_module_map: &ResourceToModuleMap,
+    ) -> Result<(), String> {
+        let (permission, scope) = required_permission(event, resource_to_module_map);
+        if self.is_allowed(provider_user_id, permission, &scope) {
+            return Ok(());
+        }
+
+        Err(match scope {
+            PermissionScope::Modules(module_ids) | PermissionScope::AnyOfModules(module_ids)
+                if !module_ids.is_empty() =>
+            {
+                format!(
+                    "You are not allowed to {} in {}.",
+                    permission.describe(),
+                    module_ids.join(", ")
+                )
+            }
+            _ => format!("You are not allowed to {}.", permission.describe()),
+        })
+    }
+}
+
+impl AdminPermission {
+    fn describe(&self) -> &'static str {
+        match self {
+            AdminPermission::View => "use the editor",
+            AdminPermission::EditScripts => "edit scripts",
+            AdminPermission::EditContent => "edit module content",
+            AdminPermission::Moderate => "moderate",
+            AdminPermission::Manage => "manage the server",
+        }
+    }
+}
+
+/// Resources no module uses yet can only be touched with a grant for all modules.
+fn modules_of(
+    resource_to_module_map: &ResourceToModuleMap,
+    resource_path: &ResourcePath,
+) -> PermissionScope {
+    PermissionScope::Modules(
+        resource_to_module_map
+            .get(resource_path)
+            .map(|module_ids| module_ids.iter().cloned().collect())
+            .unwrap_or_default(),
+    )
+}
+
+/// A folder belongs to the modules using any resource below it.
+fn modules_in_folder(
+    resource_to_module_map: &ResourceToModuleMap,
+    folder: &str,
+) -> PermissionScope {
+    let folder = folder.trim_end_matches('/');
+    let mut module_ids: Vec<ModuleId> = resource_to_module_map
+        .iter()
+        .filter(|(resource_path, _)| {
+            folder.is_empty()
+                || resource_path
+                    .strip_prefix(folder)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .flat_map(|(_, module_ids)| module_ids.iter().cloned())
+        .collect();
+    module_ids.sort();
+    module_ids.dedup();
+    PermissionScope::AnyOfModules(module_ids)
+}
+
+pub fn required_permission(
+    event: &AdminToSystemEvent,
+    resource_to_module_map: &ResourceToModuleMap,
+) -> (AdminPermission, PermissionScope) {
+    let in_module = |module_id: &ModuleId| PermissionScope::Modules(vec![module_id.clone()]);
+    match event {
+        AdminToSystemEvent::Ping
+        | AdminToSystemEvent::ProviderLoggedIn(_)
+        | AdminToSystemEvent::LoadEditorData => (AdminPermission::View, PermissionScope::Any),
+        AdminToSystemEvent::BrowseFolder(folder) => (
+            AdminPermission::View,
+            modules_in_folder(resource_to_module_map, folder),
+        ),
+        AdminToSystemEvent::GetResource(resource_path) => (
+            AdminPermission::View,
+            match modules_of(resource_to_module_map, resource_path) {
+                PermissionScope::Modules(module_ids) => PermissionScope::AnyOfModules(module_ids),
+                scope => scope,
+            },
+        ),
+        AdminToSystemEvent::OpenInstance(module_id)
+        | AdminToSystemEvent::StartInspectingWorld(module_id, _, _)
+        | AdminToSystemEvent::StopInspectingWorld(module_id, _, _)
+        | AdminToSystemEvent::WorldInitialized(module_id, _, _)
+        | AdminToSystemEvent::ControlInput(module_id, _, _) => {
+            (AdminPermission::View, in_module(module_id))
+        }
+        AdminToSystemEvent::QueryAuditLog(query) => (
+            AdminPermission::View,
+            query
+                .module_id
+                .as_ref()
+                .map_or(PermissionScope::Global, in_module),
+        ),
+        AdminToSystemEvent::UpdateConductor(_)
+        | AdminToSystemEvent::SetGateOpen(_, _)
+        | AdminToSystemEvent::CreateModule(_)
+        | AdminToSystemEvent::DeleteModule(_)
+        | AdminToSystemEvent::GrantAdminRole(_)
+        | AdminToSystemEvent::RevokeAdminRole(_) => {
+            (AdminPermission::Manage, PermissionScope::Global)
+        }
+        AdminToSystemEvent::KickFromModule(_)
+        | AdminToSystemEvent::KickFromServer(_, _)
+        | AdminToSystemEvent::BanGuest(_)
+        | AdminToSystemEvent::UnbanGuest(_)
+        | AdminToSystemEvent::SetObserver(_, _)
+        | AdminToSystemEvent::QueryRoundTripTimes => {
+            (AdminPermission::Moderate, PermissionScope::Global)
+        }
+        AdminToSystemEvent::CreateScript(module_id, _) => {
+            (AdminPermission::EditScripts, in_module(module_id))
+        }
+        AdminToSystemEvent::UpdateScript(script) | AdminToSystemEvent::DeleteScript(script) => (
+            AdminPermission::EditScripts,
+            modules_of(resource_to_module_map, &script.get_full_resource_path()),
+        ),
+        AdminToSystemEvent::UpdateModule(module_id, _)
+        | AdminToSystemEvent::ResetGameWorld(module_id, _, _)
+        | AdminToSystemEvent::UpdateInstancedNode(module_id, _, _, _)
+        | AdminToSystemEvent::RemoveInstanceNode(module_id, _, _, _)
+        | AdminToSystemEvent::AddNodeToInstanceNode(module_id, _, _, _, _)
+        | AdminToSystemEvent::CreateTileset(module_id, _)
+        | AdminToSystemEvent::CreateScene(module_id, _)
+        | AdminToSystemEvent::CreateMap(module_id, _)
+        | AdminToSystemEvent::DeleteMap(module_id, _)
+        | AdminToSystemEvent::CreateCharacterAnimation(module_id, _) => {
+            (AdminPermission::EditContent, in_module(module_id))
+        }
+        AdminToSystemEvent::SetTileset(tileset) | AdminToSystemEvent::DeleteTileset(tileset) => (
+            AdminPermission::EditContent,
+            modules_of(resource_to_module_map, &tileset.get_full_resource_path()),
+        ),
+        AdminToSystemEvent::UpdateTileset(resource_path, _)
+        | AdminToSystemEvent::OverwriteSceneRoot(resource_path, _)
+        | AdminToSystemEvent::UpdateSceneNode(
+            SceneNodeUpdate::UpdateData(resource_path, _, _, _)
+            | SceneNodeUpdate::AddChild(resource_path, _, _, _)
+            | SceneNodeUpdate::RemoveChild(resource_path, _, _),
+        ) => (
+            AdminPermission::EditContent,
+            modules_of(resource_to_module_map, resource_path),
+        ),
+        AdminToSystemEvent::DeleteScene(scene) => (
+            AdminPermission::EditContent,
+            modules_of(resource_to_module_map, &scene.get_full_resource_path()),
+        ),
+        AdminToSystemEvent::UpdateMap(map_update) => (
+            AdminPermission::EditContent,
+            modules_of(resource_to_module_map, &map_update.get_full_resource_path()),
+        ),
+        AdminToSystemEvent::UpdateCharacterAnimation(character_animation)
+        | AdminToSystemEvent::DeleteCharacterAnimation(character_animation) => (
+            AdminPermission::EditContent,
+            modules_of(
+                resource_to_module_map,
+                &character_animation.get_full_resource_path(),
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn grant(role: AdminRole, module_id: Option<&str>) -> AdminRoleGrant {
+        AdminRoleGrant {
+            provider_user_id: "builder".into(),
+            role,
+            module_id: module_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_module_grants_only_reach_their_module() {
+        let permissions = AdminPermissions::new(
+            HashSet::from(["owner".to_string()]),
+            vec![
+                grant(AdminRole::Editor, Some("castle")),
+                grant(AdminRole::Scripter, Some("forest")),
+            ],
+        );
+        let builder = "builder".to_string();
+        let in_module = |module_id: &str| PermissionScope::Modules(vec![module_id.into()]);
+
+        assert!(permissions.can_login(&builder));
+        assert!(!permissions.can_login(&"stranger".to_string()));
+        assert!(permissions.is_allowed(
+            &builder,
+            AdminPermission::EditContent,
+            &in_module("castle")
+        ));
+        assert!(!permissions.is_allowed(
+            &builder,
+            AdminPermission::EditContent,
+            &in_module("forest")
+        ));
+        assert!(permissions.is_allowed(
+            &builder,
+            AdminPermission::EditScripts,
+            &in_module("forest")
+        ));
+        assert!(!permissions.is_allowed(
+            &builder,
+            AdminPermission::EditContent,
+            &PermissionScope::Modules(Vec::new())
+        ));
+        assert!(!permissions.is_allowed(
+            &builder,
+            AdminPermission::Manage,
+            &PermissionScope::Global
+        ));
+        assert!(permissions.is_allowed(
+            &"owner".to_string(),
+            AdminPermission::Manage,
+            &PermissionScope::Global
+        ));
+    }
+
+    #[test]
+    fn test_check_event_uses_resource_modules() {
+        let mut permissions = AdminPermissions::new(
+            HashSet::new(),
+            vec![grant(AdminRole::Editor, Some("castle"))],
+        );
+        let resource_to_module_map: ResourceToModuleMap = HashMap::from([(
+            "castle/tower.tileset.json".to_string(),
+            HashSet::from(["castle".to_string(), "forest".to_string()]),
+        )]);
+        let builder = "builder".to_string();
+        let event = AdminToSystemEvent::UpdateTileset(
+            "castle/tower.tileset.json".into(),
+            crate::core::module::TilesetUpdate::RemoveTile(1),
+        );
+
+        assert!(permissions
+            .check_event(&builder, &event, &resource_to_module_map)
+            .is_err());
+        assert!(permissions.grant(grant(AdminRole::Editor, Some("forest"))));
+        assert!(!permissions.grant(grant(AdminRole::Editor, Some("forest"))));
+        assert!(permissions
+            .check_event(&builder, &event, &resource_to_module_map)
+            .is_ok());
+        assert!(permissions
+            .check_event(
+                &builder,
+                &AdminToSystemEvent::DeleteModule("castle".into()),
+                &resource_to_module_map
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_reads_are_scoped_to_the_modules_using_the_resource() {
+        let permissions = AdminPermissions::new(
+            HashSet::new(),
+            vec![grant(AdminRole::Scripter, Some("castle"))],
+        );
+        let resource_to_module_map: ResourceToModuleMap = HashMap::from([
+            (
+                "shared/tower.tileset.json".to_string(),
+                HashSet::from(["castle".to_string(), "forest".to_string()]),
+            ),
+            (
+                "forest/tree.tileset.json".to_string(),
+                HashSet::from(["forest".to_string()]),
+            ),
+        ]);
+        let builder = "builder".to_string();
+        let is_allowed = |event: AdminToSystemEvent| {
+            permissions
+                .check_event(&builder, &event, &resource_to_module_map)
+                .is_ok()
+        };
+
+        assert!(is_allowed(AdminToSystemEvent::GetResource(
+            "shared/tower.tileset.json".into()
+        )));
+        assert!(!is_allowed(AdminToSystemEvent::GetResource(
+            "forest/tree.tileset.json".into()
+        )));
+        assert!(!is_allowed(AdminToSystemEvent::GetResource(
+            "unused.tileset.json".into()
+        )));
+        assert!(is_allowed(AdminToSystemEvent::BrowseFolder("".into())));
+        assert!(is_allowed(AdminToSystemEvent::BrowseFolder(
+            "shared".into()
+        )));
+        assert!(!is_allowed(AdminToSystemEvent::BrowseFolder(
+            "forest".into()
+        )));
+        assert!(!is_allowed(AdminToSystemEvent::BrowseFolder("shar".into())));
+    }
+}
@@ -1,47 +1,102 @@
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::path::PathBuf;
 
-use flume::Sender;
 use log::{debug, error};
 use uuid::Uuid;
 
+use crate::conductor_module::audit_log::audit_log_entry;
 use crate::conductor_module::blueprint_helper::{
     bring_polygon_in_clockwise_order, loading_resources_from_blueprint_resource,
     save_and_send_conductor_update,
 };
-use crate::conductor_module::def::{
-    ConductorModule, ModuleCommunicationMap, ModuleMap, ResourceToModuleMap,
-};
+use crate::conductor_module::def::{AdminEventContext, ConductorModule, ModuleMap};
+use crate::conductor_module::errors::AdminEventError;
 use crate::conductor_module::game_instances::{
     create_game_instance_manager, remove_game_instance_manager,
 };
+use crate::conductor_module::moderation::{describe_ban, ModerationAction};
 use crate::core::blueprint::def::{
     BlueprintResource, BlueprintService, Conductor, JsonResource, Module, ModuleId, ResourceKind,
     ResourceLoaded, Tileset,
 };
 use crate::core::blueprint::resource_loader::Blueprint;
 use crate::core::blueprint::scene::def::{CollisionShape, GameNodeKind};
-use crate::core::guest::{ActorId, Admin};
+use crate::core::guest::Admin;
 use crate::core::module::{
     AdminLeftSuccessState, AdminToSystemEvent, CommunicationEvent, EditorEvent, GuestToModuleEvent,
-    SceneNodeUpdate, TilesetUpdate,
+    SceneNodeUpdate, TilesetUpdate, ToastAlertLevel,
 };
 use crate::core::module_system::def::DynamicGameModule;
-use crate::core::module_system::game_instance::{GameInstance, GameInstanceManager};
+use crate::core::module_system::game_instance::{
+    GameInstance, GameInstanceId, GameInstanceManager,
+};
+use crate::core::server_gate::lock_gates;
 use crate::core::{log_result_error, send_and_log_error};
+use crate::persistence_module::worker::PersistenceJob;
 use crate::resource_module::def::{ResourceBundle, ResourceEvent, ResourceModule};
-use crate::webserver_module::def::WebServerModule;
+
+fn module_instances(module_map: &ModuleMap) -> Vec<(ModuleId, Vec<GameInstanceId>)> {
+    module_map
+        .values()
+        .map(|m| {
+            (
+                m.module_blueprint.id.clone(),
+                m.game_instances.values().map(|g| g.id.clone()).collect(),
+            )
+        })
+        .collect()
+}
 
 pub async fn handle_admin_to_system_event(
-    module_communication_map: &mut ModuleCommunicationMap,
-    web_server_module: &mut WebServerModule,
-    resource_module: &mut ResourceModule,
-    module_map: &mut ModuleMap,
-    resource_to_module_map: &mut ResourceToModuleMap,
-    system_to_admin_communication_sender: &mut Sender<(ActorId, CommunicationEvent)>,
+    context: AdminEventContext<'_>,
     admin: &Admin,
     event: AdminToSystemEvent,
-) {
+) -> Result<(), AdminEventError> {
+    let AdminEventContext {
+        module_communication_map,
+        web_server_module,
+        resource_module,
+        module_map,
+        resource_to_module_map,
+        leaderboards,
+        admin_permissions,
+        guest_bans,
+        moderation_actions,
+        guests,
+        persistence_module,
+        system_to_admin_communication_sender,
+    } = context;
+    let Some(login_data) = &admin.login_data else {
+        error!("Admin {} sent an event without login data?!", admin.id);
+        return Err(AdminEventError::NotAllowed("Not logged in.".into()));
+    };
+    if let Err(reason) =
+        admin_permissions.check_event(&login_data.provider_user_id, &event, resource_to_module_map)
+    {
+        debug!(
+            "Admin {} is not allowed to {:?}",
+            login_data.provider_user_id, event
+        );
+        send_and_log_error(
+            system_to_admin_communication_sender,
+            (
+                admin.id,
+                CommunicationEvent::Toast(ToastAlertLevel::Error, reason.clone()),
+            ),
+        );
+        return Err(AdminEventError::NotAllowed(reason));
+    }
+    let audit_log_entry =
+        audit_log_entry(&login_data.provider_user_id, &event, resource_to_module_map);
+
+    // Failures are logged right away, an event with any of them is not recorded as handled.
+    let failures = RefCell::new(Vec::new());
+    let fail = |message: String| {
+        error!("{}", message);
+        failures.borrow_mut().push(message);
+    };
+
     let mut send_communication_event = |event: CommunicationEvent| {
         send_and_log_error(system_to_admin_communication_sender, (admin.id, event));
     };
@@ -91,7 +146,7 @@ pub async fn handle_admin_to_system_event(
 
                     module.module_blueprint.resources = resources;
                 }
-                Err(err) => error!("Could not generate gid map! {:?}", err),
+                Err(err) => fail(format!("Could not generate gid map! {:?}", err)),
             }
         };
 
@@ -110,7 +165,7 @@ pub async fn handle_admin_to_system_event(
                     module.module_blueprint.char_animation_to_tileset_map =
                         char_anim_to_tileset_map;
                 }
-                Err(err) => error!("Could not generate gid map! {:?}", err),
+                Err(err) => fail(format!("Could not generate gid map! {:?}", err)),
             }
         };
 
@@ -128,7 +183,7 @@ pub async fn handle_admin_to_system_event(
                         ));
                     }
                     Err(err) => {
-                        error!("Could not save module {:?}", err);
+                        fail(format!("Could not save module {:?}", err));
                     }
                 }
             }
@@ -148,10 +203,10 @@ pub async fn handle_admin_to_system_event(
                             );
                         }
                         Err(err) => {
-                            error!(
+                            fail(format!(
                                 "Could not reset world {:?} {:?} {:?}: {:?}",
                                 module_id, instance_id, world_id, err
-                            );
+                            ));
                         }
                     }
                 }
@@ -172,12 +227,12 @@ pub async fn handle_admin_to_system_event(
                             send_editor_event(EditorEvent::SetScene(scene));
                         }
                         Err(err) => {
-                            error!("Could not save scene {:?}", err);
+                            fail(format!("Could not save scene {:?}", err));
                         }
                     }
                 }
                 Err(err) => {
-                    error!("Could not load scene {:?}", err);
+                    fail(format!("Could not load scene {:?}", err));
                 }
             }
         }
@@ -187,6 +242,7 @@ pub async fn handle_admin_to_system_event(
                     DynamicGameModule::set_actor_input(
                         &instance.dynamic_module.guest_to_world,
                         &instance.dynamic_module.admin_to_world,
+                        &instance.dynamic_module.observers,
                         &mut instance.dynamic_module.world_map,
                         &admin.id,
                         guest_input,
@@ -214,6 +270,7 @@ pub async fn handle_admin_to_system_event(
         AdminToSystemEvent::OpenInstance(module_id) => {
             if let Some(module) = module_map.get_mut(&module_id) {
                 module.create_new_game_instance();
+                send_editor_event(EditorEvent::ModuleInstances(module_instances(module_map)));
             }
         }
         AdminToSystemEvent::StartInspectingWorld(module_id, game_instance_id, world_id) => {
@@ -265,29 +322,31 @@ pub async fn handle_admin_to_system_event(
                                                 ),
                                             );
                                         }
-                                        Err(err) => {
-                                            error!("Could not load tilesets for module! {:?}", err)
-                                        }
+                                        Err(err) => fail(format!(
+                                            "Could not load tilesets for module! {:?}",
+                                            err
+                                        )),
                                     }
                                 } else {
-                                    error!(
+                                    fail(format!(
                                         "Could not get terrain params to inspect! {:?} {:?} {:?}",
                                         module_id, game_instance_id, world_id
-                                    );
+                                    ));
                                 }
                             }
-                            Err(err) => {
-                                error!("Could not send prepare game, no resources?! {:?}", err)
-                            }
+                            Err(err) => fail(format!(
+                                "Could not send prepare game, no resources?! {:?}",
+                                err
+                            )),
                         }
                     }
-                    Err(err) => error!("Could not get admin into instance/map {:?}", err),
+                    Err(err) => fail(format!("Could not get admin into instance/map {:?}", err)),
                 }
             } else {
-                error!(
+                fail(format!(
                     "module not found to inspect...? {:?} {:?} {:?}",
                     module_id, game_instance_id, world_id
-                );
+                ));
             }
         }
         AdminToSystemEvent::StopInspectingWorld(module_id, game_instance_id, world_id) => {
@@ -304,7 +363,10 @@ pub async fn handle_admin_to_system_event(
                                 if let Err(err) = resource_module
                                     .disable_module_resource_updates(module_id.clone(), &admin.id)
                                 {
-                                    error!("Could not unregister from resource updates! {:?}", err);
+                                    fail(format!(
+                                        "Could not unregister from resource updates! {:?}",
+                                        err
+                                    ));
                                 }
                             }
                         }
@@ -315,7 +377,7 @@ pub async fn handle_admin_to_system_event(
                             Some(world_id),
                         ));
                     }
-                    Err(err) => error!("Could not let admin leave instance/map {:?}", err),
+                    Err(err) => fail(format!("Could not let admin leave instance/map {:?}", err)),
                 }
             }
         }
@@ -351,12 +413,12 @@ pub async fn handle_admin_to_system_event(
                             send_editor_event(EditorEvent::UpdatedMap(map_update));
                         }
                         Err(err) => {
-                            error!("Could not update map {:?}", err);
+                            fail(format!("Could not update map {:?}", err));
                         }
                     }
                 }
                 Err(err) => {
-                    error!("Could not load map {:?}", err);
+                    fail(format!("Could not load map {:?}", err));
                 }
             }
         }
@@ -379,12 +441,12 @@ pub async fn handle_admin_to_system_event(
                                 send_editor_event(EditorEvent::DeletedMap(map));
                             }
                             Err(err) => {
-                                error!("Could not save module {:?}", err);
+                                fail(format!("Could not save module {:?}", err));
                             }
                         }
                     }
                     Err(err) => {
-                        error!("Could not create map {:?}", err);
+                        fail(format!("Could not create map {:?}", err));
                     }
                 }
             }
@@ -399,12 +461,12 @@ pub async fn handle_admin_to_system_event(
                             .create_world(&map)
                             .values()
                             .filter(|f| f.is_err())
-                            .for_each(|err| error!("{:?}", err));
+                            .for_each(|err| fail(format!("{:?}", err)));
                     }
                     send_editor_event(EditorEvent::SetMap(map));
                 }
                 Err(err) => {
-                    error!("Could not create tileset {:?}", err);
+                    fail(format!("Could not create tileset {:?}", err));
                 }
             }
         }
@@ -435,22 +497,145 @@ pub async fn handle_admin_to_system_event(
                 send_editor_event(EditorEvent::DirectoryInfo(result));
             }
             Err(err) => {
-                error!("Could not browse directory {:?}", err);
+                fail(format!("Could not browse directory {:?}", err));
             }
         },
-        AdminToSystemEvent::SetMainDoorStatus(status) => {
-            debug!("Setting main door status");
-            web_server_module.set_main_door_status(status).await;
-        }
-        AdminToSystemEvent::SetBackDoorStatus(status) => {
-            debug!("Setting back door status");
-            web_server_module.set_back_door_status(status).await;
+        AdminToSystemEvent::SetGateOpen(name, open) => {
+            debug!("Setting gate {} open: {}", name, open);
+            let gates = {
+                let mut gates = lock_gates(&web_server_module.gates);
+                match gates.iter_mut().find(|gate| gate.name == name) {
+                    Some(gate) => {
+                        gate.open = open;
+                        Some(gates.clone())
+                    }
+                    None => None,
+                }
+            };
+            match gates {
+                Some(gates) => match BlueprintService::load_conductor_blueprint() {
+                    Ok(mut conductor) => {
+                        conductor.gates = gates;
+                        save_and_send_conductor_update(conductor, &mut send_editor_event);
+                    }
+                    Err(err) => {
+                        fail(format!("Could not load conductor! {:?}", err));
+                    }
+                },
+                None => {
+                    let message = format!("There is no gate named {}.", name);
+                    failures.borrow_mut().push(message.clone());
+                    send_communication_event(CommunicationEvent::Toast(
+                        ToastAlertLevel::Error,
+                        message,
+                    ));
+                }
+            }
         }
         AdminToSystemEvent::ProviderLoggedIn(_) => {
-            error!("Admin should already be logged in!")
+            fail("Admin should already be logged in!".into())
+        }
+        AdminToSystemEvent::GrantAdminRole(grant) => {
+            if admin_permissions.grant(grant.clone()) {
+                if let Err(err) = persistence_module.queue(PersistenceJob::GrantAdminRole {
+                    admin_id: admin.id,
+                    grant: grant.clone(),
+                }) {
+                    fail(format!("Could not queue admin role grant {:?}", err));
+                }
+            }
+            send_communication_event(CommunicationEvent::Toast(
+                ToastAlertLevel::Success,
+                format!("Granted {:?} to {}.", grant.role, grant.provider_user_id),
+            ));
+        }
+        AdminToSystemEvent::RevokeAdminRole(grant) => {
+            if admin_permissions.revoke(&grant) {
+                if let Err(err) = persistence_module.queue(PersistenceJob::RevokeAdminRole {
+                    admin_id: admin.id,
+                    grant: grant.clone(),
+                }) {
+                    fail(format!("Could not queue admin role revocation {:?}", err));
+                }
+            }
+            send_communication_event(CommunicationEvent::Toast(
+                ToastAlertLevel::Success,
+                format!("Revoked {:?} from {}.", grant.role, grant.provider_user_id),
+            ));
+        }
+        AdminToSystemEvent::QueryAuditLog(query) => {
+            if let Err(err) = persistence_module.queue(PersistenceJob::QueryAuditLog {
+                admin_id: admin.id,
+                query,
+            }) {
+                fail(format!("Could not queue audit log query {:?}", err));
+            }
+        }
+        AdminToSystemEvent::QueryRoundTripTimes => {
+            send_editor_event(EditorEvent::RoundTripTimes(
+                guests
+                    .values()
+                    .filter_map(|guest| {
+                        Some((
+                            guest.login_data.as_ref()?.provider_user_id.clone(),
+                            guest.round_trip_time?,
+                        ))
+                    })
+                    .collect(),
+            ));
+        }
+        AdminToSystemEvent::KickFromModule(provider_user_id) => {
+            moderation_actions.push(ModerationAction::KickFromModule(admin.id, provider_user_id));
+        }
+        AdminToSystemEvent::KickFromServer(provider_user_id, reason) => {
+            moderation_actions.push(ModerationAction::KickFromServer(
+                admin.id,
+                provider_user_id,
+                reason,
+            ));
+        }
+        AdminToSystemEvent::BanGuest(ban) => {
+            if let Err(err) = persistence_module.queue(PersistenceJob::BanGuest {
+                admin_id: admin.id,
+                ban: ban.clone(),
+            }) {
+                fail(format!("Could not queue guest ban {:?}", err));
+            }
+            moderation_actions.push(ModerationAction::KickFromServer(
+                admin.id,
+                ban.provider_user_id.clone(),
+                describe_ban(&ban),
+            ));
+            send_communication_event(CommunicationEvent::Toast(
+                ToastAlertLevel::Success,
+                format!("Banned {}.", ban.provider_user_id),
+            ));
+            guest_bans.ban(ban);
+        }
+        AdminToSystemEvent::UnbanGuest(provider_user_id) => {
+            if guest_bans.unban(&provider_user_id) {
+                if let Err(err) = persistence_module.queue(PersistenceJob::UnbanGuest {
+                    admin_id: admin.id,
+                    provider_user_id: provider_user_id.clone(),
+                }) {
+                    fail(format!("Could not queue guest unban {:?}", err));
+                }
+            }
+            send_communication_event(CommunicationEvent::Toast(
+                ToastAlertLevel::Success,
+                format!("Unbanned {}.", provider_user_id),
+            ));
+        }
+        AdminToSystemEvent::SetObserver(provider_user_id, is_observer) => {
+            moderation_actions.push(ModerationAction::SetObserver(
+                admin.id,
+                provider_user_id,
+                is_observer,
+            ));
         }
         AdminToSystemEvent::Ping => {}
         AdminToSystemEvent::UpdateConductor(conductor) => {
+            *lock_gates(&web_server_module.gates) = conductor.gates.clone();
             save_and_send_conductor_update(conductor, &mut send_editor_event);
         }
         AdminToSystemEvent::LoadEditorData => {
@@ -459,7 +644,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::UpdatedConductor(conductor));
                 }
                 Err(err) => {
-                    error!("Could not load conductor! {:?}", err);
+                    fail(format!("Could not load conductor! {:?}", err));
                 }
             }
             match Blueprint::get_all_modules() {
@@ -467,20 +652,10 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::Modules(modules));
                 }
                 Err(err) => {
-                    error!("Could not retrieve modules! {:?}", err);
+                    fail(format!("Could not retrieve modules! {:?}", err));
                 }
             }
-            send_editor_event(EditorEvent::ModuleInstances(
-                module_map
-                    .values()
-                    .map(|m| {
-                        (
-                            m.module_blueprint.id.clone(),
-                            m.game_instances.values().map(|g| g.id.clone()).collect(),
-                        )
-                    })
-                    .collect(),
-            ));
+            send_editor_event(EditorEvent::ModuleInstances(module_instances(module_map)));
         }
         AdminToSystemEvent::CreateTileset(module_id, tileset) => {
             match Blueprint::create_tileset(&tileset) {
@@ -489,7 +664,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::CreatedTileset(tileset));
                 }
                 Err(err) => {
-                    error!("Could not create tileset {:?}", err);
+                    fail(format!("Could not create tileset {:?}", err));
                 }
             }
         }
@@ -555,7 +730,7 @@ pub async fn handle_admin_to_system_event(
                                             ));
                                         }
                                         Err(err) => {
-                                            error!("Could not save module {:?}", err);
+                                            fail(format!("Could not save module {:?}", err));
                                         }
                                     }
                                 }
@@ -563,7 +738,7 @@ pub async fn handle_admin_to_system_event(
                         }
                         send_editor_event(EditorEvent::SetTileset(tileset));
                     }
-                    Err(err) => error!("Could not update tileset: {:?}", err),
+                    Err(err) => fail(format!("Could not update tileset: {:?}", err)),
                 }
             }
         }
@@ -571,13 +746,13 @@ pub async fn handle_admin_to_system_event(
             Ok(()) => {
                 send_editor_event(EditorEvent::SetTileset(tileset));
             }
-            Err(err) => error!("Could not update tileset: {:?}", err),
+            Err(err) => fail(format!("Could not update tileset: {:?}", err)),
         },
         AdminToSystemEvent::DeleteTileset(tileset) => match Blueprint::delete_tileset(&tileset) {
             Ok(()) => {
                 send_editor_event(EditorEvent::DeletedTileset(tileset));
             }
-            Err(err) => error!("Could not delete tileset: {:?}", err),
+            Err(err) => fail(format!("Could not delete tileset: {:?}", err)),
         },
         AdminToSystemEvent::CreateScene(module_id, scene) => {
             match Blueprint::create_scene(&scene) {
@@ -586,7 +761,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::CreatedScene(scene));
                 }
                 Err(err) => {
-                    error!("Could not create tileset {:?}", err);
+                    fail(format!("Could not create tileset {:?}", err));
                 }
             }
         }
@@ -609,10 +784,10 @@ pub async fn handle_admin_to_system_event(
                                     ),
                                 ));
                             }
-                            Err(err) => error!("Could not update scene: {:?}", err),
+                            Err(err) => fail(format!("Could not update scene: {:?}", err)),
                         }
                     }
-                    Err(err) => error!("Could not load scene to update it: {:?}", err),
+                    Err(err) => fail(format!("Could not load scene to update it: {:?}", err)),
                 }
             }
             SceneNodeUpdate::AddChild(resource_path, path, game_node_id, node) => {
@@ -630,10 +805,10 @@ pub async fn handle_admin_to_system_event(
                                     ),
                                 ));
                             }
-                            Err(err) => error!("Could not update scene: {:?}", err),
+                            Err(err) => fail(format!("Could not update scene: {:?}", err)),
                         }
                     }
-                    Err(err) => error!("Could not load scene to update it: {:?}", err),
+                    Err(err) => fail(format!("Could not load scene to update it: {:?}", err)),
                 }
             }
             SceneNodeUpdate::RemoveChild(resource_path, path, node) => {
@@ -649,9 +824,9 @@ pub async fn handle_admin_to_system_event(
                                 SceneNodeUpdate::RemoveChild(resource_path, path, node),
                             ));
                         }
-                        Err(err) => error!("Could not remove scene: {:?}", err),
+                        Err(err) => fail(format!("Could not remove scene: {:?}", err)),
                     },
-                    Err(err) => error!("Could not load scene to remove node: {:?}", err),
+                    Err(err) => fail(format!("Could not load scene to remove node: {:?}", err)),
                 }
             }
         },
@@ -659,7 +834,7 @@ pub async fn handle_admin_to_system_event(
             Ok(()) => {
                 send_editor_event(EditorEvent::DeletedScene(scene));
             }
-            Err(err) => error!("Could not delete scene: {:?}", err),
+            Err(err) => fail(format!("Could not delete scene: {:?}", err)),
         },
         AdminToSystemEvent::UpdateModule(module_id, module_update) => {
             debug!("Module update {:?} {:?}", module_map.keys(), module_id);
@@ -720,6 +895,12 @@ pub async fn handle_admin_to_system_event(
                     }
                     module.module_blueprint.exit_points = exit_points;
                 }
+                if let Some(achievements) = module_update.achievements {
+                    module.module_blueprint.achievements = achievements;
+                }
+                if let Some(leaderboards) = module_update.leaderboards {
+                    module.module_blueprint.leaderboards = leaderboards;
+                }
                 log_result_error(Blueprint::save_module(&module.module_blueprint));
                 send_editor_event(EditorEvent::UpdatedModule(
                     module_id,
@@ -736,6 +917,7 @@ pub async fn handle_admin_to_system_event(
                         resource_module,
                         resource_to_module_map,
                         module_communication_map,
+                        leaderboards,
                     ) {
                         if let Some(module) = module_map.get(&module_id) {
                             send_editor_event(EditorEvent::CreatedModule(
@@ -746,7 +928,7 @@ pub async fn handle_admin_to_system_event(
                     }
                 }
                 Err(err) => {
-                    debug!("Could not create module: {:?}", err);
+                    fail(format!("Could not create module: {:?}", err));
                 }
             }
         }
@@ -760,7 +942,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::CreatedCharacterAnimation(character_animation));
                 }
                 Err(err) => {
-                    error!("Could not create character animation {:?}", err);
+                    fail(format!("Could not create character animation {:?}", err));
                 }
             }
         }
@@ -780,7 +962,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::SetCharacterAnimation(character_animation));
                 }
                 Err(err) => {
-                    debug!("Could not save character animatino: {:?}", err);
+                    fail(format!("Could not save character animatino: {:?}", err));
                 }
             }
         }
@@ -790,7 +972,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::DeletedCharacterAnimation(character_animation));
                 }
                 Err(err) => {
-                    debug!("Could not delete character animatino: {:?}", err);
+                    fail(format!("Could not delete character animatino: {:?}", err));
                 }
             }
         }
@@ -801,7 +983,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::CreatedScript(script));
                 }
                 Err(err) => {
-                    error!("Could not create script {:?}", err);
+                    fail(format!("Could not create script {:?}", err));
                 }
             }
         }
@@ -825,7 +1007,7 @@ pub async fn handle_admin_to_system_event(
                         send_editor_event(EditorEvent::SetScript(script));
                     }
                     Err(err) => {
-                        debug!("Could not update script: {:?}", err);
+                        fail(format!("Could not update script: {:?}", err));
                     }
                 }
             }
@@ -847,7 +1029,7 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::DeletedScript(script));
                 }
                 Err(err) => {
-                    debug!("Could not delete script: {:?}", err);
+                    fail(format!("Could not delete script: {:?}", err));
                 }
             }
         }
@@ -871,10 +1053,10 @@ pub async fn handle_admin_to_system_event(
                     send_editor_event(EditorEvent::DeletedModule(module_id));
                 }
                 Err(err) => {
-                    error!(
+                    fail(format!(
                         "Something went wrong while deleting module {}: {:?}",
                         module_id, err
-                    );
+                    ));
                 }
             }
         }
@@ -912,14 +1094,26 @@ pub async fn handle_admin_to_system_event(
                         .dynamic_module
                         .add_entity(&world_id, parent_entity, game_node);
                 } else {
-                    error!(
+                    fail(format!(
                         "Could not find instance {:?} in module {:?}",
                         game_instance_id, module_id
-                    );
+                    ));
                 }
             } else {
-                error!("Could not find module {:?}", module_id);
+                fail(format!("Could not find module {:?}", module_id));
             }
         }
     }
+
+    let failures = failures.into_inner();
+    if !failures.is_empty() {
+        return Err(AdminEventError::Failed(failures.join(" ")));
+    }
+    if let Some(entry) = audit_log_entry {
+        if let Err(err) = persistence_module.queue(PersistenceJob::AppendAuditLog(entry)) {
+            error!("Could not queue audit log entry {:?}", err);
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::conductor_module::admin_permissions::{required_permission, PermissionScope};
+use crate::conductor_module::def::ResourceToModuleMap;
+use crate::core::audit_log::{json_diff, NewAuditLogEntry};
+use crate::core::blueprint::def::{BlueprintService, JsonResource};
+use crate::core::blueprint::resource_loader::Blueprint;
+use crate::core::guest::ProviderUserId;
+use crate::core::module::{AdminToSystemEvent, SceneNodeUpdate};
+
+/// Has to run before the event is applied, full replacements are diffed against what is
+/// currently stored.
+pub fn audit_log_entry(
+    provider_user_id: &ProviderUserId,
+    event: &AdminToSystemEvent,
+    resource_to_module_map: &ResourceToModuleMap,
+) -> Option<NewAuditLogEntry> {
+    if !is_audited(event) {
+        return None;
+    }
+
+    let (action, payload) = match serde_json::to_value(event) {
+        Ok(JsonValue::Object(variant)) => variant.into_iter().next()?,
+        Ok(JsonValue::String(action)) => (action, JsonValue::Null),
+        Ok(_) => return None,
+        Err(err) => {
+            error!(
+                "Could not serialize {:?} for the audit log {:?}",
+                event, err
+            );
+            return None;
+        }
+    };
+    let module_ids = match (event, required_permission(event, resource_to_module_map).1) {
+        (AdminToSystemEvent::DeleteModule(module_id), _) => vec![module_id.clone()],
+        (_, PermissionScope::Modules(module_ids) | PermissionScope::AnyOfModules(module_ids)) => {
+            module_ids
+        }
+        (_, PermissionScope::Any | PermissionScope::Global) => Vec::new(),
+    };
+
+    Some(NewAuditLogEntry {
+        provider_user_id: provider_user_id.clone(),
+        action,
+        module_ids,
+        resource: resource_of(event),
+        diff: diff_of(event, payload),
+    })
+}
+
+fn is_audited(event: &AdminToSystemEvent) -> bool {
+    match event {
+        AdminToSystemEvent::Ping
+        | AdminToSystemEvent::ProviderLoggedIn(_)
+        | AdminToSystemEvent::LoadEditorData
+        | AdminToSystemEvent::BrowseFolder(_)
+        | AdminToSystemEvent::GetResource(_)
+        | AdminToSystemEvent::OpenInstance(_)
+        | AdminToSystemEvent::StartInspectingWorld(_, _, _)
+        | AdminToSystemEvent::StopInspectingWorld(_, _, _)
+        | AdminToSystemEvent::WorldInitialized(_, _, _)
+        | AdminToSystemEvent::ControlInput(_, _, _)
+        | AdminToSystemEvent::QueryAuditLog(_)
+        | AdminToSystemEvent::QueryRoundTripTimes => false,
+        AdminToSystemEvent::UpdateConductor(_)
+        | AdminToSystemEvent::UpdateModule(_, _)
+        | AdminToSystemEvent::CreateModule(_)
+        | AdminToSystemEvent::DeleteModule(_)
+        | AdminToSystemEvent::CreateTileset(_, _)
+        | AdminToSystemEvent::SetTileset(_)
+        | AdminToSystemEvent::UpdateTileset(_, _)
+        | AdminToSystemEvent::DeleteTileset(_)
+        | AdminToSystemEvent::CreateScene(_, _)
+        | AdminToSystemEvent::UpdateSceneNode(_)
+        | AdminToSystemEvent::OverwriteSceneRoot(_, _)
+        | AdminToSystemEvent::DeleteScene(_)
+        | AdminToSystemEvent::UpdateInstancedNode(_, _, _, _)
+        | AdminToSystemEvent::ResetGameWorld(_, _, _)
+        | AdminToSystemEvent::RemoveInstanceNode(_, _, _, _)
+        | AdminToSystemEvent::AddNodeToInstanceNode(_, _, _, _, _)
+        | AdminToSystemEvent::CreateMap(_, _)
+        | AdminToSystemEvent::UpdateMap(_)
+        | AdminToSystemEvent::DeleteMap(_, _)
+        | AdminToSystemEvent::CreateScript(_, _)
+        | AdminToSystemEvent::UpdateScript(_)
+        | AdminToSystemEvent::DeleteScript(_)
+        | AdminToSystemEvent::CreateCharacterAnimation(_, _)
+        | AdminToSystemEvent::UpdateCharacterAnimation(_)
+        | AdminToSystemEvent::DeleteCharacterAnimation(_)
+        | AdminToSystemEvent::SetGateOpen(_, _)
+        | AdminToSystemEvent::GrantAdminRole(_)
+        | AdminToSystemEvent::RevokeAdminRole(_)
+        | AdminToSystemEvent::KickFromModule(_)
+        | AdminToSystemEvent::KickFromServer(_, _)
+        | AdminToSystemEvent::BanGuest(_)
+        | AdminToSystemEvent::UnbanGuest(_)
+        | AdminToSystemEvent::SetObserver(_, _) => true,
+    }
+}
+
+fn resource_of(event: &AdminToSystemEvent) -> Option<String> {
+    match event {
+        AdminToSystemEvent::CreateModule(module_name) => Some(module_name.clone()),
+        AdminToSystemEvent::CreateTileset(_, tileset)
+        | AdminToSystemEvent::SetTileset(tileset)
+        | AdminToSystemEvent::DeleteTileset(tileset) => Some(tileset.get_full_resource_path()),
+        AdminToSystemEvent::CreateScene(_, scene) | AdminToSystemEvent::DeleteScene(scene) => {
+            Some(scene.get_full_resource_path())
+        }
+        AdminToSystemEvent::UpdateTileset(resource_path, _)
+        | AdminToSystemEvent::OverwriteSceneRoot(resource_path, _)
+        | AdminToSystemEvent::UpdateSceneNode(
+            SceneNodeUpdate::UpdateData(resource_path, _, _, _)
+            | SceneNodeUpdate::AddChild(resource_path, _, _, _)
+            | SceneNodeUpdate::RemoveChild(resource_path, _, _),
+        ) => Some(resource_path.clone()),
+        AdminToSystemEvent::CreateMap(_, map) | AdminToSystemEvent::DeleteMap(_, map) => {
+            Some(map.get_full_resource_path())
+        }
+        AdminToSystemEvent::UpdateMap(map_update) => Some(map_update.get_full_resource_path()),
+        AdminToSystemEvent::CreateScript(_, script)
+        | AdminToSystemEvent::UpdateScript(script)
+        | AdminToSystemEvent::DeleteScript(script) => Some(script.get_full_resource_path()),
+        AdminToSystemEvent::CreateCharacterAnimation(_, character_animation)
+        | AdminToSystemEvent::UpdateCharacterAnimation(character_animation)
+        | AdminToSystemEvent::DeleteCharacterAnimation(character_animation) => {
+            Some(character_animation.get_full_resource_path())
+        }
+        AdminToSystemEvent::GrantAdminRole(grant) | AdminToSystemEvent::RevokeAdminRole(grant) => {
+            Some(grant.provider_user_id.clone())
+        }
+        AdminToSystemEvent::BanGuest(ban) => Some(ban.provider_user_id.clone()),
+        AdminToSystemEvent::KickFromModule(provider_user_id)
+        | AdminToSystemEvent::KickFromServer(provider_user_id, _)
+        | AdminToSystemEvent::UnbanGuest(provider_user_id)
+        | AdminToSystemEvent::SetObserver(provider_user_id, _) => Some(provider_user_id.clone()),
+        _ => None,
+    }
+}
+
+/// Events that replace a whole resource are diffed against the stored version, deletions
+/// only keep the resource path and everything else already is a delta.
+fn diff_of(event: &AdminToSystemEvent, payload: JsonValue) -> JsonValue {
+    match event {
+        AdminToSystemEvent::UpdateConductor(conductor) => {
+            diff_against(BlueprintService::load_conductor_blueprint().ok(), conductor)
+        }
+        AdminToSystemEvent::SetTileset(tileset) => diff_against(
+            Blueprint::load_tileset(PathBuf::from(tileset.get_full_resource_path())).ok(),
+            tileset,
+        ),
+        AdminToSystemEvent::UpdateScript(script) => diff_against(
+            Blueprint::load_script(PathBuf::from(script.get_full_resource_path())).ok(),
+            script,
+        ),
+        AdminToSystemEvent::UpdateCharacterAnimation(character_animation) => diff_against(
+            Blueprint::load_character_animation(PathBuf::from(
+                character_animation.get_full_resource_path(),
+            ))
+            .ok(),
+            character_animation,
+        ),
+        AdminToSystemEvent::OverwriteSceneRoot(resource_path, root_node) => diff_against(
+            Blueprint::load_scene(PathBuf::from(resource_path))
+                .ok()
+                .map(|scene| scene.root_node),
+            root_node,
+        ),
+        AdminToSystemEvent::DeleteModule(_)
+        | AdminToSystemEvent::DeleteTileset(_)
+        | AdminToSystemEvent::DeleteScene(_)
+        | AdminToSystemEvent::DeleteMap(_, _)
+        | AdminToSystemEvent::DeleteScript(_)
+        | AdminToSystemEvent::DeleteCharacterAnimation(_) => JsonValue::Null,
+        _ => payload,
+    }
+}
+
+fn diff_against<T: Serialize>(previous: Option<T>, current: &T) -> JsonValue {
+    let to_json = |value: &T| serde_json::to_value(value).unwrap_or(JsonValue::Null);
+    json_diff(
+        &previous.as_ref().map_or(JsonValue::Null, to_json),
+        &to_json(current),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use serde_json::json;
+
+    use crate::core::module::TilesetUpdate;
+
+    use super::*;
+
+    #[test]
+    fn test_only_mutating_events_are_recorded() {
+        let resource_to_module_map: ResourceToModuleMap = HashMap::from([(
+            "castle/tower.tileset.json".to_string(),
+            HashSet::from(["castle".to_string()]),
+        )]);
+        let admin = "builder".to_string();
+
+        let entry = audit_log_entry(
+            &admin,
+            &AdminToSystemEvent::UpdateTileset(
+                "castle/tower.tileset.json".into(),
+                TilesetUpdate::RemoveTile(3),
+            ),
+            &resource_to_module_map,
+        )
+        .unwrap();
+        assert_eq!(entry.action, "UpdateTileset");
+        assert_eq!(entry.module_ids, vec!["castle"]);
+        assert_eq!(entry.resource.as_deref(), Some("castle/tower.tileset.json"));
+        assert_eq!(
+            entry.diff,
+            json!(["castle/tower.tileset.json", {"RemoveTile": 3}])
+        );
+
+        let entry = audit_log_entry(
+            &admin,
+            &AdminToSystemEvent::DeleteModule("castle".into()),
+            &resource_to_module_map,
+        )
+        .unwrap();
+        assert_eq!(entry.action, "DeleteModule");
+        assert_eq!(entry.module_ids, vec!["castle"]);
+        assert_eq!(entry.diff, JsonValue::Null);
+
+        assert!(audit_log_entry(
+            &admin,
+            &AdminToSystemEvent::GetResource("castle/tower.tileset.json".into()),
+            &resource_to_module_map
+        )
+        .is_none());
+    }
+}
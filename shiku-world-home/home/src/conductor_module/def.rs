@@ -1,16 +1,23 @@
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
+use flume::{Receiver, Sender};
 use snowflake::SnowflakeIdBucket;
 
+use crate::conductor_module::admin_permissions::AdminPermissions;
+use crate::conductor_module::moderation::{GuestBans, ModerationAction};
+use crate::conductor_module::session_resume::MissedEvents;
 use crate::core::blueprint::def::{BlueprintService, ModuleId, ResourcePath};
 use crate::core::guest::ActorId;
 use crate::core::guest::{Admin, Guest, ModuleEnterSlot, ModuleExitSlot, ProviderUserId};
-use crate::core::module::{ModuleIO, SystemCommunicationIO};
+use crate::core::leaderboard::LeaderboardMap;
+use crate::core::module::{CommunicationEvent, ModuleIO, SystemCommunicationIO};
 use crate::core::module_system::game_instance::GameInstanceManager;
 use crate::core::{blueprint, Snowflake};
 use crate::login::login_manager::LoginManager;
+use crate::login::session_token::SessionTokenManager;
 use crate::persistence_module::PersistenceModule;
+use crate::webserver_module::admin_api::AdminApiRequest;
 use crate::webserver_module::def::WebServerModule;
 use crate::{ResourceModule, WebsocketModule};
 
@@ -25,7 +32,13 @@ pub struct ConductorModule {
     pub(super) resource_module: ResourceModule,
     pub(super) persistence_module: PersistenceModule,
     pub(super) web_server_module: WebServerModule,
+    pub(super) admin_api_receiver: Receiver<AdminApiRequest>,
     pub(super) login_manager: LoginManager,
+    pub(super) admin_permissions: AdminPermissions,
+    pub(super) guest_bans: GuestBans,
+    pub(super) moderation_actions: Vec<ModerationAction>,
+    pub(super) session_tokens: SessionTokenManager,
+    pub(super) leaderboards: LeaderboardMap,
     pub(super) module_map: ModuleMap,
     pub(crate) resource_to_module_map: ResourceToModuleMap,
     pub(super) module_connection_map: HashMap<ModuleExitSlot, (ModuleId, ModuleEnterSlot)>,
@@ -40,9 +53,42 @@ pub struct ConductorModule {
     pub(super) session_id_to_guest_map: HashMap<String, Snowflake>,
     pub(super) session_id_to_admin_map: HashMap<String, Snowflake>,
     pub(super) guest_timeout_map: HashMap<ActorId, Instant>,
+    pub(super) missed_events: HashMap<ActorId, MissedEvents>,
     pub(super) timeouts: Vec<ActorId>,
+    pub(super) status_refreshed_at: Instant,
 
     pub(super) snowflake_gen: SnowflakeIdBucket,
     pub(super) system_to_guest_communication: SystemCommunicationIO,
     pub(super) system_to_admin_communication: SystemCommunicationIO,
 }
+
+/// What an admin event may touch, borrowed from the `ConductorModule` for one event.
+pub struct AdminEventContext<'a> {
+    pub module_communication_map: &'a mut ModuleCommunicationMap,
+    pub web_server_module: &'a mut WebServerModule,
+    pub resource_module: &'a mut ResourceModule,
+    pub module_map: &'a mut ModuleMap,
+    pub resource_to_module_map: &'a mut ResourceToModuleMap,
+    pub leaderboards: &'a LeaderboardMap,
+    pub admin_permissions: &'a mut AdminPermissions,
+    pub guest_bans: &'a mut GuestBans,
+    pub moderation_actions: &'a mut Vec<ModerationAction>,
+    pub guests: &'a HashMap<ActorId, Guest>,
+    pub persistence_module: &'a PersistenceModule,
+    pub system_to_admin_communication_sender: &'a mut Sender<(ActorId, CommunicationEvent)>,
+}
+
+/// The lookups kept per kind of actor, guests and admins each have their own.
+pub struct ActorMaps<'a, T> {
+    pub actors: &'a mut HashMap<Snowflake, T>,
+    pub provider_id_to_actor_map: &'a mut HashMap<ProviderUserId, Snowflake>,
+    pub ws_to_actor_map: &'a mut HashMap<Snowflake, Snowflake>,
+    pub session_to_actor_map: &'a mut HashMap<String, Snowflake>,
+}
+
+/// Shared by guest and admin logins.
+pub struct LoginContext<'a> {
+    pub websocket_module: &'a mut WebsocketModule,
+    pub session_tokens: &'a mut SessionTokenManager,
+    pub guest_bans: &'a GuestBans,
+}
@@ -25,6 +25,18 @@ pub enum HandleLoginError {
     NotAuthorized(ActorId),
     #[error("Could not find guest/admin")]
     CouldNotFind(ActorId),
+    #[error("Someone tried to login that is banned.")]
+    Banned(ActorId, String),
+    #[error("Someone tried to login through a closed gate.")]
+    GateClosed(ActorId, String),
+}
+
+#[derive(Error, Debug)]
+pub enum AdminEventError {
+    #[error("{0}")]
+    NotAllowed(String),
+    #[error("{0}")]
+    Failed(String),
 }
 
 #[derive(Debug)]
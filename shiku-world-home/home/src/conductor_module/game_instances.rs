@@ -3,6 +3,7 @@ use log::{debug, error};
 use crate::conductor_module::def::{ModuleCommunicationMap, ModuleMap, ResourceToModuleMap};
 use crate::core::blueprint::def::{BlueprintError, Module, ModuleId};
 use crate::core::blueprint::resource_loader::Blueprint;
+use crate::core::leaderboard::LeaderboardMap;
 use crate::core::module::ModuleIO;
 use crate::core::module_system::game_instance::GameInstanceManager;
 use crate::resource_module::def::ResourceModule;
@@ -13,8 +14,9 @@ pub fn create_game_instance_manager(
     resource_module: &mut ResourceModule,
     resource_to_module_map: &mut ResourceToModuleMap,
     module_communication_map: &mut ModuleCommunicationMap,
+    leaderboards: &LeaderboardMap,
 ) -> Option<ModuleId> {
-    match GameInstanceManager::new(module_blueprint, resource_module) {
+    match GameInstanceManager::new(module_blueprint, resource_module, leaderboards.clone()) {
         Ok((game_instance_manager, module_input_sender, module_output_receiver)) => {
             let module_id = game_instance_manager.module_blueprint.id.clone();
             for resource in &game_instance_manager.module_blueprint.resources {
@@ -1,6 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
-use std::time::Instant;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use flume::{unbounded, Sender};
@@ -9,38 +12,61 @@ use rhai::Module;
 use snowflake::SnowflakeIdBucket;
 use tungstenite::protocol::frame::coding::CloseCode;
 
+use crate::conductor_module::admin_permissions::AdminPermissions;
 use crate::conductor_module::admin_to_system_events::handle_admin_to_system_event;
-use crate::conductor_module::def::{ConductorModule, ResourceToModuleMap};
+use crate::conductor_module::def::{
+    ActorMaps, AdminEventContext, ConductorModule, LoginContext, ResourceToModuleMap,
+};
 use crate::conductor_module::errors::{
-    HandleLoginError, ProcessGameEventError, ProcessModuleEventError, SendEventToModuleError,
+    AdminEventError, HandleLoginError, ProcessGameEventError, ProcessModuleEventError,
+    SendEventToModuleError,
 };
 use crate::conductor_module::game_instances::create_game_instance_manager;
+use crate::conductor_module::moderation::{describe_ban, GuestBans, ModerationAction};
+use crate::conductor_module::session_resume::{MissedEvents, SessionResume};
 use crate::core::blueprint::def::{
-    BlueprintResource, BlueprintService, CharAnimationToTilesetMap, GidMap, LayerKind, ModuleId,
-    ResourceKind, ResourcePath, TerrainParams, Tileset,
+    BlueprintResource, BlueprintService, CharAnimationToTilesetMap, GidMap, LayerKind,
+    LeaderboardId, ModuleId, ResourceKind, ResourcePath, TerrainParams, Tileset,
 };
 use crate::core::blueprint::resource_loader::Blueprint;
 use crate::core::guest::{
-    ActorId, Actors, Admin, Guest, LoginData, ModuleEnterSlot, ProviderUserId,
+    ActorId, Actors, Admin, Guest, GuestBan, LoginData, LoginProvider, ModuleEnterSlot,
+    ProviderUserId,
+};
+use crate::core::leaderboard::{
+    lock_leaderboards, period_start, Leaderboard, LeaderboardMap, LeaderboardSnapshot,
 };
+use crate::core::metrics::{Histogram, SERVER_METRICS, TICK_DURATION_BUCKETS};
+use crate::core::health::SERVER_HEALTH;
+use crate::core::medium_data_storage::{MediumDataStorage, MediumDataStorageGuestInfo};
 use crate::core::module::{
     AdminToSystemEvent, CommunicationEvent, EditorEvent, EnterFailedState, EnterSuccessState,
-    GamePosition, GameSystemToGuest, GuestEvent, GuestStateChange, GuestTo, GuestToModule,
-    GuestToModuleEvent, GuestToSystemEvent, LeaveFailedState, LeaveSuccessState, ModuleIO,
-    ModuleInstanceEvent, ModuleName, ModuleState, ModuleToSystem, ModuleToSystemEvent,
+    GamePosition, GameSystemToGuest, GameSystemToGuestEvent, GuestEvent, GuestStateChange, GuestTo,
+    GuestToModule, GuestToModuleEvent, GuestToSystemEvent, LeaveFailedState, LeaveSuccessState,
+    ModuleIO, ModuleInstanceEvent, ModuleName, ModuleState, ModuleToSystem, ModuleToSystemEvent,
     SignalToMedium, SystemCommunicationIO, SystemToModule, SystemToModuleEvent, ToastAlertLevel,
 };
 use crate::core::module_system::game_instance::{GameInstanceId, GameInstanceManager};
 use crate::core::module_system::world::WorldId;
+use crate::core::server_gate::{gate_refusal, lock_gates};
+use crate::core::time_sync::{RoundTripTimer, TimeSyncPing, TimeSyncPong};
 use crate::core::{blueprint, send_and_log_error, send_and_log_error_custom};
-use crate::core::{safe_unwrap, Snowflake, LOGGED_IN_TODAY_DELAY_IN_HOURS};
+use crate::core::{
+    safe_unwrap, Snowflake, DATA_STORE_LEADERBOARD_SIZE, LOGGED_IN_TODAY_DELAY_IN_HOURS,
+};
 use crate::login::login_manager::{LoginError, LoginManager};
+use crate::login::session_token::SessionTokenManager;
 use crate::persistence_module::models::{PersistedGuest, UpdatePersistedGuestState};
+use crate::persistence_module::worker::{PersistenceJob, PersistenceResult};
 use crate::persistence_module::{PersistenceError, PersistenceModule};
 use crate::resource_module::def::ResourceBundle;
+use crate::webserver_module::admin_api::AdminApiResponse;
 use crate::webserver_module::def::WebServerModule;
+use crate::websocket_module::{decode_message, ProtocolInfo};
 use crate::{ResourceModule, SystemModule, WebsocketModule};
 
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
 impl SystemModule for ConductorModule {
     fn start(&mut self) {
         todo!()
@@ -59,12 +85,17 @@ impl ConductorModule {
         self.send_system_events_to_guests();
         self.send_system_events_to_admins();
         self.process_logins();
+        self.process_persistence_results();
         self.send_load_events();
         self.process_picture_update_events();
 
         self.handle_timeouts();
 
         self.handle_admin_events().await;
+        self.handle_admin_api_requests().await;
+        self.process_moderation_actions();
+        self.refresh_server_status();
+        SERVER_HEALTH.mark_conductor_tick();
     }
 
     pub fn update_resource_to_module_map(
@@ -91,10 +122,6 @@ impl ConductorModule {
         }
     }
 
-    fn check_admin_login(login_data: &LoginData) -> bool {
-        login_data.provider_user_id == "52657886"
-    }
-
     async fn handle_admin_events(&mut self) {
         for admin in &mut self.admins.values() {
             if let Some(ws_connection_id) = admin.ws_connection_id {
@@ -115,17 +142,27 @@ impl ConductorModule {
                                 }
                                 continue;
                             }
+                            // A rejected event was already answered with a toast, failures are logged.
                             handle_admin_to_system_event(
-                                &mut self.module_communication_map,
-                                &mut self.web_server_module,
-                                &mut self.resource_module,
-                                &mut self.module_map,
-                                &mut self.resource_to_module_map,
-                                &mut self.system_to_admin_communication.sender,
+                                AdminEventContext {
+                                    module_communication_map: &mut self.module_communication_map,
+                                    web_server_module: &mut self.web_server_module,
+                                    resource_module: &mut self.resource_module,
+                                    module_map: &mut self.module_map,
+                                    resource_to_module_map: &mut self.resource_to_module_map,
+                                    leaderboards: &self.leaderboards,
+                                    admin_permissions: &mut self.admin_permissions,
+                                    guest_bans: &mut self.guest_bans,
+                                    moderation_actions: &mut self.moderation_actions,
+                                    guests: &self.guests,
+                                    persistence_module: &self.persistence_module,
+                                    system_to_admin_communication_sender: &mut self.system_to_admin_communication.sender,
+                                },
                                 admin,
                                 event,
                             )
-                            .await;
+                            .await
+                            .ok();
                         }
                         Err(err) => error!("Failed to parse admin event! {:?}", err),
                     }
@@ -134,6 +171,55 @@ impl ConductorModule {
         }
     }
 
+    /// Http admins have no session, each request acts as a short lived admin of the configured
+    /// provider user. Whatever the handler sends to it right away becomes the response.
+    async fn handle_admin_api_requests(&mut self) {
+        for request in self.admin_api_receiver.drain() {
+            let admin = Admin {
+                id: self.snowflake_gen.get_id(),
+                session_id: String::new(),
+                login_data: Some(LoginData {
+                    provider_user_id: request.provider_user_id.clone(),
+                    display_name: request.provider_user_id,
+                    views: None,
+                    provider: LoginProvider::Local,
+                }),
+                is_logged_in: true,
+                ws_connection_id: None,
+            };
+            let (mut sender, receiver) = unbounded();
+            let response = match handle_admin_to_system_event(
+                AdminEventContext {
+                    module_communication_map: &mut self.module_communication_map,
+                    web_server_module: &mut self.web_server_module,
+                    resource_module: &mut self.resource_module,
+                    module_map: &mut self.module_map,
+                    resource_to_module_map: &mut self.resource_to_module_map,
+                    leaderboards: &self.leaderboards,
+                    admin_permissions: &mut self.admin_permissions,
+                    guest_bans: &mut self.guest_bans,
+                    moderation_actions: &mut self.moderation_actions,
+                    guests: &self.guests,
+                    persistence_module: &self.persistence_module,
+                    system_to_admin_communication_sender: &mut sender,
+                },
+                &admin,
+                request.event,
+            )
+            .await
+            {
+                Ok(()) => AdminApiResponse::Handled(
+                    receiver.drain().map(|(_, event)| event).collect(),
+                ),
+                Err(AdminEventError::NotAllowed(reason)) => AdminApiResponse::NotAllowed(reason),
+                Err(AdminEventError::Failed(reason)) => AdminApiResponse::Failed(reason),
+            };
+            if let Err(err) = request.responder.send(response) {
+                debug!("Admin api request was abandoned {:?}", err);
+            }
+        }
+    }
+
     fn handle_timeouts(&mut self) {
         for (guest_id, connection_lost_time) in &self.guest_timeout_map {
             if connection_lost_time.elapsed().as_secs() > 30 {
@@ -154,16 +240,215 @@ impl ConductorModule {
                         .remove(&login_data.provider_user_id);
                 }
                 self.guest_timeout_map.remove(&guest_id);
+                self.missed_events.remove(&guest_id);
                 self.session_id_to_guest_map.remove(&guest.session_id);
+                self.session_tokens.revoke(&guest.session_id);
             }
             debug!("Guest removed {}.", guest_id);
         }
     }
 
+    fn process_moderation_actions(&mut self) {
+        for action in self.moderation_actions.drain(..) {
+            let (admin_id, provider_user_id) = match &action {
+                ModerationAction::KickFromModule(admin_id, provider_user_id)
+                | ModerationAction::KickFromServer(admin_id, provider_user_id, _)
+                | ModerationAction::SetObserver(admin_id, provider_user_id, _) => {
+                    (*admin_id, provider_user_id)
+                }
+            };
+            let Some(guest) = self
+                .provider_id_to_guest_map
+                .get(provider_user_id)
+                .and_then(|guest_id| self.guests.get_mut(guest_id))
+            else {
+                if !matches!(action, ModerationAction::KickFromServer(..)) {
+                    send_and_log_error(
+                        &mut self.system_to_admin_communication.sender,
+                        (
+                            admin_id,
+                            CommunicationEvent::Toast(
+                                ToastAlertLevel::Error,
+                                format!("{} is not online.", provider_user_id),
+                            ),
+                        ),
+                    );
+                }
+                continue;
+            };
+            let guest_id = guest.id;
+            let (guest_message, admin_message) = match &action {
+                ModerationAction::KickFromModule(_, _) => {
+                    let Some(module) = guest
+                        .current_module_id
+                        .as_ref()
+                        .and_then(|module_id| self.module_map.get_mut(module_id))
+                    else {
+                        send_and_log_error(
+                            &mut self.system_to_admin_communication.sender,
+                            (
+                                admin_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    format!("{} is not in a module.", provider_user_id),
+                                ),
+                            ),
+                        );
+                        continue;
+                    };
+                    let module_name = module.module_blueprint.name.clone();
+                    Self::try_leave_module(guest, module, &mut self.resource_module);
+                    Self::send_guest_out_of_login(guest);
+                    (
+                        format!("You were kicked from {}.", module_name),
+                        format!("Kicked {} from {}.", provider_user_id, module_name),
+                    )
+                }
+                ModerationAction::KickFromServer(_, _, reason) => {
+                    if let Some(ws_connection_id) = guest.ws_connection_id.take() {
+                        self.ws_to_guest_map.remove(&ws_connection_id);
+                        if let Err(err) = self.websocket_module.send_event(
+                            &ws_connection_id,
+                            &CommunicationEvent::Toast(ToastAlertLevel::Error, reason.clone()),
+                        ) {
+                            error!("Could not send kick reason {:?}", err);
+                        }
+                        self.websocket_module.close_connection(
+                            &ws_connection_id,
+                            CloseCode::Policy,
+                            "Kicked".into(),
+                        );
+                    }
+                    self.timeouts.push(guest_id);
+                    send_and_log_error(
+                        &mut self.system_to_admin_communication.sender,
+                        (
+                            admin_id,
+                            CommunicationEvent::Toast(
+                                ToastAlertLevel::Success,
+                                format!("Kicked {} from the server.", provider_user_id),
+                            ),
+                        ),
+                    );
+                    continue;
+                }
+                ModerationAction::SetObserver(_, _, is_observer) => {
+                    if let Some(persisted_guest) = &mut guest.persisted_guest {
+                        persisted_guest.info.is_observer = *is_observer;
+                        if let Err(err) =
+                            self.persistence_module
+                                .queue(PersistenceJob::UpdateGuestState(
+                                    UpdatePersistedGuestState {
+                                        id: persisted_guest.info.id,
+                                        is_observer: Some(*is_observer),
+                                        is_tester: None,
+                                        last_time_joined: None,
+                                        times_joined: None,
+                                    },
+                                ))
+                        {
+                            error!("Could not queue observer change {:?}", err);
+                        }
+                    }
+                    if let Some(module) = guest
+                        .current_module_id
+                        .as_ref()
+                        .and_then(|module_id| self.module_map.get_mut(module_id))
+                    {
+                        module.set_observer(&guest_id, *is_observer);
+                    }
+                    if *is_observer {
+                        (
+                            "You can only watch for now.".to_string(),
+                            format!("{} is an observer now.", provider_user_id),
+                        )
+                    } else {
+                        (
+                            "You can play again.".to_string(),
+                            format!("{} is no longer an observer.", provider_user_id),
+                        )
+                    }
+                }
+            };
+            if let Err(err) = Self::send_communication_event_to_guest(
+                &mut self.guests,
+                &mut self.websocket_module,
+                guest_id,
+                &CommunicationEvent::Toast(ToastAlertLevel::Info, guest_message),
+            ) {
+                error!("Could not tell guest about moderation {:?}", err);
+            }
+            send_and_log_error(
+                &mut self.system_to_admin_communication.sender,
+                (
+                    admin_id,
+                    CommunicationEvent::Toast(ToastAlertLevel::Success, admin_message),
+                ),
+            );
+        }
+    }
+
     pub fn update_modules(&mut self) {
-        for instance_manager in self.module_map.values_mut() {
+        let mut tick_durations = Vec::with_capacity(self.module_map.len());
+        for (module_id, instance_manager) in self.module_map.iter_mut() {
+            let started_at = Instant::now();
             instance_manager.update();
+            tick_durations.push((module_id, started_at.elapsed()));
+        }
+
+        let mut module_metrics = SERVER_METRICS.lock_modules();
+        for (module_id, tick_duration) in tick_durations {
+            module_metrics
+                .tick_durations
+                .entry(module_id.clone())
+                .or_insert_with(|| Histogram::new(&TICK_DURATION_BUCKETS))
+                .observe(tick_duration);
+        }
+    }
+
+    fn refresh_server_status(&mut self) {
+        if self.status_refreshed_at.elapsed() < STATUS_REFRESH_INTERVAL {
+            return;
+        }
+        self.status_refreshed_at = Instant::now();
+        SERVER_HEALTH.set_login_provider_tokens(self.login_manager.token_statuses());
+
+        let connected_guests = self
+            .guests
+            .values()
+            .filter(|guest| guest.ws_connection_id.is_some())
+            .count();
+        let connected_admins = self
+            .admins
+            .values()
+            .filter(|admin| admin.ws_connection_id.is_some())
+            .count();
+        SERVER_METRICS
+            .connected_guests
+            .store(connected_guests as u64, Ordering::Relaxed);
+        SERVER_METRICS
+            .connected_admins
+            .store(connected_admins as u64, Ordering::Relaxed);
+
+        let mut module_metrics = SERVER_METRICS.lock_modules();
+        module_metrics.instances.clear();
+        module_metrics.entities.clear();
+        for (module_id, instance_manager) in &self.module_map {
+            module_metrics
+                .instances
+                .insert(module_id.clone(), instance_manager.game_instances.len());
+            for (instance_id, game_instance) in &instance_manager.game_instances {
+                for (world_id, world) in &game_instance.dynamic_module.world_map {
+                    module_metrics.entities.insert(
+                        (module_id.clone(), instance_id.clone(), world_id.clone()),
+                        world.ecs.entities.len(),
+                    );
+                }
+            }
         }
+        module_metrics
+            .tick_durations
+            .retain(|module_id, _| self.module_map.contains_key(module_id));
     }
 
     pub async fn new(
@@ -181,6 +466,20 @@ impl ConductorModule {
         let system_to_admin_communication = SystemCommunicationIO { receiver, sender };
 
         let modules = Blueprint::get_all_modules().unwrap();
+        let persistence_module = PersistenceModule::new();
+        let leaderboards = Self::load_leaderboards(&persistence_module, &modules);
+        let admin_permissions =
+            AdminPermissions::from_env(persistence_module.get_admin_role_grants().unwrap_or_else(
+                |err| {
+                    error!("Could not load admin role grants: {:?}", err);
+                    Vec::new()
+                },
+            ));
+        let guest_bans =
+            GuestBans::new(persistence_module.get_guest_bans().unwrap_or_else(|err| {
+                error!("Could not load guest bans: {:?}", err);
+                Vec::new()
+            }));
         let mut resource_to_module_map = HashMap::new();
         for module in modules {
             create_game_instance_manager(
@@ -189,20 +488,34 @@ impl ConductorModule {
                 &mut resource_module,
                 &mut resource_to_module_map,
                 &mut module_communication_map,
+                &leaderboards,
             )
             .unwrap();
         }
 
         let conductor = BlueprintService::load_conductor_blueprint().unwrap();
+        let (admin_api_sender, admin_api_receiver) = unbounded();
+        let web_server_module = WebServerModule::new(
+            leaderboards.clone(),
+            persistence_module.backend(),
+            admin_api_sender,
+            Arc::new(Mutex::new(conductor.gates)),
+        );
 
         ConductorModule {
             blueprint,
             blueprint_service,
             websocket_module,
             resource_module,
-            persistence_module: PersistenceModule::new(),
-            web_server_module: WebServerModule::new(),
+            persistence_module,
+            web_server_module,
+            admin_api_receiver,
+            leaderboards,
             login_manager: LoginManager::new(),
+            admin_permissions,
+            guest_bans,
+            moderation_actions: Vec::new(),
+            session_tokens: SessionTokenManager::from_env(),
             snowflake_gen,
             module_connection_map: conductor.module_connection_map,
             resource_to_module_map,
@@ -217,8 +530,10 @@ impl ConductorModule {
             session_id_to_guest_map: HashMap::new(),
             session_id_to_admin_map: HashMap::new(),
             guest_timeout_map: HashMap::new(),
+            missed_events: HashMap::new(),
 
             timeouts: Vec::new(),
+            status_refreshed_at: Instant::now(),
             module_map,
 
             module_communication_map,
@@ -230,13 +545,25 @@ impl ConductorModule {
     pub fn handle_new_ws_connections(&mut self) {
         for (connection_id, ticket) in self.websocket_module.handle_new_ws_connections() {
             debug!("{:?}", ticket);
-            if let Some(true) = ticket.admin_login {
+            let session_claims = ticket
+                .session_token
+                .as_deref()
+                .and_then(|session_token| self.session_tokens.verify(session_token));
+            // Without a valid token the flag only asks for a fresh admin session, which has to
+            // pass the admin login check before it can do anything.
+            let is_admin_session = match &session_claims {
+                Some(session_claims) => session_claims.admin,
+                None => ticket.admin_login.unwrap_or(false),
+            };
+            let session_id = session_claims
+                .map(|session_claims| session_claims.sub)
+                .unwrap_or_default();
+
+            if is_admin_session {
                 debug!("Admin ready to start their session!");
 
-                let admin_id_from_session_id = self
-                    .session_id_to_admin_map
-                    .get(&ticket.session_id.unwrap_or_default())
-                    .unwrap_or(&0);
+                let admin_id_from_session_id =
+                    self.session_id_to_admin_map.get(&session_id).unwrap_or(&0);
 
                 debug!("{}, {:?}", admin_id_from_session_id, self.admins);
 
@@ -280,24 +607,42 @@ impl ConductorModule {
                     };
 
                 if let Some(admin) = self.admins.get(&admin_id) {
-                    if let Ok(event_as_string) =
-                        serde_json::to_string(&CommunicationEvent::ConnectionReady((
-                            admin.session_id.clone(),
-                            admin.login_data.is_none(),
-                        )))
-                    {
-                        Self::send_to_admin(admin, &mut self.websocket_module, event_as_string);
-                    } else {
-                        error!("Could not parse ConnectionReady enum, wtf?");
+                    match self.session_tokens.issue(&admin.session_id, true) {
+                        Ok(session_token) => {
+                            Self::send_to_admin(
+                                admin,
+                                &mut self.websocket_module,
+                                &CommunicationEvent::ConnectionReady((
+                                    session_token,
+                                    admin.login_data.is_none(),
+                                    ProtocolInfo::current(),
+                                )),
+                            );
+                        }
+                        Err(err) => error!("Could not issue session token for admin! {:?}", err),
                     }
                 }
                 continue;
             }
 
-            let guest_id_from_session_id = self
-                .session_id_to_guest_map
-                .get(&ticket.session_id.unwrap_or_default())
-                .unwrap_or(&0);
+            let guest_id_from_session_id =
+                self.session_id_to_guest_map.get(&session_id).unwrap_or(&0);
+            let known_provider_user_id = self
+                .guests
+                .get(guest_id_from_session_id)
+                .and_then(|guest| guest.login_data.as_ref())
+                .map(|login_data| login_data.provider_user_id.clone());
+            if let Some(refusal) = gate_refusal(
+                &lock_gates(&self.web_server_module.gates),
+                known_provider_user_id.as_ref(),
+                Utc::now().time(),
+            ) {
+                debug!("Refusing connection {}: {}", connection_id, refusal);
+                self.websocket_module
+                    .close_connection(&connection_id, CloseCode::Policy, refusal.into());
+                continue;
+            }
+            let mut session_resume = None;
 
             let guest_id: Snowflake = if let Some(guest) =
                 self.guests.get_mut(guest_id_from_session_id)
@@ -306,17 +651,19 @@ impl ConductorModule {
                 if guest.ws_connection_id.is_some() {
                     error!("Guest already has a connection!");
                     //TODO: Disconnect old connection and connect new connection
-                    self.websocket_module.send_event(
-                        &connection_id,
-                        serde_json::to_string(&CommunicationEvent::AlreadyConnected)
-                            .unwrap_or_else(|_| "AlreadyConnected".to_string()),
-                    );
+                    if let Err(err) = self
+                        .websocket_module
+                        .send_event(&connection_id, &CommunicationEvent::AlreadyConnected)
+                    {
+                        error!("Could not send AlreadyConnected {:?}", err);
+                    }
                     continue;
                 }
                 self.guest_timeout_map.remove(&guest.id);
                 self.ws_to_guest_map.insert(connection_id, guest.id);
 
                 guest.ws_connection_id = Some(connection_id);
+                let missed_events = self.missed_events.remove(&guest.id);
 
                 if let (Some(current_module_id), Some(current_instance_id)) =
                     (&guest.current_module_id, &guest.current_instance_id)
@@ -336,7 +683,26 @@ impl ConductorModule {
                             },
                             "Error sending reconnect event",
                         );
-                        if let Some(module) = self.module_map.get(current_module_id) {
+                        if ticket.resume == Some(true) {
+                            debug!("Resuming the session of the guest");
+                            session_resume = match missed_events.and_then(MissedEvents::into_replay)
+                            {
+                                Some(missed_events) => Some((SessionResume::Replay, missed_events)),
+                                None => {
+                                    send_and_log_error_custom(
+                                        &mut module_communication.sender.system_to_module_sender,
+                                        ModuleInstanceEvent {
+                                            module_id: current_module_id.clone(),
+                                            instance_id: current_instance_id.clone(),
+                                            world_id: None,
+                                            event_type: SystemToModuleEvent::Resync(guest.id),
+                                        },
+                                        "Error sending resync event",
+                                    );
+                                    Some((SessionResume::Resync, VecDeque::new()))
+                                }
+                            };
+                        } else if let Some(module) = self.module_map.get(current_module_id) {
                             if let Some((terrain_params, layer_parralax)) =
                                 module.get_terrain_info_for_guest(&guest.id, current_instance_id)
                             {
@@ -391,15 +757,29 @@ impl ConductorModule {
             };
 
             if let Some(guest) = self.guests.get(&guest_id) {
-                if let Ok(event_as_string) =
-                    serde_json::to_string(&CommunicationEvent::ConnectionReady((
-                        guest.session_id.clone(),
-                        guest.login_data.is_none(),
-                    )))
-                {
-                    Self::send_to_guest(guest, &mut self.websocket_module, event_as_string);
-                } else {
-                    error!("Could not parse ConnectionReady enum, wtf?");
+                match self.session_tokens.issue(&guest.session_id, false) {
+                    Ok(session_token) => {
+                        Self::send_to_guest(
+                            guest,
+                            &mut self.websocket_module,
+                            &CommunicationEvent::ConnectionReady((
+                                session_token,
+                                guest.login_data.is_none(),
+                                ProtocolInfo::current(),
+                            )),
+                        );
+                    }
+                    Err(err) => error!("Could not issue session token for guest! {:?}", err),
+                }
+                if let Some((session_resume, missed_events)) = session_resume {
+                    Self::send_to_guest(
+                        guest,
+                        &mut self.websocket_module,
+                        &CommunicationEvent::SessionResumed(session_resume),
+                    );
+                    for event in &missed_events {
+                        Self::send_to_guest(guest, &mut self.websocket_module, event);
+                    }
                 }
             }
         }
@@ -422,6 +802,8 @@ impl ConductorModule {
                 pending_module_exit: None,
                 ws_connection_id: Some(connection_id),
                 persisted_guest: None,
+                round_trip_time: None,
+                round_trip_timer: RoundTripTimer::default(),
                 session_id,
             },
         );
@@ -451,6 +833,7 @@ impl ConductorModule {
                     guest.ws_connection_id = None;
 
                     self.guest_timeout_map.insert(guest_id, Instant::now());
+                    self.missed_events.insert(guest_id, MissedEvents::default());
 
                     if let (Some(current_module_id), Some(current_instance_id)) =
                         (&guest.current_module_id, &guest.current_instance_id)
@@ -491,17 +874,12 @@ impl ConductorModule {
 
     pub fn send_load_events(&mut self) {
         for (actor_id, module_id, event_type) in self.resource_module.drain_load_events() {
-            if let Ok(message_as_string) =
-                serde_json::to_string(&CommunicationEvent::ResourceEvent(module_id, event_type))
-            {
-                debug!("Sending load event to actor");
-                if let Some(guest) = self.guests.get(&actor_id) {
-                    Self::send_to_guest(guest, &mut self.websocket_module, message_as_string);
-                } else if let Some(admin) = self.admins.get(&actor_id) {
-                    Self::send_to_admin(admin, &mut self.websocket_module, message_as_string);
-                }
-            } else {
-                error!("Error serializing resource event!");
+            let event = CommunicationEvent::ResourceEvent(module_id, event_type);
+            debug!("Sending load event to actor");
+            if let Some(guest) = self.guests.get(&actor_id) {
+                Self::send_to_guest(guest, &mut self.websocket_module, &event);
+            } else if let Some(admin) = self.admins.get(&actor_id) {
+                Self::send_to_admin(admin, &mut self.websocket_module, &event);
             }
         }
     }
@@ -509,10 +887,12 @@ impl ConductorModule {
     pub fn send_to_guest(
         guest: &Guest,
         websocket_module: &mut WebsocketModule,
-        event_as_string: String,
+        event: &CommunicationEvent,
     ) {
         if let Some(ws_connection_id) = &guest.ws_connection_id {
-            websocket_module.send_event(ws_connection_id, event_as_string);
+            if let Err(err) = websocket_module.send_event(ws_connection_id, event) {
+                error!("Could not encode event for guest {:?}", err);
+            }
         } else {
             debug!("Could not send to guest '{:?}' no active connection", guest);
         }
@@ -521,10 +901,12 @@ impl ConductorModule {
     pub fn send_to_admin(
         admin: &Admin,
         websocket_module: &mut WebsocketModule,
-        event_as_string: String,
+        event: &CommunicationEvent,
     ) {
         if let Some(ws_connection_id) = &admin.ws_connection_id {
-            websocket_module.send_event(ws_connection_id, event_as_string);
+            if let Err(err) = websocket_module.send_event(ws_connection_id, event) {
+                error!("Could not encode event for admin {:?}", err);
+            }
         } else {
             error!("Could not send to admin '{:?}' no active connection", admin);
         }
@@ -766,14 +1148,17 @@ impl ConductorModule {
                 }
             }
             ModuleToSystemEvent::GuestStateChange(guest_id, state_change) => {
+                let guests_online = self.guests.len() as i32;
                 let guest = safe_unwrap(
                     self.guests.get_mut(&guest_id),
                     ProcessModuleEventError::GuestNotFound,
                 )?;
-                if let Some(communication_event) = ConductorModule::process_guest_state_change(
+                for communication_event in ConductorModule::process_guest_state_change(
                     guest,
                     state_change,
-                    &mut self.persistence_module,
+                    guests_online,
+                    &self.persistence_module,
+                    &self.leaderboards,
                 )? {
                     if let CommunicationEvent::ShowGlobalMessage(_message) = &communication_event {
                         let guest_ids: Vec<ActorId> = self.guests.keys().cloned().collect();
@@ -837,46 +1222,497 @@ impl ConductorModule {
         websocket_module: &mut WebsocketModule,
         event: &CommunicationEvent,
     ) -> Result<(), ProcessModuleEventError> {
-        if let Ok(message_as_string) = serde_json::to_string(event) {
-            Self::send_to_guest(guest, websocket_module, message_as_string);
-            Ok(())
+        if let Some(ws_connection_id) = &guest.ws_connection_id {
+            websocket_module
+                .send_event(ws_connection_id, event)
+                .map_err(|_| ProcessModuleEventError::CouldNotSerializeCommunicationEvent)
         } else {
-            Err(ProcessModuleEventError::CouldNotSerializeCommunicationEvent)
+            debug!("Could not send to guest '{:?}' no active connection", guest);
+            Ok(())
         }
     }
 
     pub fn process_guest_state_change(
         guest: &mut Guest,
         guest_state_change: GuestStateChange,
-        persistence_module: &mut PersistenceModule,
-    ) -> Result<Option<CommunicationEvent>, ProcessModuleEventError> {
+        guests_online: i32,
+        persistence_module: &PersistenceModule,
+        leaderboards: &LeaderboardMap,
+    ) -> Result<Vec<CommunicationEvent>, ProcessModuleEventError> {
         match guest_state_change {
             GuestStateChange::ExitModule(module_exit_slot) => {
                 guest.pending_module_exit = Some(module_exit_slot);
 
-                Ok(None)
+                Ok(Vec::new())
             }
             GuestStateChange::FoundSecret(name, _module_name) => {
+                if let Some(persisted_guest_state) = &guest.persisted_guest {
+                    persistence_module.queue(PersistenceJob::AddSecretFound {
+                        guest_id: guest.id,
+                        persisted_guest_state_id: persisted_guest_state.info.id,
+                        name,
+                    })?;
+                }
+
+                Ok(Vec::new())
+            }
+            GuestStateChange::SetModuleData(module_id, key, value) => {
                 if let Some(persisted_guest_state) = &mut guest.persisted_guest {
-                    let secret =
-                        persistence_module.add_secret_found(name, persisted_guest_state.info.id)?;
-                    persisted_guest_state.secrets_found.push(secret);
-
-                    return Ok(Some(CommunicationEvent::ShowGlobalMessage(format!(
-                        "{} found a shard!",
-                        persisted_guest_state.info.display_name
-                    ))));
+                    persistence_module.queue_guest_module_data(
+                        persisted_guest_state.info.id,
+                        module_id.clone(),
+                        key.clone(),
+                        value.clone(),
+                    )?;
+                    persisted_guest_state
+                        .module_data
+                        .entry(module_id)
+                        .or_default()
+                        .insert(key, value);
+                }
+
+                Ok(Vec::new())
+            }
+            GuestStateChange::UnlockAchievement(module_id, achievement) => {
+                if let Some(persisted_guest_state) = &guest.persisted_guest {
+                    if persisted_guest_state
+                        .achievements_unlocked
+                        .iter()
+                        .any(|unlocked| {
+                            unlocked.module_id == module_id
+                                && unlocked.achievement_id == achievement.id
+                        })
+                    {
+                        return Ok(Vec::new());
+                    }
+                    persistence_module.queue(PersistenceJob::UnlockAchievement {
+                        guest_id: guest.id,
+                        persisted_guest_state_id: persisted_guest_state.info.id,
+                        module_id,
+                        achievement,
+                    })?;
                 }
 
-                Ok(None)
+                Ok(Vec::new())
+            }
+            GuestStateChange::SubmitScore(module_id, definition, score) => {
+                let Some(persisted_guest_state) = &guest.persisted_guest else {
+                    return Ok(Vec::new());
+                };
+                let now = Utc::now().naive_utc();
+                let mut leaderboards = lock_leaderboards(leaderboards);
+                let leaderboard = match leaderboards
+                    .entry(module_id.clone())
+                    .or_default()
+                    .entry(definition.id.clone())
+                {
+                    Entry::Occupied(entry) => {
+                        let leaderboard = entry.into_mut();
+                        leaderboard.set_definition(definition);
+                        leaderboard
+                    }
+                    Entry::Vacant(entry) => {
+                        let period_start = period_start(&definition.reset_period, now);
+                        persistence_module.queue(PersistenceJob::LoadLeaderboard {
+                            module_id: module_id.clone(),
+                            leaderboard_id: definition.id.clone(),
+                            period_start,
+                        })?;
+                        entry.insert(Leaderboard::loading(definition, period_start))
+                    }
+                };
+                let leaderboard_entry = leaderboard.submit(
+                    persisted_guest_state.info.id,
+                    persisted_guest_state.info.display_name.clone(),
+                    score,
+                    now,
+                );
+                if let Some(leaderboard_entry) =
+                    leaderboard_entry.filter(|_| leaderboard.is_loaded())
+                {
+                    persistence_module.queue(PersistenceJob::UpsertLeaderboardEntry {
+                        module_id,
+                        leaderboard_id: leaderboard.definition.id.clone(),
+                        period_start: leaderboard.period_start,
+                        entry: leaderboard_entry,
+                    })?;
+                }
+
+                Ok(Self::create_data_store_update_event(
+                    guest,
+                    guests_online,
+                    HashMap::from([(
+                        leaderboard.definition.id.clone(),
+                        leaderboard.snapshot(DATA_STORE_LEADERBOARD_SIZE),
+                    )]),
+                )?
+                .into_iter()
+                .collect())
+            }
+        }
+    }
+
+    pub fn process_persistence_results(&mut self) {
+        let results: Vec<PersistenceResult> = self.persistence_module.drain_results().collect();
+        for result in results {
+            if let Err(err) = self.process_persistence_result(result) {
+                error!("Could not process persistence result! {:?}", err);
             }
         }
     }
 
+    fn process_persistence_result(
+        &mut self,
+        result: PersistenceResult,
+    ) -> Result<(), ProcessModuleEventError> {
+        let guests_online = self.guests.len() as i32;
+        match result {
+            PersistenceResult::GuestLoaded(guest_id, mut persisted_guest) => {
+                let Some(guest) = self.guests.get_mut(&guest_id) else {
+                    debug!(
+                        "Guest {} left before its persisted state was loaded.",
+                        guest_id
+                    );
+                    return Ok(());
+                };
+                match &guest.persisted_guest {
+                    Some(previous_persisted_guest) => {
+                        let previous_id = previous_persisted_guest.info.id;
+                        for leaderboard in lock_leaderboards(&self.leaderboards)
+                            .values_mut()
+                            .flat_map(|leaderboards| leaderboards.values_mut())
+                        {
+                            leaderboard.reassign(
+                                previous_id,
+                                persisted_guest.info.id,
+                                &persisted_guest.info.display_name,
+                            );
+                        }
+                    }
+                    None => {
+                        Self::handle_times_joined(&self.persistence_module, &mut persisted_guest)?
+                    }
+                }
+                guest.persisted_guest = Some(persisted_guest);
+                Self::send_guest_out_of_login(guest);
+            }
+            PersistenceResult::AnonymousGuestLinked(guest_id) => {
+                let Some(guest) = self.guests.get_mut(&guest_id) else {
+                    return Ok(());
+                };
+                if let Some(ws_connection_id) = guest.ws_connection_id.take() {
+                    self.ws_to_guest_map.remove(&ws_connection_id);
+                    if let Err(err) = self.websocket_module.send_event(
+                        &ws_connection_id,
+                        &CommunicationEvent::Toast(
+                            ToastAlertLevel::Error,
+                            "This guest was linked to an account, please log in with it.".into(),
+                        ),
+                    ) {
+                        error!("Could not send linked guest notice {:?}", err);
+                    }
+                    self.websocket_module.close_connection(
+                        &ws_connection_id,
+                        CloseCode::Policy,
+                        "Guest linked".into(),
+                    );
+                }
+                self.timeouts.push(guest_id);
+            }
+            PersistenceResult::SecretFound(guest_id, secret) => {
+                let Some(persisted_guest_state) = self
+                    .guests
+                    .get_mut(&guest_id)
+                    .and_then(|guest| guest.persisted_guest.as_mut())
+                else {
+                    return Ok(());
+                };
+                persisted_guest_state.secrets_found.push(secret);
+                let message = CommunicationEvent::ShowGlobalMessage(format!(
+                    "{} found a shard!",
+                    persisted_guest_state.info.display_name
+                ));
+                let guest_ids: Vec<ActorId> = self.guests.keys().cloned().collect();
+                for guest_id in guest_ids {
+                    Self::send_communication_event_to_guest(
+                        &mut self.guests,
+                        &mut self.websocket_module,
+                        guest_id,
+                        &message,
+                    )?;
+                }
+            }
+            PersistenceResult::AchievementUnlocked(guest_id, achievement, unlocked_achievement) => {
+                let Some(guest) = self.guests.get_mut(&guest_id) else {
+                    return Ok(());
+                };
+                let Some(persisted_guest_state) = &mut guest.persisted_guest else {
+                    return Ok(());
+                };
+                persisted_guest_state
+                    .achievements_unlocked
+                    .push(unlocked_achievement);
+                let mut communication_events = vec![CommunicationEvent::Toast(
+                    ToastAlertLevel::Success,
+                    format!("Achievement unlocked: {}", achievement.title),
+                )];
+                communication_events.extend(Self::create_data_store_update_event(
+                    guest,
+                    guests_online,
+                    HashMap::new(),
+                )?);
+                for communication_event in communication_events {
+                    Self::send_communication_event_to_guest_direct(
+                        guest,
+                        &mut self.websocket_module,
+                        &communication_event,
+                    )?;
+                }
+            }
+            PersistenceResult::LeaderboardLoaded(
+                module_id,
+                leaderboard_id,
+                period_start,
+                entries,
+            ) => {
+                let mut leaderboards = lock_leaderboards(&self.leaderboards);
+                let Some(leaderboard) = leaderboards
+                    .get_mut(&module_id)
+                    .and_then(|leaderboards| leaderboards.get_mut(&leaderboard_id))
+                else {
+                    return Ok(());
+                };
+                for entry in leaderboard.merge_loaded_entries(period_start, entries) {
+                    self.persistence_module
+                        .queue(PersistenceJob::UpsertLeaderboardEntry {
+                            module_id: module_id.clone(),
+                            leaderboard_id: leaderboard_id.clone(),
+                            period_start: leaderboard.period_start,
+                            entry,
+                        })?;
+                }
+            }
+            PersistenceResult::AuditLogLoaded(admin_id, entries) => {
+                send_and_log_error(
+                    &mut self.system_to_admin_communication.sender,
+                    (
+                        admin_id,
+                        CommunicationEvent::EditorEvent(EditorEvent::AuditLog(entries)),
+                    ),
+                );
+            }
+            PersistenceResult::Failed(job, err) => {
+                error!("Persistence job {:?} failed! {:?}", job, err);
+                match job {
+                    PersistenceJob::LoadGuest { guest_id, .. } => {
+                        if let Some(guest) = self.guests.get_mut(&guest_id) {
+                            Self::send_guest_out_of_login(guest);
+                        }
+                        Self::send_communication_event_to_guest(
+                            &mut self.guests,
+                            &mut self.websocket_module,
+                            guest_id,
+                            &CommunicationEvent::Toast(
+                                ToastAlertLevel::Error,
+                                "Could not load your progress, it will not be saved this session."
+                                    .to_string(),
+                            ),
+                        )?;
+                    }
+                    PersistenceJob::LinkGuest { guest_id, .. } => {
+                        if let Some(guest) = self.guests.get_mut(&guest_id) {
+                            Self::send_guest_out_of_login(guest);
+                        }
+                        Self::send_communication_event_to_guest(
+                            &mut self.guests,
+                            &mut self.websocket_module,
+                            guest_id,
+                            &CommunicationEvent::Toast(
+                                ToastAlertLevel::Error,
+                                "Could not move your guest progress to your account.".to_string(),
+                            ),
+                        )?;
+                    }
+                    PersistenceJob::AddSecretFound { guest_id, .. }
+                    | PersistenceJob::UnlockAchievement { guest_id, .. } => {
+                        Self::send_communication_event_to_guest(
+                            &mut self.guests,
+                            &mut self.websocket_module,
+                            guest_id,
+                            &CommunicationEvent::Toast(
+                                ToastAlertLevel::Error,
+                                "Could not save your progress, please try again later.".to_string(),
+                            ),
+                        )?;
+                    }
+                    PersistenceJob::WriteModuleData(writes) => {
+                        let guest_ids: Vec<ActorId> = self
+                            .guests
+                            .values()
+                            .filter(|guest| {
+                                guest
+                                    .persisted_guest
+                                    .as_ref()
+                                    .is_some_and(|persisted_guest| {
+                                        writes.iter().any(|write| {
+                                            write.persisted_guest_state_id
+                                                == persisted_guest.info.id
+                                        })
+                                    })
+                            })
+                            .map(|guest| guest.id)
+                            .collect();
+                        for guest_id in guest_ids {
+                            Self::send_communication_event_to_guest(
+                                &mut self.guests,
+                                &mut self.websocket_module,
+                                guest_id,
+                                &CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    "Could not save your progress, please try again later."
+                                        .to_string(),
+                                ),
+                            )?;
+                        }
+                    }
+                    PersistenceJob::GrantAdminRole { admin_id, grant }
+                    | PersistenceJob::RevokeAdminRole { admin_id, grant } => {
+                        send_and_log_error(
+                            &mut self.system_to_admin_communication.sender,
+                            (
+                                admin_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    format!(
+                                        "Could not save the role change for {}, it is lost on restart.",
+                                        grant.provider_user_id
+                                    ),
+                                ),
+                            ),
+                        );
+                    }
+                    PersistenceJob::BanGuest {
+                        admin_id,
+                        ban:
+                            GuestBan {
+                                provider_user_id, ..
+                            },
+                    }
+                    | PersistenceJob::UnbanGuest {
+                        admin_id,
+                        provider_user_id,
+                    } => {
+                        send_and_log_error(
+                            &mut self.system_to_admin_communication.sender,
+                            (
+                                admin_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    format!(
+                                        "Could not save the ban change for {}, it is lost on restart.",
+                                        provider_user_id
+                                    ),
+                                ),
+                            ),
+                        );
+                    }
+                    PersistenceJob::QueryAuditLog { admin_id, .. } => {
+                        send_and_log_error(
+                            &mut self.system_to_admin_communication.sender,
+                            (
+                                admin_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    "Could not load the audit log.".to_string(),
+                                ),
+                            ),
+                        );
+                    }
+                    PersistenceJob::UpdateGuestState(_)
+                    | PersistenceJob::AppendAuditLog(_)
+                    | PersistenceJob::LoadLeaderboard { .. }
+                    | PersistenceJob::UpsertLeaderboardEntry { .. } => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send_guest_out_of_login(guest: &mut Guest) {
+        if guest.pending_module_exit.is_none() && guest.current_module_id.is_none() {
+            guest.pending_module_exit = Some("LoginOut".to_string());
+        }
+    }
+
+    fn create_data_store_update_event(
+        guest: &Guest,
+        guests_online: i32,
+        leaderboards: HashMap<LeaderboardId, LeaderboardSnapshot>,
+    ) -> Result<Option<CommunicationEvent>, ProcessModuleEventError> {
+        if let (Some(persisted_guest), Some(module_id), Some(instance_id)) = (
+            &guest.persisted_guest,
+            &guest.current_module_id,
+            &guest.current_instance_id,
+        ) {
+            let data_store = MediumDataStorage {
+                current_guest_info: Some(MediumDataStorageGuestInfo::new(
+                    persisted_guest,
+                    guests_online,
+                )),
+                leaderboards,
+            };
+            return Ok(Some(CommunicationEvent::GameSystemEvent(
+                module_id.clone(),
+                instance_id.clone(),
+                None,
+                GameSystemToGuestEvent::UpdateDataStore(
+                    serde_json::to_string(&data_store).map_err(|_| {
+                        ProcessModuleEventError::CouldNotSerializeCommunicationEvent
+                    })?,
+                ),
+            )));
+        }
+
+        Ok(None)
+    }
+
+    fn load_leaderboards(
+        persistence_module: &PersistenceModule,
+        modules: &[blueprint::def::Module],
+    ) -> LeaderboardMap {
+        let now = Utc::now().naive_utc();
+        let mut leaderboards: HashMap<ModuleId, HashMap<LeaderboardId, Leaderboard>> =
+            HashMap::new();
+        for module in modules {
+            for definition in &module.leaderboards {
+                let period_start = period_start(&definition.reset_period, now);
+                match persistence_module.get_leaderboard_entries(
+                    &module.id,
+                    &definition.id,
+                    period_start,
+                ) {
+                    Ok(entries) => {
+                        leaderboards.entry(module.id.clone()).or_default().insert(
+                            definition.id.clone(),
+                            Leaderboard::new(definition.clone(), period_start, entries),
+                        );
+                    }
+                    Err(err) => error!(
+                        "Could not load leaderboard '{}' of module '{}': {:?}",
+                        definition.id, module.name, err
+                    ),
+                }
+            }
+        }
+
+        Arc::new(Mutex::new(leaderboards))
+    }
+
     fn handle_times_joined(
-        persistence_module: &mut PersistenceModule,
+        persistence_module: &PersistenceModule,
         persisted_guest: &mut PersistedGuest,
-    ) -> Result<usize, PersistenceError> {
+    ) -> Result<(), PersistenceError> {
         let now = Utc::now().naive_utc();
 
         let last_joined_or_never = persisted_guest
@@ -894,13 +1730,15 @@ impl ConductorModule {
 
         persisted_guest.info.last_time_joined = Some(now);
 
-        persistence_module.update_persisted_guest_state(UpdatePersistedGuestState {
-            id: persisted_guest.info.id,
-            last_time_joined: persisted_guest.info.last_time_joined,
-            times_joined: Some(persisted_guest.info.times_joined),
-            is_tester: None,
-            is_observer: None,
-        })
+        persistence_module.queue(PersistenceJob::UpdateGuestState(
+            UpdatePersistedGuestState {
+                id: persisted_guest.info.id,
+                last_time_joined: persisted_guest.info.last_time_joined,
+                times_joined: Some(persisted_guest.info.times_joined),
+                is_tester: None,
+                is_observer: None,
+            },
+        ))
     }
 
     pub fn process_position_event(
@@ -942,19 +1780,17 @@ impl ConductorModule {
             world_id,
             event_type,
         } = event_type;
-        if let Ok(message_as_string) = serde_json::to_string(&CommunicationEvent::GameSystemEvent(
-            module_id,
-            instance_id,
-            world_id,
-            event_type,
-        )) {
-            if let Some(guest) = self.guests.get(&guest_id) {
-                Self::send_to_guest(guest, &mut self.websocket_module, message_as_string);
-            } else if let Some(admin) = self.admins.get(&guest_id) {
-                Self::send_to_admin(admin, &mut self.websocket_module, message_as_string);
+        let event =
+            CommunicationEvent::GameSystemEvent(module_id, instance_id, world_id, event_type);
+        if let Some(guest) = self.guests.get(&guest_id) {
+            match self.missed_events.get_mut(&guest_id) {
+                Some(missed_events) if guest.ws_connection_id.is_none() => {
+                    missed_events.push(event)
+                }
+                _ => Self::send_to_guest(guest, &mut self.websocket_module, &event),
             }
-        } else {
-            error!("Error serializing resource event!");
+        } else if let Some(admin) = self.admins.get(&guest_id) {
+            Self::send_to_admin(admin, &mut self.websocket_module, &event);
         }
 
         Ok(())
@@ -1032,16 +1868,20 @@ impl ConductorModule {
     }
 
     pub fn process_events_from_guest(&mut self) {
+        let mut pings = Vec::new();
+        let mut pong_echoes = Vec::new();
         for (guest_id, guest) in &self.guests {
             if let Some(ws_connection_id) = &guest.ws_connection_id {
                 for message in self.websocket_module.drain_events(ws_connection_id) {
-                    match serde_json::from_str::<GuestTo>(message.to_string().as_str()) {
+                    match decode_message::<GuestTo>(&message) {
                         Ok(guest_to) => match guest_to {
                             GuestTo::GuestToSystemEvent(event) => {
                                 ConductorModule::process_guest_to_system_event(
                                     event,
                                     *guest_id,
                                     &mut self.login_manager,
+                                    &mut pings,
+                                    &mut pong_echoes,
                                 );
                             }
                             GuestTo::GuestToModuleEvent(event) => {
@@ -1074,18 +1914,68 @@ impl ConductorModule {
                 }
             }
         }
+        self.answer_time_sync_pings(pings);
+        self.time_round_trips(pong_echoes);
     }
 
     fn process_guest_to_system_event(
         event: GuestToSystemEvent,
         guest_id: ActorId,
         login_manager: &mut LoginManager,
+        pings: &mut Vec<(ActorId, TimeSyncPing)>,
+        pong_echoes: &mut Vec<(ActorId, f64)>,
     ) {
         match event {
             GuestToSystemEvent::ProviderLoggedIn(provider_logged_in) => {
                 login_manager.add_provider_login(guest_id, provider_logged_in);
             }
-            GuestToSystemEvent::Ping => {}
+            GuestToSystemEvent::Ping(ping) => pings.push((guest_id, ping)),
+            GuestToSystemEvent::PongEcho(server_time) => {
+                pong_echoes.push((guest_id, server_time));
+            }
+        }
+    }
+
+    fn answer_time_sync_pings(&mut self, pings: Vec<(ActorId, TimeSyncPing)>) {
+        for (guest_id, ping) in pings {
+            let Some(guest) = self.guests.get_mut(&guest_id) else {
+                continue;
+            };
+            let pong = TimeSyncPong::answer(&ping);
+            Self::send_to_guest(
+                guest,
+                &mut self.websocket_module,
+                &CommunicationEvent::Pong(pong),
+            );
+            guest.round_trip_timer.pong_sent(&pong, Instant::now());
+        }
+    }
+
+    /// Scripts of the guest's current module get the round trip time measured here, from
+    /// sending a pong until its echo arrived.
+    fn time_round_trips(&mut self, pong_echoes: Vec<(ActorId, f64)>) {
+        for (guest_id, server_time) in pong_echoes {
+            let Some(guest) = self.guests.get_mut(&guest_id) else {
+                continue;
+            };
+            let Some(round_trip_time) = guest
+                .round_trip_timer
+                .echo_received(server_time, Instant::now())
+            else {
+                continue;
+            };
+            guest.round_trip_time = Some(round_trip_time);
+            if let (Some(module_id), Some(instance_id)) =
+                (&guest.current_module_id, &guest.current_instance_id)
+            {
+                let event = ModuleInstanceEvent {
+                    module_id: module_id.clone(),
+                    instance_id: instance_id.clone(),
+                    world_id: None,
+                    event_type: SystemToModuleEvent::RoundTripTime(guest_id, round_trip_time),
+                };
+                self.send_to_current_guest_module(guest_id, event);
+            }
         }
     }
 
@@ -1122,83 +2012,130 @@ impl ConductorModule {
             &mut self.system_to_admin_communication.sender;
         let provider_id_to_guest_map = &mut self.provider_id_to_guest_map;
         let provider_id_to_admin_map = &mut self.provider_id_to_admin_map;
-        let persistence_module = &mut self.persistence_module;
+        let persistence_module = &self.persistence_module;
         let websocket_module = &mut self.websocket_module;
         let ws_to_guest_map = &mut self.ws_to_guest_map;
         let ws_to_admin_map = &mut self.ws_to_admin_map;
         let session_id_to_guest_map = &mut self.session_id_to_guest_map;
         let session_id_to_admin_map = &mut self.session_id_to_admin_map;
+        let session_tokens = &mut self.session_tokens;
+        let admin_permissions = &self.admin_permissions;
+        let guest_bans = &self.guest_bans;
+        let gates = &self.web_server_module.gates;
         self.login_manager.process_running_logins(|res| match res {
-            Ok((actor_id, login_data)) => {
+            Ok((actor_id, provider_user)) => {
+                let login_data = provider_user.login_data;
                 debug!("login {} {:?}", actor_id, login_data);
-                if guests.contains_key(&actor_id) {
-                    Self::handle_actor_login_result(system_to_guest_communication_sender, Self::handle_actor_login(
-                        provider_id_to_guest_map,
-                        websocket_module,
+                if let Some(guest) = guests.get(&actor_id) {
+                    if let Some(refusal) = gate_refusal(
+                        &lock_gates(gates),
+                        Some(&login_data.provider_user_id),
+                        Utc::now().time(),
+                    ) {
+                        Self::handle_actor_login_result(
+                            system_to_guest_communication_sender,
+                            Err(HandleLoginError::GateClosed(actor_id, refusal)),
+                        );
+                        return;
+                    }
+                    let anonymous_provider_id = guest
+                        .login_data
+                        .as_ref()
+                        .filter(|current_login_data| {
+                            current_login_data.provider == LoginProvider::Anonymous
+                                && login_data.provider != LoginProvider::Anonymous
+                        })
+                        .map(|current_login_data| current_login_data.provider_user_id.clone());
+                    if let Some(anonymous_provider_id) = &anonymous_provider_id {
+                        provider_id_to_guest_map.remove(anonymous_provider_id);
+                    }
+                    let login_result = Self::handle_actor_login(
+                        LoginContext {
+                            websocket_module,
+                            session_tokens,
+                            guest_bans,
+                        },
+                        ActorMaps {
+                            actors: guests,
+                            provider_id_to_actor_map: provider_id_to_guest_map,
+                            ws_to_actor_map: ws_to_guest_map,
+                            session_to_actor_map: session_id_to_guest_map,
+                        },
                         &login_data,
                         &actor_id,
-                        guests,
-                        ws_to_guest_map,
-                        session_id_to_guest_map,
-                        false,
+                        None,
                         |login_data, guest| {
-                            if let Err(err) = Self::handle_guest_persistence(persistence_module, login_data, guest) {
-                                error!("Oh oh! There was an error while trying to get guest persistence!!! {:?}", err)
-                            }
                             debug!("Guest login success!!!!! {:?}", guest);
-                            if guest.pending_module_exit.is_none() && guest.current_module_id.is_none() {
-                               guest.pending_module_exit = Some("LoginOut".to_string());
+                            if let Err(err) = Self::handle_guest_persistence(persistence_module, login_data, anonymous_provider_id.clone(), guest) {
+                                error!("Oh oh! There was an error while trying to get guest persistence!!! {:?}", err);
+                                Self::send_guest_out_of_login(guest);
                             }
-                        }));
+                        });
+                    if let (Ok(guest_id), Some(guest_token)) = (&login_result, provider_user.guest_token) {
+                        send_and_log_error(
+                            system_to_guest_communication_sender,
+                            (*guest_id, CommunicationEvent::Signal(SignalToMedium::GuestToken(guest_token))),
+                        );
+                    }
+                    Self::handle_actor_login_result(system_to_guest_communication_sender, login_result);
                 } else {
                     Self::handle_actor_login_result(system_to_admin_communication_sender, Self::handle_actor_login(
-                        provider_id_to_admin_map,
-                        websocket_module,
+                        LoginContext {
+                            websocket_module,
+                            session_tokens,
+                            guest_bans,
+                        },
+                        ActorMaps {
+                            actors: admins,
+                            provider_id_to_actor_map: provider_id_to_admin_map,
+                            ws_to_actor_map: ws_to_admin_map,
+                            session_to_actor_map: session_id_to_admin_map,
+                        },
                         &login_data,
                         &actor_id,
-                        admins,
-                        ws_to_admin_map,
-                        session_id_to_admin_map,
-                        true,
+                        Some(admin_permissions),
                         |_, _| {}));
                 };
             }
-            Err(error) => match error {
-                LoginError::UserDidNotExistLongEnough(actor_id, time) => {
-                    let sender = if guests.contains_key(&actor_id) {&mut system_to_guest_communication_sender} else {&mut system_to_admin_communication_sender};
-                    send_and_log_error(
-                        *sender,
-                        (
-                            actor_id,
-                            CommunicationEvent::Toast(
-                                ToastAlertLevel::Error,
-                                format!(
-                                    "Your account is not older than {} days. Please ask shiku!",
-                                    time
+            Err(error) => {
+                SERVER_METRICS.login_failures.fetch_add(1, Ordering::Relaxed);
+                match error {
+                    LoginError::UserDidNotExistLongEnough(actor_id, time) => {
+                        let sender = if guests.contains_key(&actor_id) {&mut system_to_guest_communication_sender} else {&mut system_to_admin_communication_sender};
+                        send_and_log_error(
+                            *sender,
+                            (
+                                actor_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    format!(
+                                        "Your account is not older than {} days. Please ask shiku!",
+                                        time
+                                    ),
                                 ),
                             ),
-                        ),
-                    );
-                }
-                LoginError::TwitchApiError(actor_id, twitch_api_error) => {
-                    debug!(
-                        "Could not login user due to twitch api error {:?}",
-                        twitch_api_error
-                    );
-                    let sender = if guests.contains_key(&actor_id) {&mut system_to_guest_communication_sender} else {&mut system_to_admin_communication_sender};
-                    send_and_log_error(
-                        sender,
-                        (
-                            actor_id,
-                            CommunicationEvent::Toast(
-                                ToastAlertLevel::Error,
-                                "Could not login because of login error. Please ask shiku!"
-                                    .to_string(),
+                        );
+                    }
+                    LoginError::ProviderError(actor_id, login_provider_error) => {
+                        debug!(
+                            "Could not login user due to login provider error {:?}",
+                            login_provider_error
+                        );
+                        let sender = if guests.contains_key(&actor_id) {&mut system_to_guest_communication_sender} else {&mut system_to_admin_communication_sender};
+                        send_and_log_error(
+                            sender,
+                            (
+                                actor_id,
+                                CommunicationEvent::Toast(
+                                    ToastAlertLevel::Error,
+                                    "Could not login because of login error. Please ask shiku!"
+                                        .to_string(),
+                                ),
                             ),
-                        ),
-                    );
+                        );
+                    }
                 }
-            },
+            }
         })
     }
 
@@ -1207,6 +2144,9 @@ impl ConductorModule {
         result: Result<ActorId, HandleLoginError>,
     ) {
         error!("login result: {:?}", result);
+        if result.is_err() {
+            SERVER_METRICS.login_failures.fetch_add(1, Ordering::Relaxed);
+        }
         match result {
             Ok(actor_id) => {
                 debug!("handle login was successful for {}", actor_id);
@@ -1220,6 +2160,26 @@ impl ConductorModule {
 
                 debug!("Sending was successful? for {}", actor_id);
             }
+            Err(
+                HandleLoginError::Banned(actor_id, reason)
+                | HandleLoginError::GateClosed(actor_id, reason),
+            ) => {
+                debug!("Refused login of actor {}: {}", actor_id, reason);
+                send_and_log_error(
+                    sender,
+                    (
+                        actor_id,
+                        CommunicationEvent::Toast(ToastAlertLevel::Error, reason),
+                    ),
+                );
+                send_and_log_error(
+                    sender,
+                    (
+                        actor_id,
+                        CommunicationEvent::Signal(SignalToMedium::LoginFailed),
+                    ),
+                );
+            }
             Err(
                 HandleLoginError::CouldNotFind(actor_id)
                 | HandleLoginError::NotAuthorized(actor_id),
@@ -1237,18 +2197,31 @@ impl ConductorModule {
     }
 
     fn handle_actor_login<T: Actors + Debug, F: FnMut(&LoginData, &mut T)>(
-        provider_id_to_actor_map: &mut HashMap<ProviderUserId, Snowflake>,
-        websocket_module: &mut WebsocketModule,
+        context: LoginContext<'_>,
+        actor_maps: ActorMaps<'_, T>,
         login_data: &LoginData,
         actor_id: &Snowflake,
-        actors: &mut HashMap<Snowflake, T>,
-        ws_to_actor_map: &mut HashMap<Snowflake, Snowflake>,
-        session_to_actor_map: &mut HashMap<String, Snowflake>,
-        is_admin_login: bool,
+        admin_permissions: Option<&AdminPermissions>,
         mut login_success_cb: F,
     ) -> Result<ActorId, HandleLoginError> {
-        if is_admin_login && !Self::check_admin_login(login_data) {
-            return Err(HandleLoginError::NotAuthorized(*actor_id));
+        let LoginContext {
+            websocket_module,
+            session_tokens,
+            guest_bans,
+        } = context;
+        let ActorMaps {
+            actors,
+            provider_id_to_actor_map,
+            ws_to_actor_map,
+            session_to_actor_map,
+        } = actor_maps;
+        if let Some(ban) = guest_bans.active_ban(&login_data.provider_user_id) {
+            return Err(HandleLoginError::Banned(*actor_id, describe_ban(ban)));
+        }
+        if let Some(admin_permissions) = admin_permissions {
+            if !admin_permissions.can_login(&login_data.provider_user_id) {
+                return Err(HandleLoginError::NotAuthorized(*actor_id));
+            }
         }
 
         if let Some(already_logged_in_actor_id) =
@@ -1264,6 +2237,7 @@ impl ConductorModule {
                     if let Some(ws_connection_id) = already_logged_in_actor.get_ws_connection_id() {
                         ws_to_actor_map.remove(&ws_connection_id);
                         session_to_actor_map.remove(already_logged_in_actor.get_session_id());
+                        session_tokens.revoke(already_logged_in_actor.get_session_id());
                         debug!("going to close {}", ws_connection_id);
                         websocket_module.close_connection(
                             &ws_connection_id,
@@ -1312,20 +2286,26 @@ impl ConductorModule {
     }
 
     fn handle_guest_persistence(
-        persistence_module: &mut PersistenceModule,
+        persistence_module: &PersistenceModule,
         login_data: &LoginData,
-        guest: &mut Guest,
+        anonymous_provider_id: Option<ProviderUserId>,
+        guest: &Guest,
     ) -> Result<(), PersistenceError> {
-        let mut persisted_guest = persistence_module.lazy_get_persisted_guest_by_provider_id(
-            &login_data.provider_user_id,
-            &login_data.display_name,
-        )?;
-
-        Self::handle_times_joined(persistence_module, &mut persisted_guest)?;
-        guest.persisted_guest = Some(persisted_guest);
-
-        Ok(())
+        match anonymous_provider_id {
+            Some(anonymous_provider_id) => persistence_module.queue(PersistenceJob::LinkGuest {
+                guest_id: guest.id,
+                anonymous_provider_id,
+                provider_id: login_data.provider_user_id.clone(),
+                display_name: login_data.display_name.clone(),
+            }),
+            None => persistence_module.queue(PersistenceJob::LoadGuest {
+                guest_id: guest.id,
+                provider_id: login_data.provider_user_id.clone(),
+                display_name: login_data.display_name.clone(),
+            }),
+        }
     }
+
     fn send_system_events_to_guests(&mut self) {
         for (guest_id, communication_event) in self.system_to_guest_communication.receiver.drain() {
             debug!("Sending system event to guest? {:?}", communication_event);
@@ -1343,14 +2323,7 @@ impl ConductorModule {
     fn send_system_events_to_admins(&mut self) {
         for (admin_id, communication_event) in self.system_to_admin_communication.receiver.drain() {
             if let Some(admin) = self.admins.get(&admin_id) {
-                if let Ok(message_as_string) = serde_json::to_string(&communication_event) {
-                    Self::send_to_admin(admin, &mut self.websocket_module, message_as_string);
-                } else {
-                    error!(
-                        "{:?}",
-                        ProcessModuleEventError::CouldNotSerializeCommunicationEvent
-                    );
-                }
+                Self::send_to_admin(admin, &mut self.websocket_module, &communication_event);
             }
         }
     }
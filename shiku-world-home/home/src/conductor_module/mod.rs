@@ -2,7 +2,11 @@ pub mod def;
 pub mod errors;
 pub mod imp;
 
+pub mod admin_permissions;
 pub mod admin_to_system_events;
+pub mod audit_log;
+pub mod moderation;
+pub mod session_resume;
 
 pub mod blueprint_helper;
 
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+
+use crate::core::guest::{ActorId, GuestBan, ProviderUserId};
+
+/// Moderation that needs the guest itself is collected while admin events are handled
+/// and applied by the conductor afterwards. The `ActorId` is the admin to report back to.
+#[derive(Debug)]
+pub enum ModerationAction {
+    KickFromModule(ActorId, ProviderUserId),
+    KickFromServer(ActorId, ProviderUserId, String),
+    SetObserver(ActorId, ProviderUserId, bool),
+}
+
+pub struct GuestBans {
+    bans: HashMap<ProviderUserId, GuestBan>,
+}
+
+impl GuestBans {
+    pub fn new(bans: Vec<GuestBan>) -> GuestBans {
+        GuestBans {
+            bans: bans
+                .into_iter()
+                .map(|ban| (ban.provider_user_id.clone(), ban))
+                .collect(),
+        }
+    }
+
+    pub fn active_ban(&self, provider_user_id: &ProviderUserId) -> Option<&GuestBan> {
+        self.bans
+            .get(provider_user_id)
+            .filter(|ban| ban.is_active(Utc::now().timestamp()))
+    }
+
+    pub fn ban(&mut self, ban: GuestBan) {
+        self.bans.insert(ban.provider_user_id.clone(), ban);
+    }
+
+    pub fn unban(&mut self, provider_user_id: &ProviderUserId) -> bool {
+        self.bans.remove(provider_user_id).is_some()
+    }
+}
+
+pub fn describe_ban(ban: &GuestBan) -> String {
+    match ban
+        .banned_until
+        .and_then(|banned_until| NaiveDateTime::from_timestamp_opt(banned_until, 0))
+    {
+        Some(banned_until) => format!(
+            "You are banned until {} UTC: {}",
+            banned_until.format("%Y-%m-%d %H:%M"),
+            ban.reason
+        ),
+        None => format!("You are banned: {}", ban.reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_bans_are_not_active() {
+        let ban = |provider_user_id: &str, banned_until| GuestBan {
+            provider_user_id: provider_user_id.into(),
+            reason: "griefing".into(),
+            banned_until,
+        };
+        let now = Utc::now().timestamp();
+        let mut guest_bans = GuestBans::new(vec![
+            ban("expired", Some(now - 60)),
+            ban("temporary", Some(now + 60)),
+        ]);
+        guest_bans.ban(ban("permanent", None));
+
+        assert!(guest_bans.active_ban(&"expired".into()).is_none());
+        assert!(guest_bans.active_ban(&"temporary".into()).is_some());
+        assert_eq!(
+            describe_ban(guest_bans.active_ban(&"permanent".into()).unwrap()),
+            "You are banned: griefing"
+        );
+        assert!(guest_bans.unban(&"permanent".into()));
+        assert!(guest_bans.active_ban(&"permanent".into()).is_none());
+    }
+}
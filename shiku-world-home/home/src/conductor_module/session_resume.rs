@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::module::CommunicationEvent;
+
+/// About half a minute of a busy world, the same window `handle_timeouts` keeps guests for.
+pub const MAX_MISSED_EVENTS: usize = 4096;
+
+/// How a resumed guest catches up, sent right after `ConnectionReady`.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[ts(export)]
+pub enum SessionResume {
+    /// The missed events follow as they were sent.
+    Replay,
+    /// Too much was missed, the client drops its world state and gets it sent again.
+    Resync,
+}
+
+/// Game events sent to a guest while their connection was gone.
+#[derive(Debug, Default)]
+pub struct MissedEvents {
+    events: VecDeque<CommunicationEvent>,
+    overflowed: bool,
+}
+
+impl MissedEvents {
+    pub fn push(&mut self, event: CommunicationEvent) {
+        if self.overflowed {
+            return;
+        }
+        if self.events.len() >= MAX_MISSED_EVENTS {
+            self.overflowed = true;
+            self.events.clear();
+            return;
+        }
+        self.events.push_back(event);
+    }
+
+    /// `None` once more was missed than can be replayed.
+    pub fn into_replay(self) -> Option<VecDeque<CommunicationEvent>> {
+        if self.overflowed {
+            None
+        } else {
+            Some(self.events)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missed_events_are_dropped_on_overflow() {
+        let mut missed_events = MissedEvents::default();
+        missed_events.push(CommunicationEvent::AlreadyConnected);
+        assert_eq!(
+            missed_events.into_replay().map(|events| events.len()),
+            Some(1)
+        );
+
+        let mut missed_events = MissedEvents::default();
+        for _ in 0..=MAX_MISSED_EVENTS {
+            missed_events.push(CommunicationEvent::AlreadyConnected);
+        }
+        missed_events.push(CommunicationEvent::AlreadyConnected);
+        assert!(missed_events.into_replay().is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use ts_rs::TS;
+
+use crate::core::blueprint::def::ModuleId;
+use crate::core::guest::ProviderUserId;
+
+pub const DEFAULT_AUDIT_LOG_QUERY_SIZE: u32 = 50;
+pub const MAX_AUDIT_LOG_QUERY_SIZE: u32 = 200;
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub provider_user_id: ProviderUserId,
+    pub action: String,
+    pub module_ids: Vec<ModuleId>,
+    pub resource: Option<String>,
+    pub diff: String,
+    pub created_at: i64,
+}
+
+/// Entries come newest first, `before_id` pages further back.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Default)]
+#[ts(export)]
+pub struct AuditLogQuery {
+    pub module_id: Option<ModuleId>,
+    pub provider_user_id: Option<ProviderUserId>,
+    pub before_id: Option<i32>,
+    pub limit: Option<u32>,
+}
+
+impl AuditLogQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_AUDIT_LOG_QUERY_SIZE)
+            .min(MAX_AUDIT_LOG_QUERY_SIZE)
+    }
+
+    pub fn matches(&self, entry: &AuditLogEntry) -> bool {
+        self.module_id
+            .as_ref()
+            .is_none_or(|module_id| entry.module_ids.contains(module_id))
+            && self
+                .provider_user_id
+                .as_ref()
+                .is_none_or(|provider_user_id| &entry.provider_user_id == provider_user_id)
+            && self.before_id.is_none_or(|before_id| entry.id < before_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAuditLogEntry {
+    pub provider_user_id: ProviderUserId,
+    pub action: String,
+    pub module_ids: Vec<ModuleId>,
+    pub resource: Option<String>,
+    pub diff: JsonValue,
+}
+
+/// Module ids are stored as ",a,b," so a single `LIKE '%,a,%'` finds them.
+pub fn join_module_ids(module_ids: &[ModuleId]) -> String {
+    if module_ids.is_empty() {
+        return String::new();
+    }
+    format!(",{},", module_ids.join(","))
+}
+
+/// `LIKE` pattern for one of the joined module ids, wildcards in the id are escaped with `\`.
+pub fn module_id_like_pattern(module_id: &str) -> String {
+    let escaped = module_id
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%,{},%", escaped)
+}
+
+pub fn split_module_ids(module_ids: &str) -> Vec<ModuleId> {
+    module_ids
+        .split(',')
+        .filter(|module_id| !module_id.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Only what changed, objects and same sized arrays are followed down, everything else is
+/// recorded as `from`/`to`. Returns `Null` when nothing changed.
+pub fn json_diff(before: &JsonValue, after: &JsonValue) -> JsonValue {
+    if before == after {
+        return JsonValue::Null;
+    }
+    match (before, after) {
+        (JsonValue::Object(before), JsonValue::Object(after)) => {
+            let mut changes = Map::new();
+            for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key)))
+            {
+                let change = json_diff(
+                    before.get(key).unwrap_or(&JsonValue::Null),
+                    after.get(key).unwrap_or(&JsonValue::Null),
+                );
+                if !change.is_null() {
+                    changes.insert(key.clone(), change);
+                }
+            }
+            JsonValue::Object(changes)
+        }
+        (JsonValue::Array(before), JsonValue::Array(after)) if before.len() == after.len() => {
+            let mut changes = Map::new();
+            for (index, (before, after)) in before.iter().zip(after.iter()).enumerate() {
+                let change = json_diff(before, after);
+                if !change.is_null() {
+                    changes.insert(index.to_string(), change);
+                }
+            }
+            JsonValue::Object(changes)
+        }
+        (JsonValue::String(before), JsonValue::String(after))
+            if before.contains('\n') || after.contains('\n') =>
+        {
+            line_diff(before, after)
+        }
+        _ => json!({ "from": before, "to": after }),
+    }
+}
+
+fn line_diff(before: &str, after: &str) -> JsonValue {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    let common_prefix = before
+        .iter()
+        .zip(after.iter())
+        .take_while(|(before, after)| before == after)
+        .count();
+    let common_suffix = before[common_prefix..]
+        .iter()
+        .rev()
+        .zip(after[common_prefix..].iter().rev())
+        .take_while(|(before, after)| before == after)
+        .count();
+
+    json!({
+        "line": common_prefix + 1,
+        "removed": before[common_prefix..before.len() - common_suffix],
+        "added": after[common_prefix..after.len() - common_suffix],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_diff_keeps_only_changes() {
+        let before = json!({"name": "tower", "tiles": [1, 2, 3], "size": {"w": 1, "h": 2}});
+        let after = json!({"name": "tower", "tiles": [1, 5, 3], "size": {"w": 1, "h": 4}});
+
+        assert_eq!(
+            json_diff(&before, &after),
+            json!({"tiles": {"1": {"from": 2, "to": 5}}, "size": {"h": {"from": 2, "to": 4}}})
+        );
+        assert_eq!(json_diff(&before, &before), JsonValue::Null);
+        assert_eq!(
+            json_diff(&json!([1]), &json!([1, 2])),
+            json!({"from": [1], "to": [1, 2]})
+        );
+    }
+
+    #[test]
+    fn test_multiline_strings_are_diffed_by_line() {
+        assert_eq!(
+            json_diff(
+                &json!({"content": "let a = 1;\nlet b = 2;\nprint(a);"}),
+                &json!({"content": "let a = 1;\nlet b = 3;\nlet c = 4;\nprint(a);"}),
+            ),
+            json!({"content": {"line": 2, "removed": ["let b = 2;"], "added": ["let b = 3;", "let c = 4;"]}})
+        );
+    }
+
+    #[test]
+    fn test_module_ids_round_trip() {
+        let module_ids = vec!["castle".to_string(), "forest".to_string()];
+        assert_eq!(join_module_ids(&module_ids), ",castle,forest,");
+        assert_eq!(split_module_ids(&join_module_ids(&module_ids)), module_ids);
+        assert!(split_module_ids(&join_module_ids(&[])).is_empty());
+    }
+}
@@ -13,6 +13,7 @@ use walkdir::Error as WalkDirError;
 use crate::core::blueprint::scene::def::{CollisionShape, Scene, Script};
 use crate::core::guest::{ModuleEnterSlot, ModuleExitSlot};
 use crate::core::module::ModuleName;
+use crate::core::server_gate::ServerGate;
 
 pub type EntityId = usize;
 pub type JointId = usize;
@@ -23,6 +24,8 @@ pub struct Conductor {
     pub(crate) module_connection_map: HashMap<ModuleExitSlot, (ModuleId, ModuleEnterSlot)>,
     pub(crate) resources: Vec<BlueprintResource>,
     pub(crate) gid_map: GidMap,
+    #[serde(default)]
+    pub(crate) gates: Vec<ServerGate>,
 }
 
 impl Conductor {
@@ -31,6 +34,7 @@ impl Conductor {
             module_connection_map: HashMap::new(),
             resources: Vec::new(),
             gid_map: GidMap(Vec::new()),
+            gates: Vec::new(),
         }
     }
 }
@@ -163,6 +167,53 @@ pub struct IOPoint {
     pub condition_script: String,
 }
 
+pub type AchievementId = String;
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "blueprints/")]
+pub struct Achievement {
+    pub id: AchievementId,
+    pub title: String,
+    pub description: String,
+    pub icon: Option<ResourcePath>,
+    pub hidden: bool,
+}
+
+pub type LeaderboardId = String;
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export, export_to = "blueprints/")]
+pub enum LeaderboardSortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export, export_to = "blueprints/")]
+pub enum LeaderboardAggregation {
+    Best,
+    Latest,
+    Sum,
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export, export_to = "blueprints/")]
+pub enum LeaderboardResetPeriod {
+    None,
+    Daily,
+    Weekly,
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "blueprints/")]
+pub struct LeaderboardDefinition {
+    pub id: LeaderboardId,
+    pub title: String,
+    pub sort_order: LeaderboardSortOrder,
+    pub aggregation: LeaderboardAggregation,
+    pub reset_period: LeaderboardResetPeriod,
+}
+
 pub type ModuleId = String;
 
 pub type Gid = u32;
@@ -189,6 +240,10 @@ pub struct Module {
     pub max_guests: usize,
     pub min_guests: usize,
     pub close_after_full: bool,
+    #[serde(default)]
+    pub achievements: Vec<Achievement>,
+    #[serde(default)]
+    pub leaderboards: Vec<LeaderboardDefinition>,
 }
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
@@ -201,6 +256,8 @@ pub struct ModuleUpdate {
     pub main_map: Option<Option<ResourcePath>>,
     pub max_guests: Option<usize>,
     pub min_guests: Option<usize>,
+    pub achievements: Option<Vec<Achievement>>,
+    pub leaderboards: Option<Vec<LeaderboardDefinition>>,
 }
 
 impl ModuleUpdate {
@@ -213,6 +270,8 @@ impl ModuleUpdate {
             main_map: None,
             max_guests: None,
             min_guests: None,
+            achievements: None,
+            leaderboards: None,
         }
     }
 
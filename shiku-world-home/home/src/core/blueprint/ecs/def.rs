@@ -21,7 +21,19 @@ use crate::core::blueprint::scene::def::{
 };
 
 #[derive(
-    TS, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, CustomType,
+    TS,
+    Serialize,
+    Deserialize,
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    CustomType,
 )]
 #[ts(export, export_to = "blueprints/")]
 pub struct Entity(pub NodeInstanceId);
@@ -39,7 +51,8 @@ pub struct ECS {
     pub scene_resource_path: ResourcePath,
     pub scene_id: SceneId,
     pub entities: HashSet<Entity>,
-    pub entity_scripts: HashMap<Entity, GameNodeScript>,
+    /// Ordered, so scripts run in the same order every time a replay is played.
+    pub entity_scripts: BTreeMap<Entity, GameNodeScript>,
     pub processed_added_entities: Vec<Entity>,
     pub shared: ApiShare<ECSShared>,
 }
@@ -1,5 +1,5 @@
 use std::cell::{RefCell, RefMut};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use log::{debug, error};
 use rapier2d::dynamics::RigidBodyHandle;
@@ -47,7 +47,7 @@ impl ECS {
             scene_name: String::default(),
             scene_resource_path: ResourcePath::default(),
             scene_id: SceneId::default(),
-            entity_scripts: HashMap::new(),
+            entity_scripts: BTreeMap::new(),
             entities: HashSet::new(),
             processed_added_entities: Vec::new(),
             shared: ApiShare::new(ECSShared {
@@ -461,7 +461,7 @@ impl ECS {
     }
 
     pub fn apply_entity_update_s(
-        entity_scripts: &mut HashMap<Entity, GameNodeScript>,
+        entity_scripts: &mut BTreeMap<Entity, GameNodeScript>,
         shared: &mut ECSShared,
         physics: &mut RapierSimulation,
         entity_update: EntityUpdate,
@@ -31,6 +31,8 @@ impl Module {
             insert_points: Vec::new(),
             resources: Vec::new(),
             close_after_full: false,
+            achievements: Vec::new(),
+            leaderboards: Vec::new(),
         }
     }
 }
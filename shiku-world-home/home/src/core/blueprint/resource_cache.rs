@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::OnceLock;
 use std::sync::RwLock;
 
@@ -12,6 +13,7 @@ use crate::core::blueprint::def::{
 };
 use crate::core::blueprint::resource_loader::Blueprint;
 use crate::core::blueprint::scene::def::{Scene, Script};
+use crate::core::health::SERVER_HEALTH;
 use crate::core::{get_out_dir, safe_unwrap};
 
 pub struct ResourceCache {
@@ -37,15 +39,28 @@ pub fn get_resource_cache() -> &'static ResourceCache {
 }
 
 pub fn init_resource_cache() -> Result<(), BlueprintError> {
-    let resources = get_resource_cache();
     let root = get_out_dir();
     debug!("Root folder for resources is {:?}", root);
-    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-        let full_resource_path = entry.path();
+    cache_resources(&root, &root)?;
+
+    SERVER_HEALTH
+        .resource_cache_loaded
+        .store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Caches every resource below `dir` as if `root` was the out dir, so blueprints can be
+/// served from somewhere else, like test fixtures.
+pub fn cache_resources(dir: &Path, root: &Path) -> Result<(), BlueprintError> {
+    let resources = get_resource_cache();
+    let out_dir = get_out_dir();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        let file_path = entry.path();
+        let full_resource_path = out_dir.join(file_path.strip_prefix(root).unwrap_or(file_path));
         let file_name = safe_unwrap(entry.file_name().to_str(), BlueprintError::OsParsing)?;
         match BlueprintService::determine_file_type(file_name) {
             FileBrowserFileKind::Scene => {
-                let scene = Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let scene = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .scenes
                     .write()
@@ -54,7 +69,7 @@ pub fn init_resource_cache() -> Result<(), BlueprintError> {
                 debug!("Successfully loaded {:?}", full_resource_path.display());
             }
             FileBrowserFileKind::Tileset => {
-                let tileset = Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let tileset = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .tilesets
                     .write()
@@ -63,7 +78,7 @@ pub fn init_resource_cache() -> Result<(), BlueprintError> {
                 debug!("Successfully loaded {:?}", full_resource_path.display());
             }
             FileBrowserFileKind::Map => {
-                let map = Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let map = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .maps
                     .write()
@@ -72,7 +87,7 @@ pub fn init_resource_cache() -> Result<(), BlueprintError> {
                 debug!("Successfully loaded {:?}", full_resource_path.display());
             }
             FileBrowserFileKind::Module => {
-                let module = Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let module = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .modules
                     .write()
@@ -81,7 +96,7 @@ pub fn init_resource_cache() -> Result<(), BlueprintError> {
                 debug!("Successfully loaded {:?}", full_resource_path.display());
             }
             FileBrowserFileKind::Script => {
-                let script = Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let script = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .scripts
                     .write()
@@ -90,8 +105,7 @@ pub fn init_resource_cache() -> Result<(), BlueprintError> {
                 debug!("Successfully loaded {:?}", full_resource_path.display());
             }
             FileBrowserFileKind::CharacterAnimation => {
-                let character_animation =
-                    Blueprint::load_from_file(PathBuf::from(full_resource_path))?;
+                let character_animation = Blueprint::load_from_file(PathBuf::from(file_path))?;
                 resources
                     .character_animations
                     .write()
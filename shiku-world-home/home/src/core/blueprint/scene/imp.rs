@@ -1,6 +1,6 @@
 use crate::core::blueprint::def::JsonResource;
 use log::{debug, error};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::core::blueprint::ecs::def::{ECSShared, Entity, EntityUpdateKind, ECS};
 use crate::core::blueprint::ecs::game_node_script::GameNodeScript;
@@ -251,7 +251,7 @@ impl GameNodeKind {
 
     fn _get_game_node_kind_from_ecs(
         original_entity: &Entity,
-        entity_scripts: &HashMap<Entity, GameNodeScript>,
+        entity_scripts: &BTreeMap<Entity, GameNodeScript>,
         shared: &ECSShared,
     ) -> Option<GameNodeKind> {
         let mut possible_instance_root = original_entity;
@@ -1,13 +1,19 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
+use crate::core::blueprint::def::ModuleId;
 use crate::core::module::ModuleName;
 use crate::core::module_system::game_instance::GameInstanceId;
+use crate::core::time_sync::RoundTripTimer;
 use crate::core::Snowflake;
 use crate::persistence_module::models::PersistedGuest;
 
 pub type SessionId = String;
+pub type SessionToken = String;
 pub type ProviderUserId = String;
+pub type GuestToken = String;
 pub type ModuleExitSlot = String;
 pub type ModuleEnterSlot = String;
 pub type ActorId = Snowflake;
@@ -21,6 +27,8 @@ pub struct Guest {
     pub login_data: Option<LoginData>,
     pub ws_connection_id: Option<Snowflake>,
     pub persisted_guest: Option<PersistedGuest>,
+    pub round_trip_time: Option<u32>,
+    pub round_trip_timer: RoundTripTimer,
 }
 
 #[derive(Debug)]
@@ -114,9 +122,71 @@ pub struct LoginData {
     pub provider: LoginProvider,
 }
 
-#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[ts(export)]
 pub enum LoginProvider {
     Twitch,
     Google,
+    Oidc,
+    Local,
+    Anonymous,
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[ts(export)]
+pub enum AdminRole {
+    Owner,
+    Editor,
+    Scripter,
+    Moderator,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Owner => "owner",
+            AdminRole::Editor => "editor",
+            AdminRole::Scripter => "scripter",
+            AdminRole::Moderator => "moderator",
+        }
+    }
+}
+
+impl FromStr for AdminRole {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<AdminRole, String> {
+        match role {
+            "owner" => Ok(AdminRole::Owner),
+            "editor" => Ok(AdminRole::Editor),
+            "scripter" => Ok(AdminRole::Scripter),
+            "moderator" => Ok(AdminRole::Moderator),
+            _ => Err(format!("Unknown admin role {}", role)),
+        }
+    }
+}
+
+/// A role for one module, or for all of them when `module_id` is `None`.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[ts(export)]
+pub struct AdminRoleGrant {
+    pub provider_user_id: ProviderUserId,
+    pub role: AdminRole,
+    pub module_id: Option<ModuleId>,
+}
+
+/// `banned_until` is a unix timestamp in seconds, bans without one are permanent.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export)]
+pub struct GuestBan {
+    pub provider_user_id: ProviderUserId,
+    pub reason: String,
+    pub banned_until: Option<i64>,
+}
+
+impl GuestBan {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.banned_until
+            .is_none_or(|banned_until| banned_until > now)
+    }
 }
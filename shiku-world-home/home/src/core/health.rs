@@ -0,0 +1,203 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::core::guest::LoginProvider;
+use crate::login::provider::ProviderTokenStatus;
+
+/// A conductor that did not tick for this long is stuck and the process should be restarted.
+pub const MAX_LIVE_TICK_AGE: Duration = Duration::from_secs(10);
+pub const MAX_READY_TICK_AGE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub last_tick_age_ms: Option<u64>,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    fn new(last_tick_age: Option<Duration>, checks: Vec<HealthCheck>) -> HealthReport {
+        HealthReport {
+            healthy: checks.iter().all(|check| check.healthy),
+            last_tick_age_ms: last_tick_age.map(|age| age.as_millis() as u64),
+            checks,
+        }
+    }
+}
+
+pub struct ServerHealth {
+    conductor_ticked_at: AtomicU64,
+    pub websocket_listening: AtomicBool,
+    pub resource_cache_loaded: AtomicBool,
+    login_provider_tokens: Mutex<Vec<(LoginProvider, ProviderTokenStatus)>>,
+}
+
+pub static SERVER_HEALTH: ServerHealth = ServerHealth::new();
+
+fn now_in_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl ServerHealth {
+    pub const fn new() -> ServerHealth {
+        ServerHealth {
+            conductor_ticked_at: AtomicU64::new(0),
+            websocket_listening: AtomicBool::new(false),
+            resource_cache_loaded: AtomicBool::new(false),
+            login_provider_tokens: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn mark_conductor_tick(&self) {
+        self.conductor_ticked_at
+            .store(now_in_millis(), Ordering::Relaxed);
+    }
+
+    pub fn conductor_tick_age(&self) -> Option<Duration> {
+        match self.conductor_ticked_at.load(Ordering::Relaxed) {
+            0 => None,
+            ticked_at => Some(Duration::from_millis(
+                now_in_millis().saturating_sub(ticked_at),
+            )),
+        }
+    }
+
+    pub fn set_login_provider_tokens(&self, tokens: Vec<(LoginProvider, ProviderTokenStatus)>) {
+        *self
+            .login_provider_tokens
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = tokens;
+    }
+
+    /// Only fails when restarting the process is the way out. A conductor that has not
+    /// ticked yet is still starting up.
+    pub fn liveness(&self) -> HealthReport {
+        let last_tick_age = self.conductor_tick_age();
+        HealthReport::new(
+            last_tick_age,
+            vec![conductor_check(last_tick_age, MAX_LIVE_TICK_AGE, true)],
+        )
+    }
+
+    pub fn readiness(&self, persistence: Result<(), String>) -> HealthReport {
+        let last_tick_age = self.conductor_tick_age();
+        let login_provider_tokens = self
+            .login_provider_tokens
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        HealthReport::new(
+            last_tick_age,
+            vec![
+                conductor_check(last_tick_age, MAX_READY_TICK_AGE, false),
+                HealthCheck {
+                    name: "persistence",
+                    healthy: persistence.is_ok(),
+                    detail: persistence.err().unwrap_or_else(|| "reachable".into()),
+                },
+                flag_check(
+                    "websocket",
+                    self.websocket_listening.load(Ordering::Relaxed),
+                    "listening",
+                    "not listening",
+                ),
+                flag_check(
+                    "resource_cache",
+                    self.resource_cache_loaded.load(Ordering::Relaxed),
+                    "loaded",
+                    "not loaded",
+                ),
+                HealthCheck {
+                    name: "login_provider_tokens",
+                    healthy: login_provider_tokens
+                        .iter()
+                        .all(|(_, token_status)| token_status.is_healthy()),
+                    detail: login_provider_tokens
+                        .iter()
+                        .map(|(login_provider, token_status)| {
+                            format!("{:?}: {:?}", login_provider, token_status)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                },
+            ],
+        )
+    }
+}
+
+fn conductor_check(
+    last_tick_age: Option<Duration>,
+    max_tick_age: Duration,
+    healthy_when_not_started: bool,
+) -> HealthCheck {
+    match last_tick_age {
+        Some(last_tick_age) => HealthCheck {
+            name: "conductor",
+            healthy: last_tick_age <= max_tick_age,
+            detail: format!("last tick {}ms ago", last_tick_age.as_millis()),
+        },
+        None => HealthCheck {
+            name: "conductor",
+            healthy: healthy_when_not_started,
+            detail: "not started yet".into(),
+        },
+    }
+}
+
+fn flag_check(name: &'static str, healthy: bool, up: &str, down: &str) -> HealthCheck {
+    HealthCheck {
+        name,
+        healthy,
+        detail: if healthy { up } else { down }.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_needs_every_subsystem_but_liveness_only_a_ticking_conductor() {
+        let health = ServerHealth::new();
+        assert!(health.liveness().healthy);
+        assert!(!health.readiness(Ok(())).healthy);
+
+        health.mark_conductor_tick();
+        health.websocket_listening.store(true, Ordering::Relaxed);
+        health.resource_cache_loaded.store(true, Ordering::Relaxed);
+        health.set_login_provider_tokens(vec![(
+            LoginProvider::Twitch,
+            ProviderTokenStatus::NotFetched,
+        )]);
+        assert!(health.readiness(Ok(())).healthy);
+
+        let report = health.readiness(Err("connection refused".into()));
+        assert!(!report.healthy);
+        assert!(health.liveness().healthy);
+        let failed: Vec<&str> = report
+            .checks
+            .iter()
+            .filter(|check| !check.healthy)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(failed, vec!["persistence"]);
+
+        health.set_login_provider_tokens(vec![(
+            LoginProvider::Twitch,
+            ProviderTokenStatus::RefreshFailed,
+        )]);
+        assert!(!health.readiness(Ok(())).healthy);
+    }
+}
@@ -0,0 +1,490 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::blueprint::def::{
+    LeaderboardAggregation, LeaderboardDefinition, LeaderboardId, LeaderboardResetPeriod,
+    LeaderboardSortOrder, ModuleId,
+};
+
+pub const DEFAULT_LEADERBOARD_QUERY_SIZE: usize = 10;
+pub const MAX_LEADERBOARD_QUERY_SIZE: usize = 100;
+
+pub type LeaderboardMap = Arc<Mutex<HashMap<ModuleId, HashMap<LeaderboardId, Leaderboard>>>>;
+
+pub fn lock_leaderboards(
+    leaderboards: &LeaderboardMap,
+) -> MutexGuard<'_, HashMap<ModuleId, HashMap<LeaderboardId, Leaderboard>>> {
+    leaderboards
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export)]
+pub struct LeaderboardEntry {
+    pub persisted_guest_state_id: i32,
+    pub display_name: String,
+    pub score: f64,
+    pub updated_at: i64,
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[ts(export)]
+pub struct LeaderboardSnapshot {
+    pub id: LeaderboardId,
+    pub title: String,
+    pub period_start: i64,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+pub fn period_start(reset_period: &LeaderboardResetPeriod, now: NaiveDateTime) -> NaiveDateTime {
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    match reset_period {
+        LeaderboardResetPeriod::None => NaiveDateTime::default(),
+        LeaderboardResetPeriod::Daily => midnight(now.date()),
+        LeaderboardResetPeriod::Weekly => {
+            midnight(now.date() - Duration::days(now.weekday().num_days_from_monday() as i64))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Leaderboard {
+    pub definition: LeaderboardDefinition,
+    pub period_start: NaiveDateTime,
+    entries: Vec<LeaderboardEntry>,
+    loaded: bool,
+}
+
+impl Leaderboard {
+    pub fn new(
+        definition: LeaderboardDefinition,
+        period_start: NaiveDateTime,
+        entries: Vec<LeaderboardEntry>,
+    ) -> Leaderboard {
+        let mut leaderboard = Leaderboard {
+            definition,
+            period_start,
+            entries,
+            loaded: true,
+        };
+        leaderboard.sort_entries();
+        leaderboard
+    }
+
+    /// A board whose persisted entries are still being loaded. Scores submitted until then are
+    /// only kept in memory and get combined with the persisted ones in `merge_loaded_entries`.
+    pub fn loading(definition: LeaderboardDefinition, period_start: NaiveDateTime) -> Leaderboard {
+        Leaderboard {
+            loaded: false,
+            ..Leaderboard::new(definition, period_start, Vec::new())
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    pub fn set_definition(&mut self, definition: LeaderboardDefinition) {
+        self.definition = definition;
+        self.sort_entries();
+    }
+
+    pub fn roll_over(&mut self, now: NaiveDateTime) -> bool {
+        let current_period_start = period_start(&self.definition.reset_period, now);
+        if current_period_start == self.period_start {
+            return false;
+        }
+        self.period_start = current_period_start;
+        self.entries.clear();
+        true
+    }
+
+    pub fn submit(
+        &mut self,
+        persisted_guest_state_id: i32,
+        display_name: String,
+        score: f64,
+        now: NaiveDateTime,
+    ) -> Option<LeaderboardEntry> {
+        if !score.is_finite() {
+            return None;
+        }
+        self.roll_over(now);
+        let sort_order = self.definition.sort_order.clone();
+        match self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.persisted_guest_state_id == persisted_guest_state_id)
+        {
+            Some(entry) => {
+                entry.score = match self.definition.aggregation {
+                    LeaderboardAggregation::Best => {
+                        if Self::compare_scores(&sort_order, score, entry.score) != Ordering::Less {
+                            return None;
+                        }
+                        score
+                    }
+                    LeaderboardAggregation::Latest => score,
+                    LeaderboardAggregation::Sum => entry.score + score,
+                };
+                entry.display_name = display_name;
+                entry.updated_at = now.timestamp();
+            }
+            None => self.entries.push(LeaderboardEntry {
+                persisted_guest_state_id,
+                display_name,
+                score,
+                updated_at: now.timestamp(),
+            }),
+        }
+        self.sort_entries();
+
+        self.entries
+            .iter()
+            .find(|entry| entry.persisted_guest_state_id == persisted_guest_state_id)
+            .cloned()
+    }
+
+    /// Returns the entries that differ from the persisted ones and still have to be upserted.
+    pub fn merge_loaded_entries(
+        &mut self,
+        period_start: NaiveDateTime,
+        entries: Vec<LeaderboardEntry>,
+    ) -> Vec<LeaderboardEntry> {
+        if self.loaded {
+            return Vec::new();
+        }
+        self.loaded = true;
+        if period_start != self.period_start {
+            return self.entries.clone();
+        }
+
+        let mut changed: Vec<i32> = self
+            .entries
+            .iter()
+            .map(|entry| entry.persisted_guest_state_id)
+            .collect();
+        for loaded_entry in entries {
+            let Some(entry) = self.entries.iter_mut().find(|entry| {
+                entry.persisted_guest_state_id == loaded_entry.persisted_guest_state_id
+            }) else {
+                self.entries.push(loaded_entry);
+                continue;
+            };
+            match self.definition.aggregation {
+                LeaderboardAggregation::Best => {
+                    if Self::compare_scores(
+                        &self.definition.sort_order,
+                        entry.score,
+                        loaded_entry.score,
+                    ) != Ordering::Less
+                    {
+                        *entry = loaded_entry;
+                        changed.retain(|id| *id != entry.persisted_guest_state_id);
+                    }
+                }
+                LeaderboardAggregation::Latest => (),
+                LeaderboardAggregation::Sum => entry.score += loaded_entry.score,
+            }
+        }
+        self.sort_entries();
+
+        self.entries
+            .iter()
+            .filter(|entry| changed.contains(&entry.persisted_guest_state_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Follows a guest whose progress moved to another persisted state. An entry the target
+    /// already has wins, same as when the states are merged in persistence.
+    pub fn reassign(
+        &mut self,
+        from_persisted_guest_state_id: i32,
+        to_persisted_guest_state_id: i32,
+        display_name: &str,
+    ) {
+        if from_persisted_guest_state_id != to_persisted_guest_state_id
+            && self.rank(to_persisted_guest_state_id).is_some()
+        {
+            self.entries
+                .retain(|entry| entry.persisted_guest_state_id != from_persisted_guest_state_id);
+        }
+        for entry in self.entries.iter_mut().filter(|entry| {
+            entry.persisted_guest_state_id == from_persisted_guest_state_id
+                || entry.persisted_guest_state_id == to_persisted_guest_state_id
+        }) {
+            entry.persisted_guest_state_id = to_persisted_guest_state_id;
+            entry.display_name = display_name.to_string();
+        }
+    }
+
+    pub fn top(&self, count: usize) -> &[LeaderboardEntry] {
+        &self.entries[..count.min(self.entries.len())]
+    }
+
+    pub fn rank(&self, persisted_guest_state_id: i32) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.persisted_guest_state_id == persisted_guest_state_id)
+            .map(|index| index + 1)
+    }
+
+    pub fn snapshot(&self, count: usize) -> LeaderboardSnapshot {
+        LeaderboardSnapshot {
+            id: self.definition.id.clone(),
+            title: self.definition.title.clone(),
+            period_start: self.period_start.timestamp(),
+            entries: self.top(count).to_vec(),
+        }
+    }
+
+    fn compare_scores(sort_order: &LeaderboardSortOrder, a: f64, b: f64) -> Ordering {
+        let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        match sort_order {
+            LeaderboardSortOrder::Ascending => ordering,
+            LeaderboardSortOrder::Descending => ordering.reverse(),
+        }
+    }
+
+    fn sort_entries(&mut self) {
+        let sort_order = self.definition.sort_order.clone();
+        self.entries.sort_by(|a, b| {
+            Self::compare_scores(&sort_order, a.score, b.score)
+                .then(a.updated_at.cmp(&b.updated_at))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(
+        sort_order: LeaderboardSortOrder,
+        aggregation: LeaderboardAggregation,
+        reset_period: LeaderboardResetPeriod,
+    ) -> LeaderboardDefinition {
+        LeaderboardDefinition {
+            id: "board".into(),
+            title: "Board".into(),
+            sort_order,
+            aggregation,
+            reset_period,
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2024-01-03 is a Wednesday.
+        assert_eq!(
+            period_start(&LeaderboardResetPeriod::None, at(3, 15)),
+            NaiveDateTime::default()
+        );
+        assert_eq!(
+            period_start(&LeaderboardResetPeriod::Daily, at(3, 15)),
+            at(3, 0)
+        );
+        assert_eq!(
+            period_start(&LeaderboardResetPeriod::Weekly, at(3, 15)),
+            at(1, 0)
+        );
+        assert_eq!(
+            period_start(&LeaderboardResetPeriod::Weekly, at(7, 23)),
+            at(1, 0)
+        );
+    }
+
+    #[test]
+    fn test_best_respects_sort_order() {
+        let mut fastest = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Ascending,
+                LeaderboardAggregation::Best,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        assert!(fastest.submit(1, "a".into(), 30.0, at(1, 0)).is_some());
+        assert!(fastest.submit(1, "a".into(), 40.0, at(1, 1)).is_none());
+        assert_eq!(
+            fastest.submit(1, "a".into(), 20.0, at(1, 2)).unwrap().score,
+            20.0
+        );
+        fastest.submit(2, "b".into(), 25.0, at(1, 3));
+        assert_eq!(fastest.rank(1), Some(1));
+        assert_eq!(fastest.rank(2), Some(2));
+
+        let mut highest = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Best,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        highest.submit(1, "a".into(), 30.0, at(1, 0));
+        highest.submit(2, "b".into(), 50.0, at(1, 1));
+        assert!(highest.submit(2, "b".into(), 40.0, at(1, 2)).is_none());
+        assert_eq!(highest.top(1)[0].persisted_guest_state_id, 2);
+    }
+
+    #[test]
+    fn test_sum_and_latest() {
+        let mut sum = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Sum,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        sum.submit(1, "a".into(), 3.0, at(1, 0));
+        assert_eq!(sum.submit(1, "a".into(), 4.0, at(1, 1)).unwrap().score, 7.0);
+
+        let mut latest = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Latest,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        latest.submit(1, "a".into(), 10.0, at(1, 0));
+        assert_eq!(
+            latest.submit(1, "a".into(), 2.0, at(1, 1)).unwrap().score,
+            2.0
+        );
+        assert!(latest.submit(1, "a".into(), f64::NAN, at(1, 2)).is_none());
+    }
+
+    #[test]
+    fn test_ties_are_ranked_by_submission_time() {
+        let mut leaderboard = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Best,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        leaderboard.submit(2, "b".into(), 10.0, at(1, 1));
+        leaderboard.submit(1, "a".into(), 10.0, at(1, 0));
+        assert_eq!(leaderboard.rank(1), Some(1));
+        assert_eq!(leaderboard.rank(2), Some(2));
+        assert_eq!(leaderboard.rank(3), None);
+    }
+
+    #[test]
+    fn test_reassign_keeps_existing_target_entry() {
+        let mut leaderboard = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Best,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+            Vec::new(),
+        );
+        leaderboard.submit(1, "Guest-1".into(), 10.0, at(1, 0));
+        leaderboard.submit(2, "b".into(), 5.0, at(1, 1));
+        leaderboard.reassign(1, 3, "c");
+        assert_eq!(leaderboard.rank(1), None);
+        assert_eq!(leaderboard.top(1)[0].display_name, "c");
+
+        leaderboard.reassign(3, 2, "b");
+        assert_eq!(leaderboard.rank(3), None);
+        assert_eq!(leaderboard.top(1)[0].score, 5.0);
+    }
+
+    #[test]
+    fn test_scores_submitted_while_loading_keep_the_persisted_best() {
+        let persisted = |persisted_guest_state_id: i32, score: f64| LeaderboardEntry {
+            persisted_guest_state_id,
+            display_name: "a".into(),
+            score,
+            updated_at: 0,
+        };
+        let mut best = Leaderboard::loading(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Best,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+        );
+        assert!(best.submit(1, "a".into(), 5.0, at(1, 0)).is_some());
+        assert!(best.submit(2, "b".into(), 20.0, at(1, 0)).is_some());
+        assert!(!best.is_loaded());
+        let to_upsert = best.merge_loaded_entries(
+            NaiveDateTime::default(),
+            vec![persisted(1, 10.0), persisted(2, 15.0), persisted(3, 1.0)],
+        );
+        assert!(best.is_loaded());
+        assert_eq!(to_upsert.len(), 1);
+        assert_eq!(to_upsert[0].persisted_guest_state_id, 2);
+        assert_eq!(to_upsert[0].score, 20.0);
+        assert_eq!(best.top(3)[1].persisted_guest_state_id, 1);
+        assert_eq!(best.top(3)[1].score, 10.0);
+        assert_eq!(best.rank(3), Some(3));
+
+        let mut sum = Leaderboard::loading(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Sum,
+                LeaderboardResetPeriod::None,
+            ),
+            NaiveDateTime::default(),
+        );
+        sum.submit(1, "a".into(), 5.0, at(1, 0));
+        let to_upsert =
+            sum.merge_loaded_entries(NaiveDateTime::default(), vec![persisted(1, 10.0)]);
+        assert_eq!(to_upsert[0].score, 15.0);
+        assert!(sum
+            .merge_loaded_entries(NaiveDateTime::default(), vec![persisted(1, 10.0)])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_roll_over_clears_entries() {
+        let mut leaderboard = Leaderboard::new(
+            definition(
+                LeaderboardSortOrder::Descending,
+                LeaderboardAggregation::Sum,
+                LeaderboardResetPeriod::Daily,
+            ),
+            at(1, 0),
+            Vec::new(),
+        );
+        leaderboard.submit(1, "a".into(), 5.0, at(1, 12));
+        assert!(!leaderboard.roll_over(at(1, 23)));
+        assert!(leaderboard.roll_over(at(2, 1)));
+        assert_eq!(leaderboard.period_start, at(2, 0));
+        assert_eq!(leaderboard.rank(1), None);
+        assert_eq!(
+            leaderboard
+                .submit(1, "a".into(), 5.0, at(2, 2))
+                .unwrap()
+                .score,
+            5.0
+        );
+    }
+}
@@ -2,10 +2,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use ts_rs::TS;
 
+use crate::core::blueprint::def::{AchievementId, LeaderboardId, ModuleId};
+use crate::core::leaderboard::LeaderboardSnapshot;
+use crate::persistence_module::models::PersistedGuest;
+
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
 #[ts(export)]
 pub struct MediumDataStorage {
     pub current_guest_info: Option<MediumDataStorageGuestInfo>,
+    pub leaderboards: HashMap<LeaderboardId, LeaderboardSnapshot>,
 }
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
@@ -16,6 +21,51 @@ pub struct MediumDataStorageGuestInfo {
     pub times_joined: i32,
     pub secrets_found_count: i32,
     pub secrets_found_map: HashMap<String, SecretFoundEntry>,
+    pub achievements_unlocked_count: i32,
+    pub achievements_unlocked_map: HashMap<ModuleId, HashMap<AchievementId, AchievementEntry>>,
+}
+
+impl MediumDataStorageGuestInfo {
+    pub fn new(persisted_guest: &PersistedGuest, guests_online: i32) -> MediumDataStorageGuestInfo {
+        let mut achievements_unlocked_map: HashMap<
+            ModuleId,
+            HashMap<AchievementId, AchievementEntry>,
+        > = HashMap::new();
+        for unlocked_achievement in &persisted_guest.achievements_unlocked {
+            achievements_unlocked_map
+                .entry(unlocked_achievement.module_id.clone())
+                .or_default()
+                .insert(
+                    unlocked_achievement.achievement_id.clone(),
+                    AchievementEntry {
+                        id: unlocked_achievement.achievement_id.clone(),
+                        date: unlocked_achievement.unlocked_at.timestamp(),
+                    },
+                );
+        }
+
+        MediumDataStorageGuestInfo {
+            guests_online,
+            guest_name: persisted_guest.info.display_name.clone(),
+            times_joined: persisted_guest.info.times_joined,
+            secrets_found_count: persisted_guest.secrets_found.len() as i32,
+            secrets_found_map: persisted_guest
+                .secrets_found
+                .iter()
+                .map(|secret| {
+                    (
+                        secret.name.clone(),
+                        SecretFoundEntry {
+                            name: secret.name.clone(),
+                            date: secret.date.timestamp(),
+                        },
+                    )
+                })
+                .collect(),
+            achievements_unlocked_count: persisted_guest.achievements_unlocked.len() as i32,
+            achievements_unlocked_map,
+        }
+    }
 }
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
@@ -24,3 +74,10 @@ pub struct SecretFoundEntry {
     pub name: String,
     pub date: i64,
 }
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[ts(export)]
+pub struct AchievementEntry {
+    pub id: AchievementId,
+    pub date: i64,
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::core::blueprint::def::ModuleId;
+use crate::core::module_system::game_instance::GameInstanceId;
+use crate::core::module_system::world::WorldId;
+use crate::websocket_module::WEBSOCKET_METRICS;
+
+pub const TICK_DURATION_BUCKETS: [f64; 9] =
+    [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.0166, 0.025, 0.05, 0.1];
+pub const PERSISTENCE_LATENCY_BUCKETS: [f64; 10] =
+    [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub const fn new(bounds: &'static [f64]) -> Histogram {
+        Histogram {
+            bounds,
+            counts: Vec::new(),
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if self.counts.is_empty() {
+            self.counts = vec![0; self.bounds.len()];
+        }
+        if let Some(bucket) = self.bounds.iter().position(|bound| seconds <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (i, bound) in self.bounds.iter().enumerate() {
+            cumulative += self.counts.get(i).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}le=\"+Inf\"}} {}",
+            name, labels, self.count
+        );
+        let labels = labels.trim_end_matches(',');
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+/// Gauges are replaced as a whole whenever the conductor refreshes them.
+#[derive(Debug)]
+pub struct ModuleMetrics {
+    pub instances: BTreeMap<ModuleId, usize>,
+    pub entities: BTreeMap<(ModuleId, GameInstanceId, WorldId), usize>,
+    pub tick_durations: BTreeMap<ModuleId, Histogram>,
+}
+
+pub struct ServerMetrics {
+    pub connected_guests: AtomicU64,
+    pub connected_admins: AtomicU64,
+    pub login_failures: AtomicU64,
+    pub modules: Mutex<ModuleMetrics>,
+    pub persistence_latency: Mutex<Histogram>,
+}
+
+pub static SERVER_METRICS: ServerMetrics = ServerMetrics {
+    connected_guests: AtomicU64::new(0),
+    connected_admins: AtomicU64::new(0),
+    login_failures: AtomicU64::new(0),
+    modules: Mutex::new(ModuleMetrics {
+        instances: BTreeMap::new(),
+        entities: BTreeMap::new(),
+        tick_durations: BTreeMap::new(),
+    }),
+    persistence_latency: Mutex::new(Histogram::new(&PERSISTENCE_LATENCY_BUCKETS)),
+};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl ServerMetrics {
+    pub fn lock_modules(&self) -> MutexGuard<'_, ModuleMetrics> {
+        lock(&self.modules)
+    }
+
+    pub fn observe_persistence_latency(&self, duration: Duration) {
+        lock(&self.persistence_latency).observe(duration);
+    }
+
+    /// Renders everything in the prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "shiku_connected_guests",
+                "gauge",
+                "Guests with an open websocket connection.",
+                &self.connected_guests,
+            ),
+            (
+                "shiku_connected_admins",
+                "gauge",
+                "Admins with an open websocket connection.",
+                &self.connected_admins,
+            ),
+            (
+                "shiku_login_failures_total",
+                "counter",
+                "Logins that were rejected or failed at the provider.",
+                &self.login_failures,
+            ),
+            (
+                "shiku_websocket_bytes_in_total",
+                "counter",
+                "Bytes read from websocket connections.",
+                &WEBSOCKET_METRICS.bytes_in,
+            ),
+            (
+                "shiku_websocket_bytes_out_total",
+                "counter",
+                "Bytes queued for websocket connections.",
+                &WEBSOCKET_METRICS.bytes_out,
+            ),
+            (
+                "shiku_websocket_dropped_rate_limited_total",
+                "counter",
+                "Inbound messages dropped by the rate limiter.",
+                &WEBSOCKET_METRICS.dropped_rate_limited,
+            ),
+            (
+                "shiku_websocket_dropped_oversized_total",
+                "counter",
+                "Inbound messages dropped for exceeding the frame size.",
+                &WEBSOCKET_METRICS.dropped_oversized,
+            ),
+            (
+                "shiku_websocket_dropped_outbound_total",
+                "counter",
+                "Outbound messages dropped because the connection did not keep up.",
+                &WEBSOCKET_METRICS.dropped_outbound,
+            ),
+            (
+                "shiku_websocket_closed_for_abuse_total",
+                "counter",
+                "Connections closed after too many strikes.",
+                &WEBSOCKET_METRICS.closed_for_abuse,
+            ),
+        ];
+        for (name, kind, help, value) in counters {
+            render_header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+
+        {
+            let modules = self.lock_modules();
+            render_header(
+                &mut out,
+                "shiku_game_instances",
+                "gauge",
+                "Running game instances per module.",
+            );
+            for (module_id, instances) in &modules.instances {
+                let _ = writeln!(
+                    out,
+                    "shiku_game_instances{{module=\"{}\"}} {}",
+                    escape_label(module_id),
+                    instances
+                );
+            }
+            render_header(
+                &mut out,
+                "shiku_world_entities",
+                "gauge",
+                "Entities per world.",
+            );
+            for ((module_id, instance_id, world_id), entities) in &modules.entities {
+                let _ = writeln!(
+                    out,
+                    "shiku_world_entities{{module=\"{}\",instance=\"{}\",world=\"{}\"}} {}",
+                    escape_label(module_id),
+                    escape_label(instance_id),
+                    escape_label(world_id),
+                    entities
+                );
+            }
+            render_header(
+                &mut out,
+                "shiku_tick_duration_seconds",
+                "histogram",
+                "Time it takes to update all instances of a module.",
+            );
+            for (module_id, histogram) in &modules.tick_durations {
+                histogram.render(
+                    &mut out,
+                    "shiku_tick_duration_seconds",
+                    &format!("module=\"{}\",", escape_label(module_id)),
+                );
+            }
+        }
+
+        render_header(
+            &mut out,
+            "shiku_persistence_latency_seconds",
+            "histogram",
+            "Time persistence jobs take including retries.",
+        );
+        lock(&self.persistence_latency).render(&mut out, "shiku_persistence_latency_seconds", "");
+        out
+    }
+}
+
+fn render_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_renders_cumulative_buckets() {
+        let mut histogram = Histogram::new(&[0.25, 1.0]);
+        histogram.observe(Duration::from_millis(125));
+        histogram.observe(Duration::from_millis(500));
+        histogram.observe(Duration::from_secs(2));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "tick", "module=\"a\",");
+
+        assert_eq!(
+            out,
+            "tick_bucket{module=\"a\",le=\"0.25\"} 1\n\
+             tick_bucket{module=\"a\",le=\"1\"} 2\n\
+             tick_bucket{module=\"a\",le=\"+Inf\"} 3\n\
+             tick_sum{module=\"a\"} 2.625\n\
+             tick_count{module=\"a\"} 3\n"
+        );
+        assert_eq!(escape_label("a\"b"), "a\\\"b");
+    }
+}
@@ -22,17 +22,24 @@ pub mod managed_map;
 pub mod medium_data_storage;
 pub mod module_system;
 
+pub mod audit_log;
 pub mod blueprint;
 pub mod guest;
+pub mod health;
+pub mod leaderboard;
+pub mod metrics;
 pub mod module;
 pub mod rapier_simulation;
 pub mod ring;
+pub mod server_gate;
 pub mod terrain_gen;
+pub mod time_sync;
 pub mod tween;
 
 pub type Snowflake = i64;
 
 pub const LOGGED_IN_TODAY_DELAY_IN_HOURS: i64 = 16;
+pub const DATA_STORE_LEADERBOARD_SIZE: usize = 10;
 pub const TARGET_FPS: Real = 60.0;
 pub const TARGET_FRAME_DURATION: Real = 1000.0 / 60.0;
 
@@ -108,6 +115,13 @@ pub fn get_out_dir() -> PathBuf {
     }
 }
 
+/// FNV-1a, unlike the std hasher it is stable between Rust versions.
+pub fn stable_hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 pub fn send_and_log_error<T>(sender: &mut Sender<T>, data: T) {
     if let Err(err) = sender.send(data) {
         error!("{:?}", err);
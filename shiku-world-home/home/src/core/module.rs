@@ -4,11 +4,13 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ts_rs::TS;
 
+use crate::conductor_module::session_resume::SessionResume;
+use crate::core::audit_log::{AuditLogEntry, AuditLogQuery};
 use crate::core::blueprint;
 use crate::core::blueprint::character_animation::CharacterAnimation;
 use crate::core::blueprint::def::{
-    CharAnimationToTilesetMap, Chunk, Conductor, Gid, GidMap, Image, LayerKind, ModuleId,
-    ResourcePath, TerrainParams, Tile, Tileset,
+    Achievement, CharAnimationToTilesetMap, Chunk, Conductor, Gid, GidMap, Image, LayerKind,
+    LeaderboardDefinition, ModuleId, ResourcePath, TerrainParams, Tile, Tileset,
 };
 use crate::core::blueprint::ecs::def::{Entity, EntityUpdate, EntityUpdateKind};
 use crate::core::blueprint::scene::def::{
@@ -16,12 +18,19 @@ use crate::core::blueprint::scene::def::{
 };
 use crate::core::entity::def::EntityId;
 use crate::core::entity::render::CameraSettings;
-use crate::core::guest::{ActorId, LoginProvider, ModuleExitSlot, SessionId};
+use crate::core::guest::{
+    ActorId, AdminRoleGrant, GuestBan, GuestToken, LoginProvider, ModuleExitSlot, ProviderUserId,
+    SessionToken,
+};
 use crate::core::module_system::game_instance::GameInstanceId;
+use crate::core::module_system::position_snapshot::{PositionSnapshot, SnapshotId};
+use crate::core::module_system::prediction::{InputSequence, Tick};
 use crate::core::module_system::world::WorldId;
+use crate::core::time_sync::{FrameStamp, TimeSyncPing, TimeSyncPong};
 use crate::resource_module::def::{ResourceBundle, ResourceEvent};
+use crate::websocket_module::ProtocolInfo;
 
-#[derive(TS, Debug, Serialize, Deserialize, Clone)]
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Default)]
 #[ts(export)]
 pub struct GuestInput {
     pub jump: bool,
@@ -34,6 +43,8 @@ pub struct GuestInput {
     pub action_2: bool,
     pub x_axis: Real,
     pub y_axis: Real,
+    #[serde(default)]
+    pub sequence: InputSequence,
 }
 
 impl GuestInput {
@@ -49,6 +60,7 @@ impl GuestInput {
             action_2: false,
             x_axis: 0.0,
             y_axis: 0.0,
+            sequence: 0,
         }
     }
 }
@@ -93,7 +105,8 @@ pub enum EditorEvent {
     UpdatedConductor(Conductor),
     ModuleInstanceOpened(ModuleId, GameInstanceId),
     ModuleInstanceClosed(ModuleId, GameInstanceId),
-    MainDoorStatus(bool),
+    AuditLog(Vec<AuditLogEntry>),
+    RoundTripTimes(Vec<(ProviderUserId, u32)>),
 }
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
@@ -132,8 +145,16 @@ pub enum AdminToSystemEvent {
     UpdateCharacterAnimation(CharacterAnimation),
     DeleteCharacterAnimation(CharacterAnimation),
     DeleteModule(ModuleId),
-    SetMainDoorStatus(bool),
-    SetBackDoorStatus(bool),
+    SetGateOpen(String, bool),
+    GrantAdminRole(AdminRoleGrant),
+    RevokeAdminRole(AdminRoleGrant),
+    QueryAuditLog(AuditLogQuery),
+    QueryRoundTripTimes,
+    KickFromModule(ProviderUserId),
+    KickFromServer(ProviderUserId, String),
+    BanGuest(GuestBan),
+    UnbanGuest(ProviderUserId),
+    SetObserver(ProviderUserId, bool),
     LoadEditorData,
     Ping,
 }
@@ -160,6 +181,7 @@ pub enum TilesetUpdate {
 #[ts(export)]
 pub enum GuestToModuleEvent {
     ControlInput(GuestInput),
+    PositionSnapshotAck(SnapshotId),
     GameSetupDone,
     WantToChangeModule(Option<ModuleExitSlot>),
 }
@@ -168,7 +190,8 @@ pub enum GuestToModuleEvent {
 #[ts(export)]
 pub enum GuestToSystemEvent {
     ProviderLoggedIn(ProviderLoggedIn),
-    Ping,
+    Ping(TimeSyncPing),
+    PongEcho(f64),
 }
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
@@ -183,12 +206,17 @@ pub enum GuestTo {
 pub enum SystemToModuleEvent {
     Disconnected(ActorId),
     Reconnected(ActorId),
+    Resync(ActorId),
+    RoundTripTime(ActorId, u32),
 }
 
 #[derive(Debug)]
 pub enum GuestStateChange {
     ExitModule(ModuleExitSlot),
     FoundSecret(String, ModuleName),
+    SetModuleData(ModuleId, String, serde_json::Value),
+    UnlockAchievement(ModuleId, Achievement),
+    SubmitScore(ModuleId, LeaderboardDefinition, f64),
 }
 
 #[derive(Debug)]
@@ -270,6 +298,7 @@ pub trait SystemModule {
 pub enum SignalToMedium {
     LoginSuccess,
     LoginFailed,
+    GuestToken(GuestToken),
 }
 
 type ShouldLogin = bool;
@@ -297,11 +326,13 @@ pub enum CommunicationEvent {
         Option<WorldId>,
         GameSystemToGuestEvent,
     ),
-    ConnectionReady((SessionId, ShouldLogin)),
+    ConnectionReady((SessionToken, ShouldLogin, ProtocolInfo)),
     Signal(SignalToMedium),
     Toast(ToastAlertLevel, String),
     ShowGlobalMessage(String),
     AlreadyConnected,
+    SessionResumed(SessionResume),
+    Pong(TimeSyncPong),
     EditorEvent(EditorEvent),
 }
 
@@ -323,10 +354,12 @@ pub enum MouseInputSchema {
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
 #[ts(export)]
 pub enum GameSystemToGuestEvent {
+    Frame(FrameStamp),
     OpenMenu(String),
     CloseMenu(String),
     UpdateDataStore(String),
     ShowTerrain(Vec<(LayerKind, Vec<Chunk>)>),
+    HideTerrain(Vec<(LayerKind, Vec<(i32, i32)>)>),
     SetParallax(Vec<(LayerKind, f32, f32)>),
     ShowTerrainCollisionLines(Vec<Vec<(Real, Real)>>),
     ShowScene(Scene),
@@ -336,7 +369,8 @@ pub enum GameSystemToGuestEvent {
     AddEntity(Entity, GameNodeKind),
     SetMouseInputSchema(MouseInputSchema),
     SetCamera(EntityId, CameraSettings),
-    PositionEvent(Vec<(Entity, Real, Real, Real)>),
+    PositionEvent(Tick, Vec<(Entity, Real, Real, Real)>),
+    PositionSnapshot(PositionSnapshot),
 }
 
 pub type GuestToModule = GuestEvent<ModuleInstanceEvent<GuestToModuleEvent>>;
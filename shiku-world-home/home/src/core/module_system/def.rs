@@ -1,14 +1,20 @@
 use std::collections::{HashMap, HashSet};
 
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use tokio::time::Instant;
 
-use crate::core::blueprint::def::{Gid, ModuleId};
+use crate::core::blueprint::def::{AchievementId, Gid, ModuleId};
 use crate::core::blueprint::scene::def::CollisionShape;
 use crate::core::guest::ActorId;
+use crate::core::leaderboard::LeaderboardMap;
 use crate::core::module::{ModuleInputReceiver, ModuleOutputSender};
 use crate::core::module_system::game_instance::GameInstanceId;
-use crate::core::module_system::world::{World, WorldId};
-use crate::core::LazyHashmapSet;
+use crate::core::module_system::interest::{GuestInterest, InterestSettings};
+use crate::core::module_system::position_snapshot::{SnapshotEncoder, SnapshotSettings};
+use crate::core::module_system::replay::ReplayRecorder;
+use crate::core::module_system::world::{GuestDataApi, World, WorldId};
+use crate::core::{ApiShare, LazyHashmapSet};
 
 pub struct GuestCommunication {
     pub resources_loaded: bool,
@@ -32,6 +38,15 @@ impl ModuleCommunication {
     }
 }
 
+/// The connected guests of one instance and where their per-world updates are sent.
+pub struct GuestBroadcast<'a> {
+    pub module_communication: &'a mut ModuleCommunication,
+    pub world_to_guest: &'a LazyHashmapSet<WorldId, ActorId>,
+    pub connected_actors_set: &'a HashSet<ActorId>,
+    pub module_id: &'a ModuleId,
+    pub instance_id: &'a GameInstanceId,
+}
+
 pub type GuestMap = HashMap<ActorId, ModuleGuest>;
 pub type AdminMap = HashMap<ActorId, ModuleAdmin>;
 
@@ -45,9 +60,29 @@ pub struct DynamicGameModule {
     pub world_to_guest: LazyHashmapSet<WorldId, ActorId>,
     pub admin_to_world: LazyHashmapSet<ActorId, WorldId>,
     pub guest_to_world: HashMap<ActorId, WorldId>,
+    pub observers: HashSet<ActorId>,
+    pub position_snapshots: HashMap<ActorId, SnapshotEncoder>,
+    pub snapshot_settings: SnapshotSettings,
+    pub guest_interests: HashMap<ActorId, GuestInterest>,
+    pub interest_settings: InterestSettings,
     pub module_communication: ModuleCommunication,
+    pub guest_data_api: ApiShare<GuestDataApi>,
+    pub leaderboards: LeaderboardMap,
     pub instance_id: GameInstanceId,
     pub module_id: ModuleId,
+    pub seed: u64,
+    pub recorder: Option<ReplayRecorder>,
+}
+
+/// What a guest brings into the module, taken from their persisted state when they enter.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnteringGuest {
+    pub actor_id: ActorId,
+    pub world_id: WorldId,
+    pub is_observer: bool,
+    pub guest_data: HashMap<String, JsonValue>,
+    pub unlocked_achievements: HashSet<AchievementId>,
+    pub persisted_guest_id: Option<i32>,
 }
 
 pub struct ModuleGuest {
@@ -18,6 +18,7 @@ use crate::core::blueprint::ecs::game_node_script::GameNodeScriptFunction;
 use crate::core::blueprint::resource_loader::Blueprint;
 use crate::core::blueprint::scene::def::{CollisionShape, Script};
 use crate::core::guest::{ActorId, Admin, Guest, ModuleEnterSlot};
+use crate::core::leaderboard::LeaderboardMap;
 use crate::core::module::{
     create_module_communication, AdminEnterSuccessState, AdminLeftSuccessState, EnterFailedState,
     EnterSuccessState, LeaveFailedState, LeaveSuccessState, ModuleInputReceiver, ModuleInputSender,
@@ -25,6 +26,7 @@ use crate::core::module::{
 };
 use crate::core::module_system::def::DynamicGameModule;
 use crate::core::module_system::error::{CreateWorldError, DestroyWorldError};
+use crate::core::module_system::replay::ReplayRecorder;
 use crate::core::module_system::world::WorldId;
 use crate::core::{send_and_log_error, TARGET_FRAME_DURATION};
 use crate::resource_module::def::{LoadResource, ResourceModule};
@@ -51,6 +53,7 @@ pub struct GameInstanceManager {
     pub(crate) module_blueprint: Module,
     pub(crate) game_instance_timeout: Real,
     pub(crate) instance_id_gen: SnowflakeIdBucket,
+    pub(crate) leaderboards: LeaderboardMap,
 }
 
 #[derive(Debug)]
@@ -108,6 +111,7 @@ impl GameInstanceManager {
     pub fn new(
         module_blueprint: Module,
         resource_module: &mut ResourceModule,
+        leaderboards: LeaderboardMap,
     ) -> Result<
         (GameInstanceManager, ModuleInputSender, ModuleOutputReceiver),
         CreateInstanceManagerError,
@@ -126,6 +130,7 @@ impl GameInstanceManager {
             input_receiver,
             output_sender,
             module_blueprint,
+            leaderboards,
         };
 
         manager.register_resources(resource_module);
@@ -175,7 +180,13 @@ impl GameInstanceManager {
         }
 
         for inactive_game_instanced_id in self.inactive_game_instances.drain(..) {
-            self.game_instances.remove(&inactive_game_instanced_id);
+            if let Some(recorder) = self
+                .game_instances
+                .remove(&inactive_game_instanced_id)
+                .and_then(|game_instance| game_instance.dynamic_module.recorder)
+            {
+                recorder.finish();
+            }
             send_and_log_error(
                 &mut self.output_sender.module_to_system_sender,
                 ModuleToSystemEvent::GameInstanceClosed(
@@ -344,6 +355,18 @@ impl GameInstanceManager {
         Err(LeaveFailedState::NotInModule)
     }
 
+    pub fn set_observer(&mut self, guest_id: &ActorId, is_observer: bool) {
+        if let Some(game_instance) = self
+            .guest_to_game_instance_map
+            .get(guest_id)
+            .and_then(|game_instance_id| self.game_instances.get_mut(game_instance_id))
+        {
+            game_instance
+                .dynamic_module
+                .set_observer(guest_id, is_observer);
+        }
+    }
+
     pub fn create_world(
         &mut self,
         game_map: &GameMap,
@@ -498,6 +521,14 @@ impl GameInstanceManager {
                         game_instance.dynamic_module.actor_reconnected(&actor_id);
                         self.connected_actor_ids.insert(actor_id);
                     }
+                    SystemToModuleEvent::Resync(actor_id) => {
+                        game_instance.dynamic_module.resync_guest(&actor_id);
+                    }
+                    SystemToModuleEvent::RoundTripTime(actor_id, round_trip_time) => {
+                        game_instance
+                            .dynamic_module
+                            .set_round_trip_time(&actor_id, round_trip_time);
+                    }
                 }
             }
         }
@@ -508,6 +539,7 @@ impl GameInstanceManager {
             self.instance_id_gen.get_id().to_string(),
             &self.module_blueprint,
             self.output_sender.clone(),
+            self.leaderboards.clone(),
         );
         let new_game_instance_id = new_game_instance.id.clone();
         self.game_instances
@@ -607,9 +639,12 @@ impl GameInstance {
         id: GameInstanceId,
         module: &Module,
         output_sender: ModuleOutputSender,
+        leaderboards: LeaderboardMap,
     ) -> GameInstance {
-        let (dynamic_module, input_sender) =
-            DynamicGameModule::create(id.clone(), module, output_sender);
+        let seed = rand::random();
+        let (mut dynamic_module, input_sender) =
+            DynamicGameModule::create(id.clone(), module, output_sender, leaderboards, seed);
+        dynamic_module.recorder = ReplayRecorder::from_env(module, &id, seed);
         GameInstance {
             id,
             dynamic_module,
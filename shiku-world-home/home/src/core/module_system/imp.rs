@@ -15,25 +15,39 @@ use crate::core::blueprint::scene::def::{CollisionShape, GameNodeKind};
 use crate::core::blueprint::scene::imp::build_scene_from_ecs;
 use crate::core::guest::ActorId;
 use crate::core::guest::{Admin, Guest, ModuleEnterSlot};
+use crate::core::leaderboard::LeaderboardMap;
 use crate::core::module::{
     create_module_communication_input, EnterFailedState, EnterSuccessState, GameSystemToGuest,
-    GameSystemToGuestEvent, GuestEvent, GuestToModule, LeaveFailedState, LeaveSuccessState,
-    ModuleInputSender, ModuleInstanceEvent, ModuleOutputSender, SystemToModuleEvent,
+    GameSystemToGuestEvent, GuestEvent, GuestStateChange, GuestToModule, LeaveFailedState,
+    LeaveSuccessState, ModuleInputSender, ModuleInstanceEvent, ModuleOutputSender,
+    ModuleToSystemEvent, SystemToModuleEvent,
 };
 use crate::core::module::{GuestInput, GuestToModuleEvent};
 use crate::core::module_system::def::{
-    DynamicGameModule, GuestCommunication, GuestMap, ModuleAdmin, ModuleCommunication, ModuleGuest,
+    DynamicGameModule, EnteringGuest, GuestBroadcast, GuestCommunication, GuestMap, ModuleAdmin,
+    ModuleCommunication, ModuleGuest,
 };
 use crate::core::module_system::error::{CreateWorldError, DestroyWorldError};
 use crate::core::module_system::game_instance::{AstCache, GameInstanceId};
-use crate::core::module_system::world::{World, WorldId};
-use crate::core::{send_and_log_error, send_and_log_error_custom, LazyHashmapSet};
+use crate::core::module_system::interest::{
+    chunks_in_view, top_level_entity, ChunkPosition, GuestInterest, InterestScope,
+    InterestSettings, SpatialGrid,
+};
+use crate::core::module_system::position_snapshot::{SnapshotEncoder, SnapshotSettings};
+use crate::core::module_system::replay::ReplayEvent;
+use crate::core::module_system::world::{GuestDataApi, World, WorldId};
+use crate::core::time_sync::FrameStamp;
+use crate::core::{
+    send_and_log_error, send_and_log_error_custom, stable_hash, ApiShare, LazyHashmapSet,
+};
 
 impl DynamicGameModule {
     pub fn create(
         instance_id: GameInstanceId,
         module: &Module,
         module_output_sender: ModuleOutputSender,
+        leaderboards: LeaderboardMap,
+        seed: u64,
     ) -> (DynamicGameModule, ModuleInputSender) {
         let (module_input_sender, module_input_receiver) = create_module_communication_input();
         let gid_to_collision_shape_map =
@@ -46,6 +60,11 @@ impl DynamicGameModule {
             guests: HashMap::new(),
             admins: HashMap::new(),
             guest_to_world: HashMap::new(),
+            observers: HashSet::new(),
+            position_snapshots: HashMap::new(),
+            snapshot_settings: SnapshotSettings::from_env(),
+            guest_interests: HashMap::new(),
+            interest_settings: InterestSettings::from_env(),
             admin_to_world: LazyHashmapSet::new(),
             world_to_admin: LazyHashmapSet::new(),
             world_to_guest: LazyHashmapSet::new(),
@@ -55,8 +74,12 @@ impl DynamicGameModule {
                 module_input_receiver,
                 module_output_sender,
             ),
+            guest_data_api: ApiShare::new(GuestDataApi::new(module.id.clone())),
+            leaderboards,
             module_id: module.id.clone(),
             instance_id,
+            seed,
+            recorder: None,
         };
         let game_maps = BlueprintService::load_all_maps_for_module(module).unwrap_or_else(|err| {
             error!("Could not load maps for module to create worlds {:?}", err);
@@ -115,7 +138,13 @@ impl DynamicGameModule {
             return Err(CreateWorldError::DidAlreadyExist);
         }
 
-        let new_world = World::new(game_map, &self.gid_to_collision_shape_map)?;
+        let new_world = World::new(
+            game_map,
+            &self.gid_to_collision_shape_map,
+            &self.guest_data_api,
+            &self.leaderboards,
+            self.seed ^ stable_hash(game_map.world_id.bytes()),
+        )?;
         self.world_map.insert(game_map.world_id.clone(), new_world);
         self.world_to_admin.init(game_map.world_id.clone());
         self.world_to_guest.init(game_map.world_id.clone());
@@ -123,6 +152,7 @@ impl DynamicGameModule {
     }
 
     pub fn reset_world(&mut self, world_id: &WorldId) -> Result<(), CreateWorldError> {
+        self.record(ReplayEvent::ResetWorld(world_id.clone()));
         if let Some(world) = self.world_map.get_mut(world_id) {
             return world.reset();
         }
@@ -159,6 +189,8 @@ impl DynamicGameModule {
                 &self.world_to_guest,
                 &self.world_to_admin,
                 &self.connected_actor_set,
+                &self.guest_interests,
+                InterestScope::World,
                 ModuleInstanceEvent {
                     module_id: self.module_id.clone(),
                     instance_id: self.instance_id.clone(),
@@ -203,10 +235,14 @@ impl DynamicGameModule {
     pub fn set_actor_input(
         guest_to_world_map: &HashMap<ActorId, WorldId>,
         admin_to_world_map: &LazyHashmapSet<ActorId, WorldId>,
+        observers: &HashSet<ActorId>,
         world_map: &mut HashMap<WorldId, World>,
         actor_id: &ActorId,
         input: GuestInput,
     ) {
+        if observers.contains(actor_id) {
+            return;
+        }
         let world_ids_of_admin_or_guest = guest_to_world_map
             .get(actor_id)
             .into_iter()
@@ -221,6 +257,24 @@ impl DynamicGameModule {
         }
     }
 
+    /// Observers stay in their world, whatever they last pressed is released.
+    pub fn set_observer(&mut self, guest_id: &ActorId, is_observer: bool) {
+        self.record(ReplayEvent::SetObserver(*guest_id, is_observer));
+        if !is_observer {
+            self.observers.remove(guest_id);
+            return;
+        }
+        Self::set_actor_input(
+            &self.guest_to_world,
+            &self.admin_to_world,
+            &self.observers,
+            &mut self.world_map,
+            guest_id,
+            GuestInput::default(),
+        );
+        self.observers.insert(*guest_id);
+    }
+
     fn set_resources_loaded(
         guests: &mut GuestMap,
         connected_actors_set: &mut HashSet<ActorId>,
@@ -233,10 +287,38 @@ impl DynamicGameModule {
     }
 
     pub fn update(&mut self, module: &Module) {
-        self.handle_guest_events(module);
+        self.handle_guest_events();
         self.send_scope_updates_to_admins();
         for world in self.world_map.values_mut() {
             world.update();
+            Self::send_event_to_actors(
+                &world.world_id,
+                &mut self.module_communication,
+                &self.world_to_guest,
+                &self.world_to_admin,
+                &self.connected_actor_set,
+                &self.guest_interests,
+                InterestScope::World,
+                ModuleInstanceEvent {
+                    world_id: None,
+                    module_id: self.module_id.clone(),
+                    instance_id: self.instance_id.clone(),
+                    event_type: GameSystemToGuestEvent::Frame(FrameStamp::now(world.tick)),
+                },
+                "Could not send frame stamp",
+            );
+            let grid = Self::update_guest_interests(
+                world,
+                GuestBroadcast {
+                    module_communication: &mut self.module_communication,
+                    world_to_guest: &self.world_to_guest,
+                    connected_actors_set: &self.connected_actor_set,
+                    module_id: &self.module_id,
+                    instance_id: &self.instance_id,
+                },
+                &mut self.guest_interests,
+                &self.interest_settings,
+            );
 
             let position_updates = Self::get_position_updates(world);
             if !position_updates.is_empty() {
@@ -244,18 +326,30 @@ impl DynamicGameModule {
                     world_id: None,
                     module_id: self.module_id.clone(),
                     instance_id: self.instance_id.clone(),
-                    event_type: GameSystemToGuestEvent::PositionEvent(position_updates),
+                    event_type: GameSystemToGuestEvent::PositionEvent(world.tick, position_updates),
                 };
-                Self::send_event_to_actors(
+                Self::send_event_to_admins(
                     &world.world_id,
                     &mut self.module_communication,
-                    &self.world_to_guest,
                     &self.world_to_admin,
-                    &self.connected_actor_set,
                     update_position_event,
                     "Could not send entity update",
                 );
             }
+            Self::send_position_snapshots(
+                world,
+                GuestBroadcast {
+                    module_communication: &mut self.module_communication,
+                    world_to_guest: &self.world_to_guest,
+                    connected_actors_set: &self.connected_actor_set,
+                    module_id: &self.module_id,
+                    instance_id: &self.instance_id,
+                },
+                &mut self.position_snapshots,
+                &self.snapshot_settings,
+                &self.guest_interests,
+                grid.as_ref(),
+            );
 
             let gid_updates = Self::get_gid_updates(world);
             if !gid_updates.is_empty() {
@@ -269,18 +363,104 @@ impl DynamicGameModule {
                             kind: EntityUpdateKind::Gid(gid),
                         }),
                     };
+                    let scope = match &grid {
+                        Some(grid) => InterestScope::Entity(grid.top_level_of(&entity)),
+                        None => InterestScope::World,
+                    };
                     Self::send_event_to_actors(
                         &world.world_id,
                         &mut self.module_communication,
                         &self.world_to_guest,
                         &self.world_to_admin,
                         &self.connected_actor_set,
+                        &self.guest_interests,
+                        scope,
                         update_position_event,
                         "Could not send entity update",
                     );
                 }
             }
         }
+        self.send_guest_state_changes(module);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.frame_done();
+        }
+    }
+
+    fn record(&mut self, event: ReplayEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(event);
+        }
+    }
+
+    fn send_guest_state_changes(&mut self, module: &Module) {
+        if let Some(mut guest_data_api) = self.guest_data_api.try_borrow_mut() {
+            for (actor_id, achievement_id) in guest_data_api.drain_pending_achievement_unlocks() {
+                match module
+                    .achievements
+                    .iter()
+                    .find(|achievement| achievement.id == achievement_id)
+                {
+                    Some(achievement) => send_and_log_error(
+                        &mut self
+                            .module_communication
+                            .output_sender
+                            .module_to_system_sender,
+                        ModuleToSystemEvent::GuestStateChange(
+                            actor_id,
+                            GuestStateChange::UnlockAchievement(
+                                self.module_id.clone(),
+                                achievement.clone(),
+                            ),
+                        ),
+                    ),
+                    None => {
+                        error!(
+                            "Achievement '{}' is not defined in module '{}'!",
+                            achievement_id, module.name
+                        );
+                        guest_data_api.forget_achievement_unlock(&actor_id, &achievement_id);
+                    }
+                }
+            }
+            for (actor_id, leaderboard_id, score) in
+                guest_data_api.drain_pending_score_submissions()
+            {
+                match module
+                    .leaderboards
+                    .iter()
+                    .find(|leaderboard| leaderboard.id == leaderboard_id)
+                {
+                    Some(leaderboard) => send_and_log_error(
+                        &mut self
+                            .module_communication
+                            .output_sender
+                            .module_to_system_sender,
+                        ModuleToSystemEvent::GuestStateChange(
+                            actor_id,
+                            GuestStateChange::SubmitScore(
+                                self.module_id.clone(),
+                                leaderboard.clone(),
+                                score,
+                            ),
+                        ),
+                    ),
+                    None => error!(
+                        "Leaderboard '{}' is not defined in module '{}'!",
+                        leaderboard_id, module.name
+                    ),
+                }
+            }
+            for (actor_id, state_change) in guest_data_api.drain_state_changes() {
+                send_and_log_error(
+                    &mut self
+                        .module_communication
+                        .output_sender
+                        .module_to_system_sender,
+                    ModuleToSystemEvent::GuestStateChange(actor_id, state_change),
+                );
+            }
+        }
     }
 
     fn send_scope_updates_to_admins(&mut self) {
@@ -310,6 +490,241 @@ impl DynamicGameModule {
         }
     }
 
+    /// Guests with a focus entity only hold what is around it, whatever enters their view is
+    /// added to their client and whatever leaves it is removed again.
+    fn update_guest_interests(
+        world: &World,
+        guests: GuestBroadcast<'_>,
+        guest_interests: &mut HashMap<ActorId, GuestInterest>,
+        interest_settings: &InterestSettings,
+    ) -> Option<SpatialGrid> {
+        let GuestBroadcast {
+            module_communication,
+            world_to_guest,
+            connected_actors_set,
+            module_id,
+            instance_id,
+        } = guests;
+        let guest_ids = world_to_guest.hashset(&world.world_id)?;
+        let focused_guests: Vec<(ActorId, Entity)> = {
+            let actor_api = world.actor_api.try_borrow()?;
+            guest_ids
+                .iter()
+                .filter(|guest_id| connected_actors_set.contains(guest_id))
+                .filter_map(|guest_id| {
+                    actor_api
+                        .get_focus_entity(guest_id)
+                        .map(|entity| (*guest_id, entity))
+                })
+                .collect()
+        };
+        if focused_guests.is_empty() {
+            return None;
+        }
+        let grid = {
+            let shared = world.ecs.shared.try_borrow()?;
+            SpatialGrid::build(&shared.entities, world.ecs.scene_root, interest_settings)
+        };
+
+        for (guest_id, focus_entity) in focused_guests {
+            let interest = guest_interests
+                .entry(guest_id)
+                .or_insert_with(|| Self::full_interest(world, &grid));
+            if interest.world_id != world.world_id {
+                *interest = Self::full_interest(world, &grid);
+            }
+            let Some(center) = grid
+                .top_level_of(&focus_entity)
+                .and_then(|top_level| grid.cell_of_entity(&top_level))
+            else {
+                continue;
+            };
+
+            let mut events = Vec::new();
+            let in_view = grid.entities_in_view(center, interest_settings);
+            for entity in in_view.difference(&interest.entities) {
+                if let Some(game_node) =
+                    GameNodeKind::get_game_node_kind_from_ecs(entity, &world.ecs)
+                {
+                    events.push(GameSystemToGuestEvent::AddEntity(
+                        world.ecs.scene_root,
+                        game_node,
+                    ));
+                }
+            }
+            for entity in interest.entities.difference(&in_view) {
+                events.push(GameSystemToGuestEvent::RemoveEntity(*entity));
+            }
+            interest.entities = in_view;
+
+            if interest.center != Some(center) {
+                interest.center = Some(center);
+                let mut chunks = HashSet::new();
+                let mut shown: HashMap<LayerKind, Vec<Chunk>> = HashMap::new();
+                for (layer_kind, chunk) in chunks_in_view(
+                    &world.terrain_manager.layer_data,
+                    center,
+                    interest_settings,
+                    &world.terrain_manager.params,
+                    world.terrain_manager.pixel_to_meter_conversion,
+                ) {
+                    let key = (layer_kind.clone(), chunk.position);
+                    if !interest.chunks.contains(&key) {
+                        shown
+                            .entry(layer_kind.clone())
+                            .or_default()
+                            .push(chunk.clone());
+                    }
+                    chunks.insert(key);
+                }
+                let mut hidden: HashMap<LayerKind, Vec<ChunkPosition>> = HashMap::new();
+                for (layer_kind, chunk_position) in interest.chunks.difference(&chunks) {
+                    hidden
+                        .entry(layer_kind.clone())
+                        .or_default()
+                        .push(*chunk_position);
+                }
+                interest.chunks = chunks;
+                if !shown.is_empty() {
+                    events.push(GameSystemToGuestEvent::ShowTerrain(
+                        shown.into_iter().collect(),
+                    ));
+                }
+                if !hidden.is_empty() {
+                    events.push(GameSystemToGuestEvent::HideTerrain(
+                        hidden.into_iter().collect(),
+                    ));
+                }
+            }
+
+            for event in events {
+                send_and_log_error_custom(
+                    &mut module_communication
+                        .output_sender
+                        .game_system_to_guest_sender,
+                    GuestEvent {
+                        guest_id,
+                        event_type: ModuleInstanceEvent {
+                            world_id: None,
+                            module_id: module_id.clone(),
+                            instance_id: instance_id.clone(),
+                            event_type: event,
+                        },
+                    },
+                    "Could not send interest update",
+                );
+            }
+        }
+
+        Some(grid)
+    }
+
+    /// Until a guest got their first interest update their client holds the whole world.
+    fn full_interest(world: &World, grid: &SpatialGrid) -> GuestInterest {
+        GuestInterest::new(
+            world.world_id.clone(),
+            grid.all_entities(),
+            world
+                .terrain_manager
+                .layer_data
+                .iter()
+                .flat_map(|(layer_kind, chunks)| {
+                    chunks
+                        .values()
+                        .map(|chunk| (layer_kind.clone(), chunk.position))
+                })
+                .collect(),
+        )
+    }
+
+    /// Guests get quantized snapshots instead of every dirty position, the editor keeps
+    /// getting the raw `PositionEvent`s.
+    fn send_position_snapshots(
+        world: &World,
+        guests: GuestBroadcast<'_>,
+        position_snapshots: &mut HashMap<ActorId, SnapshotEncoder>,
+        snapshot_settings: &SnapshotSettings,
+        guest_interests: &HashMap<ActorId, GuestInterest>,
+        grid: Option<&SpatialGrid>,
+    ) {
+        let GuestBroadcast {
+            module_communication,
+            world_to_guest,
+            connected_actors_set,
+            module_id,
+            instance_id,
+        } = guests;
+        let Some(guest_ids) = world_to_guest.hashset(&world.world_id) else {
+            return;
+        };
+        if !guest_ids
+            .iter()
+            .any(|guest_id| connected_actors_set.contains(guest_id))
+        {
+            return;
+        }
+        let Some(state) = world
+            .ecs
+            .shared
+            .try_borrow()
+            .map(|shared| snapshot_settings.quantize_all(&shared.entities.transforms))
+        else {
+            return;
+        };
+
+        for guest_id in guest_ids {
+            if !connected_actors_set.contains(guest_id) {
+                continue;
+            }
+            let encoder = position_snapshots
+                .entry(*guest_id)
+                .or_insert_with(|| SnapshotEncoder::new(world.world_id.clone()));
+            if encoder.world_id != world.world_id {
+                *encoder = SnapshotEncoder::new(world.world_id.clone());
+            }
+            let last_processed_input = world
+                .actor_api
+                .try_borrow()
+                .and_then(|actor_api| actor_api.get_processed_input(guest_id));
+            let snapshot = match (guest_interests.get(guest_id), grid) {
+                (Some(interest), Some(grid)) if interest.world_id == world.world_id => {
+                    let state_in_view = state
+                        .iter()
+                        .filter(|(entity, _)| {
+                            grid.top_level_of(entity)
+                                .is_some_and(|top_level| interest.entities.contains(&top_level))
+                        })
+                        .map(|(entity, position)| (*entity, *position))
+                        .collect();
+                    encoder.encode(
+                        world.tick,
+                        &state_in_view,
+                        last_processed_input,
+                        snapshot_settings,
+                    )
+                }
+                _ => encoder.encode(world.tick, &state, last_processed_input, snapshot_settings),
+            };
+            if let Some(snapshot) = snapshot {
+                send_and_log_error_custom(
+                    &mut module_communication
+                        .output_sender
+                        .game_system_to_guest_sender,
+                    GuestEvent {
+                        guest_id: *guest_id,
+                        event_type: ModuleInstanceEvent {
+                            world_id: None,
+                            module_id: module_id.clone(),
+                            instance_id: instance_id.clone(),
+                            event_type: GameSystemToGuestEvent::PositionSnapshot(snapshot),
+                        },
+                    },
+                    "Could not send position snapshot",
+                );
+            }
+        }
+    }
+
     pub fn get_position_updates(world: &mut World) -> Vec<(Entity, Real, Real, Real)> {
         if let Some(mut shared) = world.ecs.shared.try_borrow_mut() {
             let entities = &mut shared.entities;
@@ -358,6 +773,7 @@ impl DynamicGameModule {
     }
 
     pub fn actor_disconnected(&mut self, actor_id: &ActorId) {
+        self.record(ReplayEvent::Disconnected(*actor_id));
         if let Some(guest) = self.guests.get_mut(actor_id) {
             guest.guest_com.connected = false;
             if let Some(world_id) = &guest.world_id {
@@ -379,8 +795,13 @@ impl DynamicGameModule {
     }
 
     pub fn apply_admin_entity_update(&mut self, world_id: &WorldId, entity_update: EntityUpdate) {
+        self.record(ReplayEvent::UpdateEntity(
+            world_id.clone(),
+            entity_update.clone(),
+        ));
         if let Some(world) = self.world_map.get_mut(world_id) {
             world.apply_admin_entity_update(entity_update.clone());
+            let scope = InterestScope::Entity(Self::top_level_entity_of(world, entity_update.id));
 
             let entity_update_event = ModuleInstanceEvent {
                 world_id: None,
@@ -394,6 +815,8 @@ impl DynamicGameModule {
                 &self.world_to_guest,
                 &self.world_to_admin,
                 &self.connected_actor_set,
+                &self.guest_interests,
+                scope,
                 entity_update_event,
                 "Could not send entity update",
             );
@@ -401,7 +824,10 @@ impl DynamicGameModule {
     }
 
     pub fn remove_entity(&mut self, world_id: &WorldId, entity: Entity) {
+        self.record(ReplayEvent::RemoveEntity(world_id.clone(), entity));
+        let mut scope = InterestScope::World;
         if let Some(world) = self.world_map.get_mut(world_id) {
+            scope = InterestScope::Entity(Self::top_level_entity_of(world, entity));
             world.remove_entity(entity);
         }
         let entity_removed_event = ModuleInstanceEvent {
@@ -416,9 +842,22 @@ impl DynamicGameModule {
             &self.world_to_guest,
             &self.world_to_admin,
             &self.connected_actor_set,
+            &self.guest_interests,
+            scope,
             entity_removed_event,
             "Could not send entity remove event",
         );
+        for interest in self.guest_interests.values_mut() {
+            interest.entities.remove(&entity);
+        }
+    }
+
+    fn top_level_entity_of(world: &World, entity: Entity) -> Option<Entity> {
+        world
+            .ecs
+            .shared
+            .try_borrow()
+            .and_then(|shared| top_level_entity(&shared.entities, world.ecs.scene_root, entity))
     }
 
     pub fn add_entity(
@@ -427,11 +866,17 @@ impl DynamicGameModule {
         parent_entity: Entity,
         game_node: GameNodeKind,
     ) {
+        self.record(ReplayEvent::AddEntity(
+            world_id.clone(),
+            parent_entity,
+            game_node.clone(),
+        ));
         if let Some(world) = self.world_map.get_mut(world_id) {
             if let Some(entity) = world.add_entity(parent_entity, &game_node) {
                 if let Some(game_node) =
                     GameNodeKind::get_game_node_kind_from_ecs(&entity, &world.ecs)
                 {
+                    let scope = InterestScope::Entity(Self::top_level_entity_of(world, entity));
                     let add_entity_event = ModuleInstanceEvent {
                         world_id: None,
                         module_id: self.module_id.clone(),
@@ -445,6 +890,8 @@ impl DynamicGameModule {
                         &self.world_to_guest,
                         &self.world_to_admin,
                         &self.connected_actor_set,
+                        &self.guest_interests,
+                        scope,
                         add_entity_event,
                         "Could not send entity add event",
                     );
@@ -503,6 +950,8 @@ impl DynamicGameModule {
                     &self.world_to_guest,
                     &self.world_to_admin,
                     &self.connected_actor_set,
+                    &self.guest_interests,
+                    InterestScope::Chunk(layer_kind.clone(), chunk.position),
                     terrain_update,
                     "Could not send terrain update",
                 );
@@ -557,6 +1006,8 @@ impl DynamicGameModule {
         world_to_guest: &LazyHashmapSet<WorldId, ActorId>,
         world_to_admin: &LazyHashmapSet<WorldId, ActorId>,
         connected_actors_set: &HashSet<ActorId>,
+        guest_interests: &HashMap<ActorId, GuestInterest>,
+        scope: InterestScope,
         event: ModuleInstanceEvent<GameSystemToGuestEvent>,
         custom_error_msg: &str,
     ) {
@@ -578,7 +1029,11 @@ impl DynamicGameModule {
                     );
                 };
             for guest_id in actor_id {
-                if connected_actors_set.contains(guest_id) {
+                let wants_event = guest_interests
+                    .get(guest_id)
+                    .filter(|interest| interest.world_id == *world_id)
+                    .is_none_or(|interest| interest.wants(&scope));
+                if connected_actors_set.contains(guest_id) && wants_event {
                     send_event_update(*guest_id, event.clone());
                 }
             }
@@ -592,7 +1047,18 @@ impl DynamicGameModule {
         }
     }
 
+    pub fn set_round_trip_time(&mut self, actor_id: &ActorId, round_trip_time: u32) {
+        self.record(ReplayEvent::RoundTripTime(*actor_id, round_trip_time));
+        for world in self.world_map.values() {
+            if let Some(mut actor_api) = world.actor_api.try_borrow_mut() {
+                actor_api.set_round_trip_time(*actor_id, round_trip_time);
+            }
+        }
+    }
+
     pub fn actor_reconnected(&mut self, actor_id: &ActorId) {
+        self.record(ReplayEvent::Reconnected(*actor_id));
+        self.position_snapshots.remove(actor_id);
         if let Some(guest) = self.guests.get_mut(actor_id) {
             guest.guest_com.connected = true;
             if let Some(world_id) = &guest.world_id {
@@ -613,7 +1079,8 @@ impl DynamicGameModule {
         }
     }
 
-    fn handle_guest_events(&mut self, module: &Module) {
+    fn handle_guest_events(&mut self) {
+        let mut guests_to_resync = Vec::new();
         for event in self
             .module_communication
             .input_receiver
@@ -626,41 +1093,67 @@ impl DynamicGameModule {
             } = event;
             match event_type.event_type {
                 GuestToModuleEvent::ControlInput(input) => {
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(ReplayEvent::ControlInput(guest_id, input.clone()));
+                    }
                     Self::set_actor_input(
                         &self.guest_to_world,
                         &self.admin_to_world,
+                        &self.observers,
                         &mut self.world_map,
                         &guest_id,
                         input,
                     );
                 }
+                GuestToModuleEvent::PositionSnapshotAck(snapshot_id) => {
+                    if let Some(encoder) = self.position_snapshots.get_mut(&guest_id) {
+                        encoder.ack(snapshot_id);
+                    }
+                }
                 GuestToModuleEvent::GameSetupDone => {
                     Self::set_resources_loaded(
                         &mut self.guests,
                         &mut self.connected_actor_set,
                         &guest_id,
                     );
-                    if let Some(world_id) = self.guest_to_world.get(&guest_id) {
-                        Self::send_initial_world_events(
-                            &mut self
-                                .module_communication
-                                .output_sender
-                                .game_system_to_guest_sender,
-                            &self.world_map,
-                            self.instance_id.clone(),
-                            guest_id,
-                            world_id,
-                            module.id.clone(),
-                            false,
-                            true,
-                        );
-                    }
+                    guests_to_resync.push(guest_id);
                 }
                 GuestToModuleEvent::WantToChangeModule(_exit_slot) => {
                     debug!("WantToChangeModule not implemented!");
                 }
             }
         }
+        for guest_id in guests_to_resync {
+            self.resync_guest(&guest_id);
+        }
+    }
+
+    /// Sends the guest's world as if they just entered it, their client dropped what it had.
+    pub fn resync_guest(&mut self, guest_id: &ActorId) {
+        self.position_snapshots.remove(guest_id);
+        self.guest_interests.remove(guest_id);
+        if let Some(world_id) = self.guest_to_world.get(guest_id) {
+            let interest = self.world_map.get(world_id).and_then(|world| {
+                Self::initial_guest_interest(world, guest_id, &self.interest_settings)
+            });
+            Self::send_initial_world_events(
+                &mut self
+                    .module_communication
+                    .output_sender
+                    .game_system_to_guest_sender,
+                &self.world_map,
+                self.instance_id.clone(),
+                *guest_id,
+                world_id,
+                self.module_id.clone(),
+                false,
+                true,
+                interest.as_ref(),
+            );
+            if let Some(interest) = interest {
+                self.guest_interests.insert(*guest_id, interest);
+            }
+        }
     }
 
     pub fn send_initial_world_events_admin(
@@ -682,9 +1175,40 @@ impl DynamicGameModule {
             module_id,
             true,
             send_terrain,
+            None,
         );
     }
 
+    fn initial_guest_interest(
+        world: &World,
+        guest_id: &ActorId,
+        interest_settings: &InterestSettings,
+    ) -> Option<GuestInterest> {
+        let focus_entity = world.actor_api.try_borrow()?.get_focus_entity(guest_id)?;
+        let grid = {
+            let shared = world.ecs.shared.try_borrow()?;
+            SpatialGrid::build(&shared.entities, world.ecs.scene_root, interest_settings)
+        };
+        let center = grid
+            .top_level_of(&focus_entity)
+            .and_then(|top_level| grid.cell_of_entity(&top_level))?;
+
+        Some(GuestInterest {
+            world_id: world.world_id.clone(),
+            center: Some(center),
+            entities: grid.entities_in_view(center, interest_settings),
+            chunks: chunks_in_view(
+                &world.terrain_manager.layer_data,
+                center,
+                interest_settings,
+                &world.terrain_manager.params,
+                world.terrain_manager.pixel_to_meter_conversion,
+            )
+            .map(|(layer_kind, chunk)| (layer_kind.clone(), chunk.position))
+            .collect(),
+        })
+    }
+
     pub fn send_initial_world_events(
         sender: &mut Sender<GameSystemToGuest>,
         world_map: &HashMap<WorldId, World>,
@@ -694,15 +1218,27 @@ impl DynamicGameModule {
         module_id: ModuleId,
         is_admin: bool,
         send_terrain: bool,
+        interest: Option<&GuestInterest>,
     ) {
         if send_terrain {
-            if let Some(initial_terrain_event) = Self::get_initial_terrain_event(
+            if let Some(mut initial_terrain_event) = Self::get_initial_terrain_event(
                 world_map,
                 module_id.clone(),
                 instance_id.clone(),
                 world_id,
                 is_admin,
             ) {
+                if let (Some(interest), GameSystemToGuestEvent::ShowTerrain(terrain)) =
+                    (interest, &mut initial_terrain_event.event_type)
+                {
+                    for (layer_kind, chunks) in terrain.iter_mut() {
+                        chunks.retain(|chunk| {
+                            interest
+                                .chunks
+                                .contains(&(layer_kind.clone(), chunk.position))
+                        });
+                    }
+                }
                 send_and_log_error(
                     sender,
                     GuestEvent {
@@ -731,7 +1267,15 @@ impl DynamicGameModule {
 
                 Self::send_current_script_scopes(sender, &instance_id, actor_id, &module_id, world);
             }
-            if let Some(scene) = build_scene_from_ecs(&world.ecs) {
+            if let Some(mut scene) = build_scene_from_ecs(&world.ecs) {
+                if let Some(interest) = interest {
+                    let GameNodeKind::Node2D(root_node) = &mut scene.root_node;
+                    root_node.children.retain(|GameNodeKind::Node2D(child)| {
+                        child
+                            .entity_id
+                            .is_none_or(|entity| interest.entities.contains(&entity))
+                    });
+                }
                 send_and_log_error(
                     sender,
                     GuestEvent {
@@ -851,34 +1395,97 @@ impl DynamicGameModule {
         main_world_id: WorldId,
         _module_enter_slot: &ModuleEnterSlot,
     ) -> Result<EnterSuccessState, EnterFailedState> {
-        debug!("Guest entering world with id: {:?}", main_world_id);
-        self.guest_to_world.insert(guest.id, main_world_id.clone());
-        self.world_to_guest
-            .insert_entry(main_world_id.clone(), guest.id);
+        let persisted_guest = guest.persisted_guest.as_ref();
+        self.enter(EnteringGuest {
+            actor_id: guest.id,
+            world_id: main_world_id,
+            is_observer: persisted_guest
+                .is_some_and(|persisted_guest| persisted_guest.info.is_observer),
+            guest_data: persisted_guest
+                .and_then(|persisted_guest| persisted_guest.module_data.get(&self.module_id))
+                .cloned()
+                .unwrap_or_default(),
+            unlocked_achievements: persisted_guest
+                .map(|persisted_guest| {
+                    persisted_guest
+                        .achievements_unlocked
+                        .iter()
+                        .filter(|unlocked| unlocked.module_id == self.module_id)
+                        .map(|unlocked| unlocked.achievement_id.clone())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            persisted_guest_id: persisted_guest.map(|persisted_guest| persisted_guest.info.id),
+        });
+
+        Ok(EnterSuccessState::Entered)
+    }
+
+    pub fn enter(&mut self, entering_guest: EnteringGuest) {
+        debug!(
+            "Guest entering world with id: {:?}",
+            entering_guest.world_id
+        );
+        self.record(ReplayEvent::Join(entering_guest.clone()));
+        let EnteringGuest {
+            actor_id,
+            world_id,
+            is_observer,
+            guest_data,
+            unlocked_achievements,
+            persisted_guest_id,
+        } = entering_guest;
+        if is_observer {
+            self.observers.insert(actor_id);
+        }
+        self.guest_to_world.insert(actor_id, world_id.clone());
+        self.world_to_guest.insert_entry(world_id.clone(), actor_id);
         self.guests.insert(
-            guest.id,
+            actor_id,
             ModuleGuest {
-                id: guest.id,
+                id: actor_id,
                 guest_com: GuestCommunication {
                     connected: true,
                     resources_loaded: false,
                 },
                 last_input_time: Instant::now(),
-                world_id: Some(main_world_id.clone()),
+                world_id: Some(world_id.clone()),
             },
         );
 
-        if let Some(world) = self.world_map.get_mut(&main_world_id) {
-            world.actor_joined_world(guest.id);
+        if let Some(mut guest_data_api) = self.guest_data_api.try_borrow_mut() {
+            guest_data_api.set_guest_data(actor_id, guest_data);
+            guest_data_api.set_unlocked_achievements(actor_id, unlocked_achievements);
+            if let Some(persisted_guest_id) = persisted_guest_id {
+                guest_data_api.set_persisted_guest_id(actor_id, persisted_guest_id);
+            }
         }
 
-        Ok(EnterSuccessState::Entered)
+        if let Some(world) = self.world_map.get_mut(&world_id) {
+            world.actor_joined_world(actor_id);
+        }
     }
 
-    pub fn try_leave(&mut self, _guest: &Guest) -> Result<LeaveSuccessState, LeaveFailedState> {
+    pub fn try_leave(&mut self, guest: &Guest) -> Result<LeaveSuccessState, LeaveFailedState> {
+        self.leave(&guest.id);
         Ok(LeaveSuccessState::Left)
     }
 
+    pub fn leave(&mut self, actor_id: &ActorId) {
+        self.record(ReplayEvent::Leave(*actor_id));
+        self.observers.remove(actor_id);
+        self.position_snapshots.remove(actor_id);
+        self.guest_interests.remove(actor_id);
+        for world in self.world_map.values() {
+            if let Some(mut actor_api) = world.actor_api.try_borrow_mut() {
+                actor_api.remove_round_trip_time(actor_id);
+            }
+        }
+        if let Some(mut guest_data_api) = self.guest_data_api.try_borrow_mut() {
+            guest_data_api.remove_guest_data(actor_id);
+        }
+    }
+
     pub fn get_all_terrain(
         world_map: &HashMap<WorldId, World>,
         world_id: &WorldId,
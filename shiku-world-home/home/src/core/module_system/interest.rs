@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use rapier2d::math::Real;
+
+use crate::core::blueprint::def::{Chunk, LayerKind, TerrainParams};
+use crate::core::blueprint::ecs::def::{Entity, EntityMaps};
+use crate::core::module_system::world::WorldId;
+use crate::core::CantorPair;
+
+pub type GridCell = (i32, i32);
+pub type ChunkPosition = (i32, i32);
+
+/// A guest sees every cell within `view_distance` cells of the cell its focus entity is in.
+#[derive(Debug, Clone, Copy)]
+pub struct InterestSettings {
+    pub cell_size: Real,
+    pub view_distance: i32,
+}
+
+impl Default for InterestSettings {
+    fn default() -> Self {
+        InterestSettings {
+            cell_size: 16.0,
+            view_distance: 2,
+        }
+    }
+}
+
+impl InterestSettings {
+    pub fn from_env() -> InterestSettings {
+        fn var_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+        let defaults = InterestSettings::default();
+
+        InterestSettings {
+            cell_size: var_or("INTEREST_CELL_SIZE", defaults.cell_size),
+            view_distance: var_or("INTEREST_VIEW_DISTANCE", defaults.view_distance).max(0),
+        }
+    }
+
+    pub fn cell_of(&self, (x, y): (Real, Real)) -> GridCell {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Chunks are in view as soon as they overlap one of the cells in view.
+    pub fn is_chunk_in_view(
+        &self,
+        center: GridCell,
+        chunk_position: ChunkPosition,
+        terrain_params: &TerrainParams,
+        pixel_to_meter_conversion: Real,
+    ) -> bool {
+        let chunk_width = (terrain_params.chunk_size * terrain_params.tile_width) as Real
+            / pixel_to_meter_conversion;
+        let chunk_height = (terrain_params.chunk_size * terrain_params.tile_height) as Real
+            / pixel_to_meter_conversion;
+        let view_start = (
+            (center.0 - self.view_distance) as Real * self.cell_size,
+            (center.1 - self.view_distance) as Real * self.cell_size,
+        );
+        let view_end = (
+            (center.0 + self.view_distance + 1) as Real * self.cell_size,
+            (center.1 + self.view_distance + 1) as Real * self.cell_size,
+        );
+        let chunk_start = (
+            chunk_position.0 as Real * chunk_width,
+            chunk_position.1 as Real * chunk_height,
+        );
+
+        chunk_start.0 < view_end.0
+            && chunk_start.0 + chunk_width > view_start.0
+            && chunk_start.1 < view_end.1
+            && chunk_start.1 + chunk_height > view_start.1
+    }
+}
+
+/// Walks up to the child of the scene root, that is the unit entities are sent and removed in.
+pub fn top_level_entity(
+    entities: &EntityMaps,
+    scene_root: Entity,
+    entity: Entity,
+) -> Option<Entity> {
+    let mut current = entity;
+    while let Some(parent) = entities.game_node_parent.get(&current) {
+        if *parent == scene_root {
+            return Some(current);
+        }
+        current = *parent;
+    }
+    None
+}
+
+/// Grid over the top level entities of a world. Entities without a transform have no place
+/// in it and are always in view.
+#[derive(Debug, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<GridCell, Vec<Entity>>,
+    entity_cells: HashMap<Entity, GridCell>,
+    unplaced: Vec<Entity>,
+    top_level: HashMap<Entity, Entity>,
+}
+
+impl SpatialGrid {
+    pub fn build(
+        entities: &EntityMaps,
+        scene_root: Entity,
+        settings: &InterestSettings,
+    ) -> SpatialGrid {
+        let mut grid = SpatialGrid::default();
+        for entity in entities
+            .game_node_children
+            .get(&scene_root)
+            .into_iter()
+            .flatten()
+        {
+            match entities.transforms.get(entity) {
+                Some(transform) => {
+                    let cell = settings.cell_of(transform.position);
+                    grid.cells.entry(cell).or_default().push(*entity);
+                    grid.entity_cells.insert(*entity, cell);
+                }
+                None => grid.unplaced.push(*entity),
+            }
+            let mut descendants = vec![*entity];
+            while let Some(descendant) = descendants.pop() {
+                grid.top_level.insert(descendant, *entity);
+                if let Some(children) = entities.game_node_children.get(&descendant) {
+                    descendants.extend(children.iter().copied());
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn top_level_of(&self, entity: &Entity) -> Option<Entity> {
+        self.top_level.get(entity).copied()
+    }
+
+    pub fn cell_of_entity(&self, entity: &Entity) -> Option<GridCell> {
+        self.entity_cells.get(entity).copied()
+    }
+
+    pub fn all_entities(&self) -> HashSet<Entity> {
+        self.entity_cells
+            .keys()
+            .chain(self.unplaced.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn entities_in_view(
+        &self,
+        center: GridCell,
+        settings: &InterestSettings,
+    ) -> HashSet<Entity> {
+        let mut in_view: HashSet<Entity> = self.unplaced.iter().copied().collect();
+        for x in center.0 - settings.view_distance..=center.0 + settings.view_distance {
+            for y in center.1 - settings.view_distance..=center.1 + settings.view_distance {
+                if let Some(cell_entities) = self.cells.get(&(x, y)) {
+                    in_view.extend(cell_entities.iter().copied());
+                }
+            }
+        }
+        in_view
+    }
+}
+
+/// What a guest's client currently holds, `center` is `None` until the first pass placed it.
+#[derive(Debug)]
+pub struct GuestInterest {
+    pub world_id: WorldId,
+    pub center: Option<GridCell>,
+    pub entities: HashSet<Entity>,
+    pub chunks: HashSet<(LayerKind, ChunkPosition)>,
+}
+
+impl GuestInterest {
+    pub fn new(
+        world_id: WorldId,
+        entities: HashSet<Entity>,
+        chunks: HashSet<(LayerKind, ChunkPosition)>,
+    ) -> GuestInterest {
+        GuestInterest {
+            world_id,
+            center: None,
+            entities,
+            chunks,
+        }
+    }
+
+    pub fn wants(&self, scope: &InterestScope) -> bool {
+        match scope {
+            InterestScope::World => true,
+            InterestScope::Entity(entity) => entity
+                .as_ref()
+                .is_some_and(|entity| self.entities.contains(entity)),
+            InterestScope::Chunk(layer_kind, chunk_position) => {
+                self.chunks.contains(&(layer_kind.clone(), *chunk_position))
+            }
+        }
+    }
+}
+
+/// What an event is about, entity events carry the top level entity they belong to.
+#[derive(Debug, Clone)]
+pub enum InterestScope {
+    World,
+    Entity(Option<Entity>),
+    Chunk(LayerKind, ChunkPosition),
+}
+
+pub fn chunks_in_view<'a>(
+    layer_data: &'a HashMap<LayerKind, HashMap<CantorPair, Chunk>>,
+    center: GridCell,
+    settings: &'a InterestSettings,
+    terrain_params: &'a TerrainParams,
+    pixel_to_meter_conversion: Real,
+) -> impl Iterator<Item = (&'a LayerKind, &'a Chunk)> + 'a {
+    layer_data.iter().flat_map(move |(layer_kind, chunks)| {
+        chunks
+            .values()
+            .filter(move |chunk| {
+                settings.is_chunk_in_view(
+                    center,
+                    chunk.position,
+                    terrain_params,
+                    pixel_to_meter_conversion,
+                )
+            })
+            .map(move |chunk| (layer_kind, chunk))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_overlapping_the_view_are_in_view() {
+        let settings = InterestSettings {
+            cell_size: 10.0,
+            view_distance: 1,
+        };
+        let terrain_params = TerrainParams {
+            chunk_size: 10,
+            tile_width: 32,
+            tile_height: 32,
+        };
+
+        assert!(settings.is_chunk_in_view((0, 0), (-1, -1), &terrain_params, 32.0));
+        assert!(settings.is_chunk_in_view((0, 0), (1, 1), &terrain_params, 32.0));
+        assert!(!settings.is_chunk_in_view((0, 0), (2, 0), &terrain_params, 32.0));
+        assert!(!settings.is_chunk_in_view((0, 0), (-2, 0), &terrain_params, 32.0));
+    }
+}
@@ -8,6 +8,17 @@ pub mod game_instance;
 
 pub mod world;
 
+pub mod position_snapshot;
+
+pub mod interest;
+
+pub mod prediction;
+
 pub mod terrain_manager;
 
 pub mod script_types;
+
+pub mod replay;
+
+#[cfg(test)]
+pub mod simulation;
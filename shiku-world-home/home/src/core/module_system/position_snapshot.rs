@@ -0,0 +1,254 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+
+use rapier2d::math::Real;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::blueprint::ecs::def::Entity;
+use crate::core::blueprint::scene::def::Transform;
+use crate::core::module_system::prediction::{InputSequence, Tick};
+use crate::core::module_system::world::WorldId;
+
+pub type SnapshotId = u32;
+pub type QuantizedPosition = (i32, i32, i32);
+pub type QuantizedState = HashMap<Entity, QuantizedPosition>;
+
+/// Changes smaller than the precision are never sent, entities only show up in a snapshot
+/// once their quantized value changes.
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotSettings {
+    pub position_precision: Real,
+    pub rotation_precision: Real,
+    pub keyframe_interval: u32,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        SnapshotSettings {
+            position_precision: 0.01,
+            rotation_precision: 0.001,
+            keyframe_interval: 60,
+        }
+    }
+}
+
+impl SnapshotSettings {
+    pub fn from_env() -> SnapshotSettings {
+        fn var_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+        let defaults = SnapshotSettings::default();
+
+        SnapshotSettings {
+            position_precision: var_or("SNAPSHOT_POSITION_PRECISION", defaults.position_precision),
+            rotation_precision: var_or("SNAPSHOT_ROTATION_PRECISION", defaults.rotation_precision),
+            keyframe_interval: var_or("SNAPSHOT_KEYFRAME_INTERVAL", defaults.keyframe_interval)
+                .max(1),
+        }
+    }
+
+    pub fn quantize(&self, transform: &Transform) -> QuantizedPosition {
+        (
+            (transform.position.0 / self.position_precision).round() as i32,
+            (transform.position.1 / self.position_precision).round() as i32,
+            (transform.rotation / self.rotation_precision).round() as i32,
+        )
+    }
+
+    pub fn quantize_all(&self, transforms: &HashMap<Entity, Transform>) -> QuantizedState {
+        transforms
+            .iter()
+            .map(|(entity, transform)| (*entity, self.quantize(transform)))
+            .collect()
+    }
+}
+
+/// Keyframes have no `base` and carry every entity with its quantized value. Otherwise only
+/// entities that differ from `base` are listed, as difference to their value in `base`.
+/// Entities missing from `base` count as `(0, 0, 0)` there. `last_processed_input` is the
+/// newest input of the receiving guest that already moved their possessed entity.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export)]
+pub struct PositionSnapshot {
+    pub id: SnapshotId,
+    pub tick: Tick,
+    pub base: Option<SnapshotId>,
+    pub last_processed_input: Option<InputSequence>,
+    pub position_precision: Real,
+    pub rotation_precision: Real,
+    pub entities: Vec<(Entity, i32, i32, i32)>,
+}
+
+/// One per guest, deltas are built against the newest snapshot the guest acknowledged.
+#[derive(Debug)]
+pub struct SnapshotEncoder {
+    pub world_id: WorldId,
+    next_id: SnapshotId,
+    since_keyframe: u32,
+    acked: Option<(SnapshotId, QuantizedState)>,
+    sent: VecDeque<(SnapshotId, QuantizedState)>,
+    last_sent_input: Option<InputSequence>,
+}
+
+impl SnapshotEncoder {
+    pub fn new(world_id: WorldId) -> SnapshotEncoder {
+        SnapshotEncoder {
+            world_id,
+            next_id: 0,
+            since_keyframe: u32::MAX,
+            acked: None,
+            sent: VecDeque::new(),
+            last_sent_input: None,
+        }
+    }
+
+    pub fn ack(&mut self, snapshot_id: SnapshotId) {
+        if let Some(index) = self.sent.iter().position(|(id, _)| *id == snapshot_id) {
+            self.acked = self.sent.drain(..=index).next_back();
+        }
+    }
+
+    /// Returns `None` when nothing changed since the last snapshot and no keyframe is due.
+    pub fn encode(
+        &mut self,
+        tick: Tick,
+        state: &QuantizedState,
+        last_processed_input: Option<InputSequence>,
+        settings: &SnapshotSettings,
+    ) -> Option<PositionSnapshot> {
+        let keyframe_due = self.since_keyframe >= settings.keyframe_interval;
+        if !keyframe_due
+            && last_processed_input == self.last_sent_input
+            && self
+                .sent
+                .back()
+                .is_some_and(|(_, last_sent)| last_sent == state)
+        {
+            return None;
+        }
+
+        let base = if keyframe_due {
+            None
+        } else {
+            self.acked.as_ref()
+        };
+        let entities = state
+            .iter()
+            .filter_map(|(entity, (x, y, rotation))| {
+                let (base_x, base_y, base_rotation) = base
+                    .and_then(|(_, base_state)| base_state.get(entity))
+                    .copied()
+                    .unwrap_or_default();
+                if base.is_some() && (base_x, base_y, base_rotation) == (*x, *y, *rotation) {
+                    return None;
+                }
+                Some((
+                    *entity,
+                    x.wrapping_sub(base_x),
+                    y.wrapping_sub(base_y),
+                    rotation.wrapping_sub(base_rotation),
+                ))
+            })
+            .collect();
+
+        let snapshot = PositionSnapshot {
+            id: self.next_id,
+            tick,
+            base: base.map(|(base_id, _)| *base_id),
+            last_processed_input,
+            position_precision: settings.position_precision,
+            rotation_precision: settings.rotation_precision,
+            entities,
+        };
+        self.since_keyframe = if keyframe_due {
+            1
+        } else {
+            self.since_keyframe + 1
+        };
+        self.next_id = self.next_id.wrapping_add(1);
+        self.last_sent_input = last_processed_input;
+        self.sent.push_back((snapshot.id, state.clone()));
+        if self.sent.len() > settings.keyframe_interval as usize {
+            self.sent.pop_front();
+        }
+
+        Some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(base: &QuantizedState, snapshot: &PositionSnapshot) -> QuantizedState {
+        let mut state = base.clone();
+        for (entity, x, y, rotation) in &snapshot.entities {
+            let (base_x, base_y, base_rotation) = base.get(entity).copied().unwrap_or_default();
+            state.insert(*entity, (base_x + x, base_y + y, base_rotation + rotation));
+        }
+        state
+    }
+
+    #[test]
+    fn test_deltas_are_against_the_acknowledged_snapshot() {
+        let settings = SnapshotSettings {
+            keyframe_interval: 3,
+            ..SnapshotSettings::default()
+        };
+        let mut encoder = SnapshotEncoder::new("world".into());
+        let mut state = QuantizedState::from([(Entity(1), (100, 0, 0)), (Entity(2), (5, 5, 5))]);
+
+        let keyframe = encoder.encode(1, &state, None, &settings).unwrap();
+        assert_eq!(keyframe.base, None);
+        assert_eq!(keyframe.entities.len(), 2);
+        assert_eq!(encoder.encode(1, &state, None, &settings), None);
+
+        encoder.ack(keyframe.id);
+        state.insert(Entity(1), (103, 0, 0));
+        let first = encoder.encode(1, &state, None, &settings).unwrap();
+        assert_eq!(first.base, Some(keyframe.id));
+        assert_eq!(first.entities, vec![(Entity(1), 3, 0, 0)]);
+
+        state.insert(Entity(1), (104, 0, 0));
+        let second = encoder.encode(1, &state, None, &settings).unwrap();
+        assert_eq!(second.base, Some(keyframe.id));
+        assert_eq!(
+            apply(&apply(&QuantizedState::new(), &keyframe), &second),
+            state
+        );
+
+        state.insert(Entity(2), (6, 5, 5));
+        let third = encoder.encode(1, &state, None, &settings).unwrap();
+        assert_eq!(third.base, None);
+        assert_eq!(third.entities.len(), 2);
+
+        let processed = encoder.encode(2, &state, Some(7), &settings).unwrap();
+        assert_eq!(processed.last_processed_input, Some(7));
+        assert_eq!(processed.base, Some(keyframe.id));
+        assert_eq!(encoder.encode(3, &state, Some(7), &settings), None);
+    }
+
+    #[test]
+    fn test_changes_below_precision_are_skipped() {
+        let settings = SnapshotSettings::default();
+        let transform = |x: Real| Transform {
+            position: (x, 0.0),
+            scale: (1.0, 1.0),
+            velocity: (0.0, 0.0),
+            rotation: 0.0,
+        };
+
+        assert_eq!(
+            settings.quantize(&transform(1.0)),
+            settings.quantize(&transform(1.004))
+        );
+        assert_ne!(
+            settings.quantize(&transform(1.0)),
+            settings.quantize(&transform(1.01))
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use rapier2d::math::Real;
+
+use crate::core::module::GuestInput;
+use crate::core::TARGET_FRAME_DURATION;
+
+pub type Tick = u32;
+pub type InputSequence = u32;
+
+/// How far a possessed kinematic character wants to move in one tick, before collisions.
+/// It only depends on the input and is all f32, a client that wants to predict its own
+/// character can mirror it with `Math.fround` and gets the same result.
+pub fn kinematic_input_translation(input: &GuestInput, speed: Real) -> (Real, Real) {
+    let axis = |analog: Real, negative: bool, positive: bool| -> Real {
+        if analog != 0.0 {
+            analog.clamp(-1.0, 1.0)
+        } else {
+            (positive as i8 - negative as i8) as Real
+        }
+    };
+    let x = axis(input.x_axis, input.left, input.right);
+    let y = axis(input.y_axis, input.up, input.down);
+    let length = (x * x + y * y).sqrt();
+    let scale = if length > 1.0 { 1.0 / length } else { 1.0 };
+    let step = speed * TARGET_FRAME_DURATION / 1000.0;
+
+    (x * scale * step, y * scale * step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagonal_input_is_not_faster() {
+        let input = GuestInput {
+            right: true,
+            down: true,
+            ..GuestInput::default()
+        };
+        let (x, y) = kinematic_input_translation(&input, 60.0);
+
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 0.0001);
+        assert!(x > 0.0 && y > 0.0);
+        assert_eq!(
+            kinematic_input_translation(&GuestInput::default(), 60.0),
+            (0.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+use flume::{unbounded, Receiver, Sender};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use crate::core::blueprint::def::{BlueprintError, Module, ModuleId, ResourceKind, ResourcePath};
+use crate::core::blueprint::ecs::def::{Entity, EntityUpdate};
+use crate::core::blueprint::resource_loader::Blueprint;
+use crate::core::blueprint::scene::def::{GameNodeKind, Transform};
+use crate::core::guest::ActorId;
+use crate::core::module::{create_module_communication_output, GuestInput};
+use crate::core::module_system::def::{DynamicGameModule, EnteringGuest};
+use crate::core::module_system::game_instance::GameInstanceId;
+use crate::core::module_system::prediction::Tick;
+use crate::core::module_system::world::WorldId;
+use crate::core::stable_hash;
+
+/// Ten seconds of ticks, a crash loses at most that much of a recording.
+const FLUSH_INTERVAL: Tick = 600;
+
+/// Everything that reaches a game instance from outside, the rest follows from the seed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ReplayEvent {
+    Join(EnteringGuest),
+    Leave(ActorId),
+    ControlInput(ActorId, GuestInput),
+    SetObserver(ActorId, bool),
+    Disconnected(ActorId),
+    Reconnected(ActorId),
+    RoundTripTime(ActorId, u32),
+    UpdateEntity(WorldId, EntityUpdate),
+    AddEntity(WorldId, Entity, GameNodeKind),
+    RemoveEntity(WorldId, Entity),
+    ResetWorld(WorldId),
+}
+
+/// First line of a replay file, written once when recording starts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayHeader {
+    pub module_id: ModuleId,
+    pub instance_id: GameInstanceId,
+    pub seed: u64,
+    pub blueprint_versions: BTreeMap<ResourcePath, String>,
+}
+
+impl ReplayHeader {
+    pub fn new(module: &Module, instance_id: GameInstanceId, seed: u64) -> ReplayHeader {
+        ReplayHeader {
+            module_id: module.id.clone(),
+            instance_id,
+            seed,
+            blueprint_versions: blueprint_versions(module),
+        }
+    }
+}
+
+/// Every line after the header. Each flush ends with `Frames`, so a replay of an instance that
+/// crashed stops at the last flush.
+#[derive(Debug, Serialize, Deserialize)]
+enum ReplayLine {
+    Event(Tick, Box<ReplayEvent>),
+    Frames(Tick),
+}
+
+/// Events are stamped with the number of updates that ran before them.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub frames: Tick,
+    pub events: Vec<(Tick, ReplayEvent)>,
+}
+
+/// What a replay is compared by, every named entity's transform per world.
+pub type WorldState = BTreeMap<WorldId, BTreeMap<Entity, (String, Transform)>>;
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, BlueprintError> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header = serde_json::from_str(&lines.next().transpose()?.unwrap_or_default())?;
+        let mut replay = Replay {
+            header,
+            frames: 0,
+            events: Vec::new(),
+        };
+        for line in lines {
+            match serde_json::from_str(&line?) {
+                Ok(ReplayLine::Event(tick, event)) => replay.events.push((tick, *event)),
+                Ok(ReplayLine::Frames(frames)) => replay.frames = frames,
+                Err(err) => {
+                    error!("Replay {:?} ends in a broken line: {:?}", path, err);
+                    break;
+                }
+            }
+        }
+        Ok(replay)
+    }
+
+    /// Resources that changed since recording, the replay can drift from what the guests saw.
+    pub fn outdated_blueprints(&self, module: &Module) -> Vec<ResourcePath> {
+        let current_versions = blueprint_versions(module);
+        self.header
+            .blueprint_versions
+            .iter()
+            .filter(|(path, version)| current_versions.get(*path) != Some(*version))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Runs the recorded instance from scratch without conductor or guests.
+    pub fn play(&self, module: &Module) -> DynamicGameModule {
+        for path in self.outdated_blueprints(module) {
+            error!("Blueprint {} changed since the replay was recorded.", path);
+        }
+        let (output_sender, output_receiver) = create_module_communication_output();
+        let (mut dynamic_module, _input_sender) = DynamicGameModule::create(
+            self.header.instance_id.clone(),
+            module,
+            output_sender,
+            Arc::new(Mutex::new(HashMap::new())),
+            self.header.seed,
+        );
+
+        let mut events = self.events.iter().peekable();
+        for frame in 0..=self.frames {
+            while let Some((_, event)) = events.next_if(|(tick, _)| *tick <= frame) {
+                apply_event(&mut dynamic_module, event.clone());
+            }
+            if frame < self.frames {
+                dynamic_module.update(module);
+                output_receiver.module_to_system_receiver.drain();
+                output_receiver.game_system_to_guest_receiver.drain();
+                output_receiver.position_receiver.drain();
+            }
+        }
+
+        dynamic_module
+    }
+
+    /// Plays a replay file against the modules in the out dir, for `home replay <file>`.
+    pub fn play_file(path: &Path) -> Result<WorldState, BlueprintError> {
+        let replay = Replay::load(path)?;
+        let module = Blueprint::get_all_modules()?
+            .into_iter()
+            .find(|module| module.id == replay.header.module_id)
+            .ok_or(BlueprintError::FileDoesNotExist(
+                replay.header.module_id.clone(),
+            ))?;
+        Ok(world_state(&replay.play(&module)))
+    }
+}
+
+fn apply_event(dynamic_module: &mut DynamicGameModule, event: ReplayEvent) {
+    match event {
+        ReplayEvent::Join(entering_guest) => dynamic_module.enter(entering_guest),
+        ReplayEvent::Leave(actor_id) => dynamic_module.leave(&actor_id),
+        ReplayEvent::ControlInput(actor_id, input) => DynamicGameModule::set_actor_input(
+            &dynamic_module.guest_to_world,
+            &dynamic_module.admin_to_world,
+            &dynamic_module.observers,
+            &mut dynamic_module.world_map,
+            &actor_id,
+            input,
+        ),
+        ReplayEvent::SetObserver(actor_id, is_observer) => {
+            dynamic_module.set_observer(&actor_id, is_observer)
+        }
+        ReplayEvent::Disconnected(actor_id) => dynamic_module.actor_disconnected(&actor_id),
+        ReplayEvent::Reconnected(actor_id) => dynamic_module.actor_reconnected(&actor_id),
+        ReplayEvent::RoundTripTime(actor_id, round_trip_time) => {
+            dynamic_module.set_round_trip_time(&actor_id, round_trip_time)
+        }
+        ReplayEvent::UpdateEntity(world_id, entity_update) => {
+            dynamic_module.apply_admin_entity_update(&world_id, entity_update)
+        }
+        ReplayEvent::AddEntity(world_id, parent_entity, game_node) => {
+            dynamic_module.add_entity(&world_id, parent_entity, game_node)
+        }
+        ReplayEvent::RemoveEntity(world_id, entity) => {
+            dynamic_module.remove_entity(&world_id, entity)
+        }
+        ReplayEvent::ResetWorld(world_id) => {
+            if let Err(err) = dynamic_module.reset_world(&world_id) {
+                error!("Could not reset world {} in replay: {:?}", world_id, err);
+            }
+        }
+    }
+}
+
+fn version_of<T: Serialize>(resource: Result<T, BlueprintError>) -> Option<String> {
+    // Going through a json value sorts map keys, hash maps would hash differently every run.
+    let value = serde_json::to_value(resource.ok()?).ok()?;
+    let bytes = serde_json::to_vec(&value).ok()?;
+    Some(format!("{:016x}", stable_hash(bytes)))
+}
+
+pub fn blueprint_versions(module: &Module) -> BTreeMap<ResourcePath, String> {
+    let mut versions = BTreeMap::new();
+    if let Some(version) = version_of(Ok(module)) {
+        versions.insert(format!("{}.module.json", module.name), version);
+    }
+    for resource in &module.resources {
+        let path = PathBuf::from(&resource.path);
+        let version = match resource.kind {
+            ResourceKind::Map => version_of(Blueprint::load_map(path)),
+            ResourceKind::Scene => version_of(Blueprint::load_scene(path)),
+            ResourceKind::Script => version_of(Blueprint::load_script(path)),
+            ResourceKind::Tileset => version_of(Blueprint::load_tileset(path)),
+            ResourceKind::CharacterAnimation => {
+                version_of(Blueprint::load_character_animation(path))
+            }
+            ResourceKind::Unknown => None,
+        };
+        if let Some(version) = version {
+            versions.insert(resource.path.clone(), version);
+        }
+    }
+    versions
+}
+
+pub fn world_state(dynamic_module: &DynamicGameModule) -> WorldState {
+    dynamic_module
+        .world_map
+        .iter()
+        .map(|(world_id, world)| {
+            let entities = world
+                .ecs
+                .shared
+                .try_borrow()
+                .map(|shared| {
+                    shared
+                        .entities
+                        .game_node_name
+                        .iter()
+                        .filter_map(|(entity, name)| {
+                            let transform = shared.entities.transforms.get(entity)?;
+                            Some((*entity, (name.clone(), transform.clone())))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (world_id.clone(), entities)
+        })
+        .collect()
+}
+
+/// Records an instance while it runs. Events are handed to a writer thread every few seconds
+/// and when the instance closes, the thread only appends them to the file.
+pub struct ReplayRecorder {
+    frames: Tick,
+    pending_lines: Vec<ReplayLine>,
+    line_sender: Sender<Vec<ReplayLine>>,
+    writer: JoinHandle<()>,
+}
+
+impl ReplayRecorder {
+    pub fn new(header: ReplayHeader, path: PathBuf) -> ReplayRecorder {
+        let (line_sender, line_receiver) = unbounded();
+        let writer = thread::spawn(move || {
+            if let Err(err) = write_replay(&path, &header, line_receiver) {
+                error!("Could not write replay to {:?}: {:?}", path, err);
+            }
+        });
+        ReplayRecorder {
+            frames: 0,
+            pending_lines: Vec::new(),
+            line_sender,
+            writer,
+        }
+    }
+
+    /// Only records when `REPLAY_DIR` is set, replays go to `<dir>/<module>/<instance>.replay.jsonl`.
+    pub fn from_env(
+        module: &Module,
+        instance_id: &GameInstanceId,
+        seed: u64,
+    ) -> Option<ReplayRecorder> {
+        let dir = env::var("REPLAY_DIR").ok().filter(|dir| !dir.is_empty())?;
+        let path = PathBuf::from(dir)
+            .join(&module.name)
+            .join(format!("{}.replay.jsonl", instance_id));
+        debug!("Recording game instance {} to {:?}", instance_id, path);
+        Some(ReplayRecorder::new(
+            ReplayHeader::new(module, instance_id.clone(), seed),
+            path,
+        ))
+    }
+
+    pub fn record(&mut self, event: ReplayEvent) {
+        self.pending_lines
+            .push(ReplayLine::Event(self.frames, Box::new(event)));
+    }
+
+    pub fn frame_done(&mut self) {
+        self.frames += 1;
+        if self.frames.is_multiple_of(FLUSH_INTERVAL) {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let mut lines = std::mem::take(&mut self.pending_lines);
+        lines.push(ReplayLine::Frames(self.frames));
+        if let Err(err) = self.line_sender.send(lines) {
+            error!("Replay writer is gone: {:?}", err);
+        }
+    }
+
+    /// Hands over the rest of the recording, the writer finishes on its own.
+    pub fn finish(mut self) -> JoinHandle<()> {
+        self.flush();
+        self.writer
+    }
+}
+
+fn write_replay(
+    path: &Path,
+    header: &ReplayHeader,
+    line_receiver: Receiver<Vec<ReplayLine>>,
+) -> Result<(), BlueprintError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, header)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    for lines in line_receiver.iter() {
+        for line in lines {
+            serde_json::to_writer(&mut writer, &line)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::blueprint::ecs::def::EntityUpdateKind;
+    use crate::core::blueprint::ecs::game_node_script::ScopeCacheValue;
+    use crate::core::module_system::simulation::Simulation;
+
+    fn scope_value(dynamic_module: &DynamicGameModule, key: &str) -> Vec<ScopeCacheValue> {
+        dynamic_module
+            .world_map
+            .values()
+            .flat_map(|world| world.ecs.entity_scripts.values())
+            .filter_map(|script| script.scope.get(key).map(|value| value.clone().into()))
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_reproduces_recorded_world_state() {
+        let mut simulation = Simulation::load("walker").unwrap();
+        let world_id = simulation.main_world_id().unwrap();
+        let path =
+            env::temp_dir().join(format!("shiku-replay-{}.replay.jsonl", std::process::id()));
+        simulation.dynamic_module.recorder = Some(ReplayRecorder::new(
+            ReplayHeader::new(
+                &simulation.module,
+                "recorded".into(),
+                simulation.dynamic_module.seed,
+            ),
+            path.clone(),
+        ));
+
+        simulation.join(1, world_id.clone());
+        simulation.input_at(
+            5,
+            1,
+            GuestInput {
+                right: true,
+                ..GuestInput::default()
+            },
+        );
+        simulation.input_at(15, 1, GuestInput::default());
+        simulation.run(20);
+        let walker = simulation.entity(&world_id, "walker").unwrap();
+        simulation.dynamic_module.apply_admin_entity_update(
+            &world_id,
+            EntityUpdate {
+                id: walker,
+                kind: EntityUpdateKind::PositionRotation((3.0, 4.0, 0.0)),
+            },
+        );
+        simulation.run(10);
+
+        let recorder = simulation.dynamic_module.recorder.take().unwrap();
+        recorder.finish().join().unwrap();
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.frames, 30);
+        assert!(replay
+            .header
+            .blueprint_versions
+            .contains_key("walker/walker.script.json"));
+        assert!(replay.outdated_blueprints(&simulation.module).is_empty());
+
+        let replayed = replay.play(&simulation.module);
+        assert_eq!(
+            serde_json::to_value(world_state(&replayed)).unwrap(),
+            serde_json::to_value(world_state(&simulation.dynamic_module)).unwrap()
+        );
+        assert_eq!(
+            scope_value(&replayed, "roll"),
+            scope_value(&simulation.dynamic_module, "roll")
+        );
+        assert_eq!(
+            scope_value(&replayed, "joined"),
+            vec![ScopeCacheValue::Integer(1)]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::core::blueprint::def::{BlueprintError, Module};
+use crate::core::blueprint::ecs::def::Entity;
+use crate::core::blueprint::ecs::game_node_script::ScopeCacheValue;
+use crate::core::blueprint::resource_cache::{cache_resources, get_resource_cache};
+use crate::core::blueprint::resource_loader::Blueprint;
+use crate::core::blueprint::scene::def::Transform;
+use crate::core::guest::{ActorId, Guest};
+use crate::core::module::{
+    create_module_communication_output, GameSystemToGuestEvent, GuestEvent, GuestInput,
+    GuestToModuleEvent, ModuleInputSender, ModuleInstanceEvent, ModuleOutputReceiver,
+};
+use crate::core::module_system::def::DynamicGameModule;
+use crate::core::module_system::prediction::Tick;
+use crate::core::module_system::world::{World, WorldId};
+use crate::core::time_sync::RoundTripTimer;
+use crate::core::{get_out_dir, send_and_log_error};
+
+pub const FIXTURES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+/// Runs the worlds of a fixture module without conductor, websocket or out dir. Virtual actors
+/// enter like guests whose client finished loading, their inputs go through the module's
+/// input channel at the tick they were scripted for.
+pub struct Simulation {
+    pub module: Module,
+    pub dynamic_module: DynamicGameModule,
+    input_sender: ModuleInputSender,
+    output_receiver: ModuleOutputReceiver,
+    scripted_inputs: BTreeMap<Tick, Vec<(ActorId, GuestInput)>>,
+    guest_events: HashMap<ActorId, Vec<GameSystemToGuestEvent>>,
+    tick: Tick,
+}
+
+impl Simulation {
+    /// Fixtures live in `fixtures/<module_name>/` and reference their resources relative to
+    /// `fixtures/`, the module blueprint is `<module_name>.module.json`.
+    pub fn load(module_name: &str) -> Result<Simulation, BlueprintError> {
+        let fixtures_dir = Path::new(FIXTURES_DIR);
+        cache_resources(&fixtures_dir.join(module_name), fixtures_dir)?;
+        let module: Module = Blueprint::load(
+            get_out_dir()
+                .join(module_name)
+                .join(format!("{}.module.json", module_name)),
+            &get_resource_cache().modules,
+        )?;
+
+        let (output_sender, output_receiver) = create_module_communication_output();
+        let (dynamic_module, input_sender) = DynamicGameModule::create(
+            "simulation".into(),
+            &module,
+            output_sender,
+            Arc::new(Mutex::new(HashMap::new())),
+            0,
+        );
+
+        Ok(Simulation {
+            module,
+            dynamic_module,
+            input_sender,
+            output_receiver,
+            scripted_inputs: BTreeMap::new(),
+            guest_events: HashMap::new(),
+            tick: 0,
+        })
+    }
+
+    pub fn main_world_id(&self) -> Option<WorldId> {
+        let main_map = self.module.main_map.as_ref()?;
+        Blueprint::load_map(main_map.into())
+            .ok()
+            .map(|game_map| game_map.world_id)
+    }
+
+    pub fn join(&mut self, actor_id: ActorId, world_id: WorldId) {
+        let guest = Guest {
+            id: actor_id,
+            session_id: format!("simulation-{}", actor_id),
+            current_module_id: Some(self.module.id.clone()),
+            current_instance_id: Some(self.dynamic_module.instance_id.clone()),
+            pending_module_exit: None,
+            login_data: None,
+            ws_connection_id: None,
+            persisted_guest: None,
+            round_trip_time: None,
+            round_trip_timer: RoundTripTimer::default(),
+        };
+        if let Err(err) = self
+            .dynamic_module
+            .try_enter(&guest, world_id, &String::new())
+        {
+            panic!("Virtual actor {} could not enter: {:?}", actor_id, err);
+        }
+        self.send(actor_id, GuestToModuleEvent::GameSetupDone);
+    }
+
+    /// Scripted inputs replace whatever the actor pressed before, like a client would.
+    pub fn input_at(&mut self, tick: Tick, actor_id: ActorId, input: GuestInput) {
+        self.scripted_inputs
+            .entry(tick)
+            .or_default()
+            .push((actor_id, input));
+    }
+
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    pub fn run(&mut self, ticks: Tick) {
+        for _ in 0..ticks {
+            if let Some(inputs) = self.scripted_inputs.remove(&self.tick) {
+                for (actor_id, input) in inputs {
+                    self.send(actor_id, GuestToModuleEvent::ControlInput(input));
+                }
+            }
+            self.dynamic_module.update(&self.module);
+            for event in self.output_receiver.game_system_to_guest_receiver.drain() {
+                self.guest_events
+                    .entry(event.guest_id)
+                    .or_default()
+                    .push(event.event_type.event_type);
+            }
+            self.tick += 1;
+        }
+    }
+
+    pub fn world(&self, world_id: &WorldId) -> Option<&World> {
+        self.dynamic_module.world_map.get(world_id)
+    }
+
+    pub fn entity(&self, world_id: &WorldId, name: &str) -> Option<Entity> {
+        let shared = self.world(world_id)?.ecs.shared.try_borrow()?;
+        shared
+            .entities
+            .game_node_name
+            .iter()
+            .find(|(_, entity_name)| *entity_name == name)
+            .map(|(entity, _)| *entity)
+    }
+
+    pub fn transform(&self, world_id: &WorldId, name: &str) -> Option<Transform> {
+        let entity = self.entity(world_id, name)?;
+        let shared = self.world(world_id)?.ecs.shared.try_borrow()?;
+        shared.entities.transforms.get(&entity).cloned()
+    }
+
+    pub fn scope_value(
+        &self,
+        world_id: &WorldId,
+        name: &str,
+        key: &str,
+    ) -> Option<ScopeCacheValue> {
+        let entity = self.entity(world_id, name)?;
+        let script = self.world(world_id)?.ecs.entity_scripts.get(&entity)?;
+        script.scope.get(key).map(|value| value.clone().into())
+    }
+
+    pub fn take_guest_events(&mut self, actor_id: &ActorId) -> Vec<GameSystemToGuestEvent> {
+        self.guest_events.remove(actor_id).unwrap_or_default()
+    }
+
+    fn send(&mut self, actor_id: ActorId, event_type: GuestToModuleEvent) {
+        send_and_log_error(
+            &mut self.input_sender.guest_to_module_sender,
+            GuestEvent {
+                guest_id: actor_id,
+                event_type: ModuleInstanceEvent {
+                    module_id: self.module.id.clone(),
+                    instance_id: self.dynamic_module.instance_id.clone(),
+                    world_id: None,
+                    event_type,
+                },
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_input_moves_possessed_walker() {
+        let mut simulation = Simulation::load("walker").unwrap();
+        let world_id = simulation.main_world_id().unwrap();
+        let start = simulation.transform(&world_id, "walker").unwrap();
+
+        simulation.join(1, world_id.clone());
+        simulation.input_at(
+            5,
+            1,
+            GuestInput {
+                right: true,
+                ..GuestInput::default()
+            },
+        );
+        simulation.input_at(15, 1, GuestInput::default());
+        simulation.run(30);
+        assert_eq!(simulation.tick(), 30);
+
+        let end = simulation.transform(&world_id, "walker").unwrap();
+        // Ten ticks held right at 60 per second is one unit per tick.
+        assert!(
+            (end.position.0 - start.position.0 - 10.0).abs() < 0.001,
+            "{:?}",
+            end
+        );
+        assert_eq!(end.position.1, start.position.1);
+        assert_eq!(
+            simulation.scope_value(&world_id, "walker", "joined"),
+            Some(ScopeCacheValue::Integer(1))
+        );
+
+        let events = simulation.take_guest_events(&1);
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameSystemToGuestEvent::ShowScene(_))));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, GameSystemToGuestEvent::Frame(_)))
+                .count(),
+            30
+        );
+    }
+}
@@ -1,18 +1,28 @@
 use std::collections::{HashMap, HashSet};
 
+use chrono::Utc;
 use log::{debug, error};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rapier2d::prelude::*;
-use rhai::{exported_module, Dynamic, Engine, FuncRegistration, Module as RhaiModule};
+use rhai::{
+    exported_module, Dynamic, Engine, EvalAltResult, FuncRegistration, Module as RhaiModule,
+};
+use serde_json::Value as JsonValue;
 
 use crate::core::blueprint::character_animation::{CharacterDirection, StateId};
-use crate::core::blueprint::def::{GameMap, Gid, JsonResource, ResourcePath, TerrainParams};
+use crate::core::blueprint::def::{
+    AchievementId, GameMap, Gid, JsonResource, LeaderboardId, ModuleId, ResourcePath, TerrainParams,
+};
 use crate::core::blueprint::ecs::def::{ECSShared, Entity, EntityMaps, EntityUpdate, ECS};
 use crate::core::blueprint::ecs::game_node_script::GameNodeScriptFunction;
 use crate::core::blueprint::resource_loader::Blueprint;
 use crate::core::blueprint::scene::def::{CollisionShape, GameNodeKind, Transform};
 use crate::core::guest::ActorId;
-use crate::core::module::GuestInput;
+use crate::core::leaderboard::{lock_leaderboards, LeaderboardMap, MAX_LEADERBOARD_QUERY_SIZE};
+use crate::core::module::{GuestInput, GuestStateChange};
 use crate::core::module_system::error::CreateWorldError;
+use crate::core::module_system::prediction::{kinematic_input_translation, InputSequence, Tick};
 use crate::core::module_system::script_types::CharacterDirectionModule;
 use crate::core::module_system::terrain_manager::TerrainManager;
 use crate::core::rapier_simulation::def::RapierSimulation;
@@ -21,20 +31,30 @@ use crate::core::{ApiShare, TARGET_FRAME_DURATION};
 pub type WorldId = String;
 
 const MIN_EQUAL_FLOAT_VALUE: f32 = 0.00001;
+const MAX_GUEST_DATA_KEY_LENGTH: usize = 64;
 
 pub struct World {
     pub world_id: WorldId,
     pub game_map_path: ResourcePath,
     pub physics: ApiShare<RapierSimulation>,
     pub actor_api: ApiShare<ActorApi>,
+    pub guest_data_api: ApiShare<GuestDataApi>,
+    pub leaderboards: LeaderboardMap,
     pub terrain_manager: TerrainManager,
     pub ecs: ECS,
     pub script_engine: Engine,
+    pub tick: Tick,
+    pub seed: u64,
+    pub random: ApiShare<StdRng>,
 }
 
 pub struct ActorApi {
     active_users: HashSet<ActorId>,
     actor_inputs: HashMap<ActorId, GuestInput>,
+    focus_entities: HashMap<ActorId, Entity>,
+    possessed_entities: HashMap<ActorId, (Entity, Real)>,
+    processed_inputs: HashMap<ActorId, InputSequence>,
+    round_trip_times: HashMap<ActorId, u32>,
 }
 
 impl ActorApi {
@@ -45,12 +65,185 @@ impl ActorApi {
     pub fn set_actor_input(&mut self, actor_id: ActorId, guest_input: GuestInput) {
         self.actor_inputs.insert(actor_id, guest_input);
     }
+
+    /// The entity a guest's view follows, usually their avatar. Guests without one see the
+    /// whole world.
+    pub fn get_focus_entity(&self, actor_id: &ActorId) -> Option<Entity> {
+        self.focus_entities.get(actor_id).copied()
+    }
+
+    /// Sequence of the newest input that moved the actor's possessed entity, everything up to
+    /// it is part of the positions the client receives.
+    pub fn get_processed_input(&self, actor_id: &ActorId) -> Option<InputSequence> {
+        self.processed_inputs.get(actor_id).copied()
+    }
+
+    pub fn get_round_trip_time(&self, actor_id: &ActorId) -> Option<u32> {
+        self.round_trip_times.get(actor_id).copied()
+    }
+
+    pub fn set_round_trip_time(&mut self, actor_id: ActorId, round_trip_time: u32) {
+        self.round_trip_times.insert(actor_id, round_trip_time);
+    }
+
+    pub fn remove_round_trip_time(&mut self, actor_id: &ActorId) {
+        self.round_trip_times.remove(actor_id);
+    }
+}
+
+pub struct GuestDataApi {
+    module_id: ModuleId,
+    guest_data: HashMap<ActorId, HashMap<String, JsonValue>>,
+    unlocked_achievements: HashMap<ActorId, HashSet<AchievementId>>,
+    state_changes: Vec<(ActorId, GuestStateChange)>,
+    pending_achievement_unlocks: Vec<(ActorId, AchievementId)>,
+    persisted_guest_ids: HashMap<ActorId, i32>,
+    pending_score_submissions: Vec<(ActorId, LeaderboardId, f64)>,
+}
+
+impl GuestDataApi {
+    pub fn new(module_id: ModuleId) -> GuestDataApi {
+        GuestDataApi {
+            module_id,
+            guest_data: HashMap::new(),
+            unlocked_achievements: HashMap::new(),
+            state_changes: Vec::new(),
+            pending_achievement_unlocks: Vec::new(),
+            persisted_guest_ids: HashMap::new(),
+            pending_score_submissions: Vec::new(),
+        }
+    }
+
+    pub fn module_id(&self) -> &ModuleId {
+        &self.module_id
+    }
+
+    pub fn set_persisted_guest_id(&mut self, actor_id: ActorId, persisted_guest_state_id: i32) {
+        self.persisted_guest_ids
+            .insert(actor_id, persisted_guest_state_id);
+    }
+
+    pub fn get_persisted_guest_id(&self, actor_id: &ActorId) -> Option<i32> {
+        self.persisted_guest_ids.get(actor_id).cloned()
+    }
+
+    pub fn set_guest_data(&mut self, actor_id: ActorId, guest_data: HashMap<String, JsonValue>) {
+        self.guest_data.insert(actor_id, guest_data);
+    }
+
+    pub fn set_unlocked_achievements(
+        &mut self,
+        actor_id: ActorId,
+        unlocked_achievements: HashSet<AchievementId>,
+    ) {
+        self.unlocked_achievements
+            .insert(actor_id, unlocked_achievements);
+    }
+
+    pub fn remove_guest_data(&mut self, actor_id: &ActorId) {
+        self.guest_data.remove(actor_id);
+        self.unlocked_achievements.remove(actor_id);
+        self.persisted_guest_ids.remove(actor_id);
+    }
+
+    pub fn is_achievement_unlocked(&self, actor_id: &ActorId, achievement_id: &str) -> bool {
+        self.unlocked_achievements
+            .get(actor_id)
+            .map(|unlocked| unlocked.contains(achievement_id))
+            .unwrap_or(false)
+    }
+
+    pub fn unlock_achievement(
+        &mut self,
+        actor_id: ActorId,
+        achievement_id: AchievementId,
+    ) -> Result<(), String> {
+        let unlocked = self
+            .unlocked_achievements
+            .get_mut(&actor_id)
+            .ok_or(format!("Guest {} is not in this module.", actor_id))?;
+        if unlocked.insert(achievement_id.clone()) {
+            self.pending_achievement_unlocks
+                .push((actor_id, achievement_id));
+        }
+
+        Ok(())
+    }
+
+    pub fn forget_achievement_unlock(&mut self, actor_id: &ActorId, achievement_id: &str) {
+        if let Some(unlocked) = self.unlocked_achievements.get_mut(actor_id) {
+            unlocked.remove(achievement_id);
+        }
+    }
+
+    pub fn drain_pending_achievement_unlocks(&mut self) -> Vec<(ActorId, AchievementId)> {
+        self.pending_achievement_unlocks.drain(..).collect()
+    }
+
+    pub fn submit_score(
+        &mut self,
+        actor_id: ActorId,
+        leaderboard_id: LeaderboardId,
+        score: f64,
+    ) -> Result<(), String> {
+        if !self.persisted_guest_ids.contains_key(&actor_id) {
+            return Err(format!("Guest {} can not submit scores.", actor_id));
+        }
+        if !score.is_finite() {
+            return Err(format!("Score {} is not a finite number.", score));
+        }
+        self.pending_score_submissions
+            .push((actor_id, leaderboard_id, score));
+
+        Ok(())
+    }
+
+    pub fn drain_pending_score_submissions(&mut self) -> Vec<(ActorId, LeaderboardId, f64)> {
+        self.pending_score_submissions.drain(..).collect()
+    }
+
+    pub fn get(&self, actor_id: &ActorId, key: &str) -> Option<&JsonValue> {
+        self.guest_data
+            .get(actor_id)
+            .and_then(|guest_data| guest_data.get(key))
+    }
+
+    pub fn set(&mut self, actor_id: ActorId, key: String, value: JsonValue) -> Result<(), String> {
+        // The column is a varchar, its limit counts characters and not bytes.
+        if key.is_empty() || key.chars().count() > MAX_GUEST_DATA_KEY_LENGTH {
+            return Err(format!(
+                "Key has to be between 1 and {} characters long.",
+                MAX_GUEST_DATA_KEY_LENGTH
+            ));
+        }
+        let guest_data = self
+            .guest_data
+            .get_mut(&actor_id)
+            .ok_or(format!("Guest {} is not in this module.", actor_id))?;
+        if guest_data.get(&key) == Some(&value) {
+            return Ok(());
+        }
+        guest_data.insert(key.clone(), value.clone());
+        self.state_changes.push((
+            actor_id,
+            GuestStateChange::SetModuleData(self.module_id.clone(), key, value),
+        ));
+
+        Ok(())
+    }
+
+    pub fn drain_state_changes(&mut self) -> Vec<(ActorId, GuestStateChange)> {
+        self.state_changes.drain(..).collect()
+    }
 }
 
 impl World {
     pub fn new(
         game_map: &GameMap,
         collision_shape_map: &HashMap<Gid, CollisionShape>,
+        guest_data_api: &ApiShare<GuestDataApi>,
+        leaderboards: &LeaderboardMap,
+        seed: u64,
     ) -> Result<World, CreateWorldError> {
         let world_scene = Blueprint::load_scene(game_map.main_scene.clone().into())?;
         let mut physics = RapierSimulation::new();
@@ -73,10 +266,19 @@ impl World {
             actor_api: ApiShare::new(ActorApi {
                 actor_inputs: HashMap::new(),
                 active_users: HashSet::new(),
+                focus_entities: HashMap::new(),
+                possessed_entities: HashMap::new(),
+                processed_inputs: HashMap::new(),
+                round_trip_times: HashMap::new(),
             }),
+            guest_data_api: guest_data_api.clone(),
+            leaderboards: leaderboards.clone(),
             terrain_manager,
             ecs: ECS::from(&world_scene),
             script_engine: Engine::new(),
+            tick: 0,
+            seed,
+            random: ApiShare::new(StdRng::seed_from_u64(seed)),
         };
 
         world.reset()?;
@@ -93,25 +295,35 @@ impl World {
         Self::init_physics_simulation_from_ecs(&mut ecs, &mut physics);
         self.terrain_manager.re_add_polylines(&mut physics);
         let physics_share = ApiShare::new(physics);
+        let random_share = ApiShare::new(StdRng::seed_from_u64(self.seed));
         let mut script_engine = Engine::new();
         Self::register_types(&mut script_engine);
         Self::setup_nodes_api(&mut script_engine, &mut ecs, &physics_share);
         Self::setup_physics_scripting_api(&mut script_engine, &physics_share, &mut ecs);
         Self::setup_animation_api(&mut script_engine, &mut ecs);
         Self::setup_actor_api(&mut script_engine, &self.actor_api);
+        Self::setup_storage_api(&mut script_engine, &self.guest_data_api);
+        Self::setup_achievements_api(&mut script_engine, &self.guest_data_api);
+        Self::setup_leaderboards_api(&mut script_engine, &self.guest_data_api, &self.leaderboards);
+        Self::setup_random_api(&mut script_engine, &random_share);
         ecs.process_added_and_removed_entities_and_scope_sets(&script_engine);
         self.ecs = ecs;
         self.physics = physics_share;
+        self.random = random_share;
         self.script_engine = script_engine;
 
         Ok(())
     }
 
     pub fn update(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
         if let Some(mut physics) = self.physics.try_borrow_mut() {
             physics.update();
             if let Some(mut shared_ecs) = self.ecs.shared.try_borrow_mut() {
                 Self::update_entities_gid_from_animations(&mut shared_ecs);
+                if let Some(mut actor_api) = self.actor_api.try_borrow_mut() {
+                    Self::apply_possessed_inputs(&mut actor_api, &mut shared_ecs);
+                }
                 Self::update_kinematic_character_controllers(&shared_ecs, &mut physics);
                 Self::update_positions(&mut physics, &mut shared_ecs);
             }
@@ -135,6 +347,29 @@ impl World {
         }
     }
 
+    /// Possessed characters are moved by the server from the latest input with
+    /// `kinematic_input_translation`, scripts can still move every other character themselves.
+    pub fn apply_possessed_inputs(actor_api: &mut ActorApi, ecs_shared: &mut ECSShared) {
+        let ActorApi {
+            actor_inputs,
+            possessed_entities,
+            processed_inputs,
+            ..
+        } = actor_api;
+        for (actor_id, (entity, speed)) in possessed_entities.iter() {
+            let Some(input) = actor_inputs.get(actor_id) else {
+                continue;
+            };
+            if let Some(kinematic_character) =
+                ecs_shared.entities.kinematic_character.get_mut(entity)
+            {
+                let (x, y) = kinematic_input_translation(input, *speed);
+                kinematic_character.desired_translation = vector![x, y];
+                processed_inputs.insert(*actor_id, input.sequence);
+            }
+        }
+    }
+
     pub fn update_kinematic_character_controllers(
         ecs_shared: &ECSShared,
         physics: &mut RapierSimulation,
@@ -181,6 +416,9 @@ impl World {
     pub fn actor_left_world(&mut self, actor_id: ActorId) {
         if let Some(mut actor_api) = self.actor_api.try_borrow_mut() {
             actor_api.active_users.remove(&actor_id);
+            actor_api.focus_entities.remove(&actor_id);
+            actor_api.possessed_entities.remove(&actor_id);
+            actor_api.processed_inputs.remove(&actor_id);
         }
         for game_node_script in self.ecs.entity_scripts.values_mut() {
             game_node_script.call(
@@ -438,19 +676,226 @@ impl World {
                 actor_api_share_clone
                     .try_borrow_mut()
                     .map(|actor_api| {
-                        actor_api
-                            .active_users
-                            .iter()
-                            .cloned()
-                            .map(Dynamic::from)
-                            .collect::<Vec<Dynamic>>()
+                        let mut active_users: Vec<ActorId> =
+                            actor_api.active_users.iter().cloned().collect();
+                        active_users.sort();
+                        active_users.into_iter().map(Dynamic::from).collect()
                     })
                     .unwrap_or_default()
             },
         );
+        let actor_api_share_clone = actor_api_share.clone();
+        FuncRegistration::new("set_focus_entity").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, entity: Entity| {
+                if let Some(mut actor_api) = actor_api_share_clone.try_borrow_mut() {
+                    actor_api.focus_entities.insert(actor_id, entity);
+                }
+            },
+        );
+        let actor_api_share_clone = actor_api_share.clone();
+        FuncRegistration::new("get_round_trip_time").set_into_module(
+            &mut module,
+            move |actor_id: ActorId| -> Dynamic {
+                actor_api_share_clone
+                    .try_borrow()
+                    .and_then(|actor_api| actor_api.get_round_trip_time(&actor_id))
+                    .map(|round_trip_time| Dynamic::from(round_trip_time as i64))
+                    .unwrap_or(Dynamic::UNIT)
+            },
+        );
+        let actor_api_share_clone = actor_api_share.clone();
+        FuncRegistration::new("possess_entity").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, entity: Entity, speed: f64| {
+                if let Some(mut actor_api) = actor_api_share_clone.try_borrow_mut() {
+                    actor_api
+                        .possessed_entities
+                        .insert(actor_id, (entity, speed as Real));
+                }
+            },
+        );
+        let actor_api_share_clone = actor_api_share.clone();
+        FuncRegistration::new("release_entity").set_into_module(
+            &mut module,
+            move |actor_id: ActorId| {
+                if let Some(mut actor_api) = actor_api_share_clone.try_borrow_mut() {
+                    actor_api.possessed_entities.remove(&actor_id);
+                    actor_api.processed_inputs.remove(&actor_id);
+                }
+            },
+        );
         engine.register_static_module("shiku::actors", module.into());
     }
 
+    /// Scripts get their randomness from the world's seed, so replays roll the same numbers.
+    fn setup_random_api(engine: &mut Engine, random_share: &ApiShare<StdRng>) {
+        let mut module = RhaiModule::new();
+        let random_share_clone = random_share.clone();
+        FuncRegistration::new("float").set_into_module(&mut module, move || -> f64 {
+            random_share_clone
+                .try_borrow_mut()
+                .map(|mut random| random.gen::<f64>())
+                .unwrap_or(0.0)
+        });
+        let random_share_clone = random_share.clone();
+        FuncRegistration::new("int").set_into_module(
+            &mut module,
+            move |min: i64, max: i64| -> Result<i64, Box<EvalAltResult>> {
+                if min > max {
+                    return Err(format!("Min {} is bigger than max {}.", min, max).into());
+                }
+                let mut random = random_share_clone
+                    .try_borrow_mut()
+                    .ok_or("Could not borrow random number generator.")?;
+                Ok(random.gen_range(min..=max))
+            },
+        );
+        engine.register_static_module("shiku::random", module.into());
+    }
+
+    fn setup_storage_api(engine: &mut Engine, guest_data_api_share: &ApiShare<GuestDataApi>) {
+        let mut module = RhaiModule::new();
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        FuncRegistration::new("get").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, key: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                let guest_data_api = guest_data_api_share_clone
+                    .try_borrow()
+                    .ok_or("Could not borrow guest data api.")?;
+                match guest_data_api.get(&actor_id, key) {
+                    Some(value) => rhai::serde::to_dynamic(value),
+                    None => Ok(Dynamic::UNIT),
+                }
+            },
+        );
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        FuncRegistration::new("set").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, key: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let value: JsonValue = rhai::serde::from_dynamic(&value)?;
+                let mut guest_data_api = guest_data_api_share_clone
+                    .try_borrow_mut()
+                    .ok_or("Could not borrow guest data api.")?;
+                guest_data_api
+                    .set(actor_id, key.to_string(), value)
+                    .map_err(|err| err.into())
+            },
+        );
+        engine.register_static_module("shiku::storage", module.into());
+    }
+
+    fn setup_achievements_api(engine: &mut Engine, guest_data_api_share: &ApiShare<GuestDataApi>) {
+        let mut module = RhaiModule::new();
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        FuncRegistration::new("unlock").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, achievement_id: &str| -> Result<(), Box<EvalAltResult>> {
+                let mut guest_data_api = guest_data_api_share_clone
+                    .try_borrow_mut()
+                    .ok_or("Could not borrow guest data api.")?;
+                guest_data_api
+                    .unlock_achievement(actor_id, achievement_id.to_string())
+                    .map_err(|err| err.into())
+            },
+        );
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        FuncRegistration::new("is_unlocked").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, achievement_id: &str| {
+                guest_data_api_share_clone
+                    .try_borrow()
+                    .map(|guest_data_api| {
+                        guest_data_api.is_achievement_unlocked(&actor_id, achievement_id)
+                    })
+                    .unwrap_or(false)
+            },
+        );
+        engine.register_static_module("shiku::achievements", module.into());
+    }
+
+    fn setup_leaderboards_api(
+        engine: &mut Engine,
+        guest_data_api_share: &ApiShare<GuestDataApi>,
+        leaderboards: &LeaderboardMap,
+    ) {
+        let mut module = RhaiModule::new();
+        let module_id = guest_data_api_share
+            .try_borrow()
+            .map(|guest_data_api| guest_data_api.module_id().clone())
+            .unwrap_or_default();
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        FuncRegistration::new("submit").set_into_module(
+            &mut module,
+            move |actor_id: ActorId,
+                  leaderboard_id: &str,
+                  score: Dynamic|
+                  -> Result<(), Box<EvalAltResult>> {
+                let score = score
+                    .as_float()
+                    .or_else(|_| score.as_int().map(|score| score as f64))
+                    .map_err(|_| "Score has to be a number.")?;
+                let mut guest_data_api = guest_data_api_share_clone
+                    .try_borrow_mut()
+                    .ok_or("Could not borrow guest data api.")?;
+                guest_data_api
+                    .submit_score(actor_id, leaderboard_id.to_string(), score)
+                    .map_err(|err| err.into())
+            },
+        );
+        let leaderboards_clone = leaderboards.clone();
+        let module_id_clone = module_id.clone();
+        FuncRegistration::new("top").set_into_module(
+            &mut module,
+            move |leaderboard_id: &str, count: i64| -> Vec<Dynamic> {
+                let mut leaderboards = lock_leaderboards(&leaderboards_clone);
+                let Some(leaderboard) = leaderboards
+                    .get_mut(&module_id_clone)
+                    .and_then(|module_leaderboards| module_leaderboards.get_mut(leaderboard_id))
+                else {
+                    return Vec::new();
+                };
+                leaderboard.roll_over(Utc::now().naive_utc());
+                leaderboard
+                    .top((count.max(0) as usize).min(MAX_LEADERBOARD_QUERY_SIZE))
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| {
+                        let mut map = rhai::Map::new();
+                        map.insert("name".into(), entry.display_name.clone().into());
+                        map.insert("score".into(), entry.score.into());
+                        map.insert("rank".into(), ((index + 1) as i64).into());
+                        Dynamic::from_map(map)
+                    })
+                    .collect()
+            },
+        );
+        let guest_data_api_share_clone = guest_data_api_share.clone();
+        let leaderboards_clone = leaderboards.clone();
+        FuncRegistration::new("rank").set_into_module(
+            &mut module,
+            move |actor_id: ActorId, leaderboard_id: &str| -> Dynamic {
+                let Some(persisted_guest_state_id) = guest_data_api_share_clone
+                    .try_borrow()
+                    .and_then(|guest_data_api| guest_data_api.get_persisted_guest_id(&actor_id))
+                else {
+                    return Dynamic::UNIT;
+                };
+                let mut leaderboards = lock_leaderboards(&leaderboards_clone);
+                leaderboards
+                    .get_mut(&module_id)
+                    .and_then(|module_leaderboards| module_leaderboards.get_mut(leaderboard_id))
+                    .and_then(|leaderboard| {
+                        leaderboard.roll_over(Utc::now().naive_utc());
+                        leaderboard.rank(persisted_guest_state_id)
+                    })
+                    .map(|rank| Dynamic::from(rank as i64))
+                    .unwrap_or(Dynamic::UNIT)
+            },
+        );
+        engine.register_static_module("shiku::leaderboards", module.into());
+    }
+
     fn update_positions(physics: &mut RapierSimulation, shared: &mut ECSShared) {
         for (entity, rigid_body_handle) in shared.entities.rigid_body_handle.iter() {
             if let Some(transform) = shared.entities.transforms.get_mut(entity) {
@@ -557,3 +1002,32 @@ impl World {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_data_key_limit_counts_characters() {
+        let mut guest_data_api = GuestDataApi::new("module".into());
+        guest_data_api.set_guest_data(1, HashMap::new());
+
+        assert!(guest_data_api
+            .set(
+                1,
+                "ä".repeat(MAX_GUEST_DATA_KEY_LENGTH),
+                JsonValue::Bool(true)
+            )
+            .is_ok());
+        assert!(guest_data_api
+            .set(
+                1,
+                "a".repeat(MAX_GUEST_DATA_KEY_LENGTH + 1),
+                JsonValue::Bool(true)
+            )
+            .is_err());
+        assert!(guest_data_api
+            .set(1, String::new(), JsonValue::Bool(true))
+            .is_err());
+    }
+}
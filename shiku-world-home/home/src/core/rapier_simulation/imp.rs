@@ -9,6 +9,7 @@ use rapier2d::prelude::*;
 use crate::core::blueprint::scene::def::KinematicCharacterControllerProps;
 use crate::core::rapier_simulation::def::RapierSimulation;
 use crate::core::terrain_gen::TerrainGenTerrainChunk;
+use crate::core::TARGET_FRAME_DURATION;
 
 pub const COL_GROUP_A: InteractionGroups = InteractionGroups::new(Group::GROUP_1, Group::GROUP_1);
 pub const COL_GROUP_B: InteractionGroups = InteractionGroups::new(Group::GROUP_2, Group::GROUP_2);
@@ -503,7 +504,11 @@ impl RapierSimulation {
             bodies: RigidBodySet::new(),
             colliders: ColliderSet::new(),
             gravity: Vector::new(0.0, 1.0),
-            integration_parameters: IntegrationParameters::default(),
+            // One step per tick no matter how late the tick runs, replays depend on it.
+            integration_parameters: IntegrationParameters {
+                dt: TARGET_FRAME_DURATION / 1000.0,
+                ..IntegrationParameters::default()
+            },
             physics_pipeline: PhysicsPipeline::new(),
             islands: IslandManager::new(),
             broad_phase_multi_sap: BroadPhaseMultiSap::new(),
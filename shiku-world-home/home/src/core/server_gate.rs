@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::NaiveTime;
+use log::error;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::guest::ProviderUserId;
+
+pub type ServerGates = Arc<Mutex<Vec<ServerGate>>>;
+
+pub fn lock_gates(gates: &ServerGates) -> MutexGuard<'_, Vec<ServerGate>> {
+    gates
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Times are `HH:MM` in UTC, a window that closes before it opens spans midnight.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export, export_to = "blueprints/")]
+pub struct GateWindow {
+    pub opens_at: String,
+    pub closes_at: String,
+}
+
+impl GateWindow {
+    fn contains(&self, now: NaiveTime) -> bool {
+        let (Some(opens_at), Some(closes_at)) =
+            (parse_time(&self.opens_at), parse_time(&self.closes_at))
+        else {
+            error!("Ignoring gate window with invalid times {:?}", self);
+            return false;
+        };
+        if opens_at <= closes_at {
+            opens_at <= now && now < closes_at
+        } else {
+            now >= opens_at || now < closes_at
+        }
+    }
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+/// A guest only gets in when every gate is open or lets them through its allowlist.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export, export_to = "blueprints/")]
+pub struct ServerGate {
+    pub name: String,
+    pub open: bool,
+    #[serde(default)]
+    pub schedule: Vec<GateWindow>,
+    #[serde(default)]
+    pub allowlist: Vec<ProviderUserId>,
+    #[serde(default)]
+    pub refused_message: Option<String>,
+}
+
+/// What the public gate endpoints show, the allowlist stays private.
+#[derive(Debug, Serialize)]
+pub struct GateStatus {
+    pub name: String,
+    pub open: bool,
+    pub schedule: Vec<GateWindow>,
+    pub message: Option<String>,
+}
+
+impl ServerGate {
+    pub fn is_open_at(&self, now: NaiveTime) -> bool {
+        self.open
+            && (self.schedule.is_empty() || self.schedule.iter().any(|window| window.contains(now)))
+    }
+
+    /// Without a provider user id the guest has not logged in yet, they are let through as
+    /// long as the allowlist could still admit them at login.
+    pub fn lets_in(&self, provider_user_id: Option<&ProviderUserId>, now: NaiveTime) -> bool {
+        self.is_open_at(now)
+            || match provider_user_id {
+                Some(provider_user_id) => self.allowlist.contains(provider_user_id),
+                None => !self.allowlist.is_empty(),
+            }
+    }
+
+    pub fn refusal(&self) -> String {
+        self.refused_message
+            .clone()
+            .unwrap_or_else(|| format!("The {} gate is closed right now.", self.name))
+    }
+
+    pub fn status(&self, now: NaiveTime) -> GateStatus {
+        GateStatus {
+            name: self.name.clone(),
+            open: self.is_open_at(now),
+            schedule: self.schedule.clone(),
+            message: if self.is_open_at(now) {
+                None
+            } else {
+                Some(self.refusal())
+            },
+        }
+    }
+}
+
+pub fn gate_refusal(
+    gates: &[ServerGate],
+    provider_user_id: Option<&ProviderUserId>,
+    now: NaiveTime,
+) -> Option<String> {
+    gates
+        .iter()
+        .find(|gate| !gate.lets_in(provider_user_id, now))
+        .map(ServerGate::refusal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveTime {
+        parse_time(time).unwrap()
+    }
+
+    #[test]
+    fn test_gates_follow_schedule_and_allowlist() {
+        let maintenance = ServerGate {
+            name: "main".into(),
+            open: true,
+            schedule: vec![GateWindow {
+                opens_at: "06:00".into(),
+                closes_at: "02:00".into(),
+            }],
+            allowlist: Vec::new(),
+            refused_message: Some("Maintenance until 6 UTC.".into()),
+        };
+        let testers = ServerGate {
+            name: "testers".into(),
+            open: false,
+            schedule: Vec::new(),
+            allowlist: vec!["twitch:tester".into()],
+            refused_message: None,
+        };
+
+        assert!(maintenance.is_open_at(at("23:30")));
+        assert!(maintenance.is_open_at(at("01:59")));
+        assert!(!maintenance.is_open_at(at("02:00")));
+        assert_eq!(
+            gate_refusal(std::slice::from_ref(&maintenance), None, at("03:00")),
+            Some("Maintenance until 6 UTC.".into())
+        );
+        assert_eq!(
+            gate_refusal(std::slice::from_ref(&maintenance), None, at("12:00")),
+            None
+        );
+
+        let gates = [maintenance, testers];
+        let tester = "twitch:tester".to_string();
+        let guest = "twitch:guest".to_string();
+        assert_eq!(gate_refusal(&gates, None, at("12:00")), None);
+        assert_eq!(gate_refusal(&gates, Some(&tester), at("12:00")), None);
+        assert_eq!(
+            gate_refusal(&gates, Some(&guest), at("12:00")),
+            Some("The testers gate is closed right now.".into())
+        );
+        assert!(gate_refusal(&gates, Some(&tester), at("03:00")).is_some());
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::module_system::prediction::Tick;
+
+/// Echoes arriving later than this are dropped, such a round trip says more about a stalled
+/// client than about the connection.
+pub const MAX_ROUND_TRIP_TIME: u32 = 10_000;
+
+/// Milliseconds since the unix epoch, as float so it arrives as a plain number on the client.
+pub fn server_time() -> f64 {
+    Utc::now().timestamp_millis() as f64
+}
+
+/// Sent before everything a world sends in one update, every event after it belongs to that
+/// tick until the next stamp arrives.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[ts(export)]
+pub struct FrameStamp {
+    pub tick: Tick,
+    pub server_time: f64,
+}
+
+impl FrameStamp {
+    pub fn now(tick: Tick) -> FrameStamp {
+        FrameStamp {
+            tick,
+            server_time: server_time(),
+        }
+    }
+}
+
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[ts(export)]
+pub struct TimeSyncPing {
+    pub client_time: f64,
+}
+
+/// Answer to a [`TimeSyncPing`], the client takes `now - client_time` as round trip and
+/// `server_time + round_trip / 2 - now` as clock offset.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[ts(export)]
+pub struct TimeSyncPong {
+    pub client_time: f64,
+    pub server_time: f64,
+}
+
+impl TimeSyncPong {
+    pub fn answer(ping: &TimeSyncPing) -> TimeSyncPong {
+        TimeSyncPong {
+            client_time: ping.client_time,
+            server_time: server_time(),
+        }
+    }
+}
+
+/// Measures the round trip on the server, guests echo the `server_time` of a pong as soon as it
+/// arrives. Only the last pong sent is timed, an echo of an older one is ignored.
+#[derive(Debug, Default)]
+pub struct RoundTripTimer {
+    pending_pong: Option<(f64, Instant)>,
+}
+
+impl RoundTripTimer {
+    pub fn pong_sent(&mut self, pong: &TimeSyncPong, now: Instant) {
+        self.pending_pong = Some((pong.server_time, now));
+    }
+
+    pub fn echo_received(&mut self, server_time: f64, now: Instant) -> Option<u32> {
+        let (pong_server_time, sent_at) = self.pending_pong?;
+        if pong_server_time != server_time {
+            return None;
+        }
+        self.pending_pong = None;
+        let round_trip_time = now.saturating_duration_since(sent_at).as_millis();
+        (round_trip_time <= MAX_ROUND_TRIP_TIME as u128).then_some(round_trip_time as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip_time_is_measured_from_the_echoed_pong() {
+        let mut timer = RoundTripTimer::default();
+        let sent_at = Instant::now();
+        let pong = |server_time| TimeSyncPong {
+            client_time: 0.0,
+            server_time,
+        };
+
+        assert_eq!(timer.echo_received(1.0, sent_at), None);
+
+        timer.pong_sent(&pong(1.0), sent_at);
+        timer.pong_sent(&pong(2.0), sent_at);
+        assert_eq!(
+            timer.echo_received(1.0, sent_at + Duration::from_millis(40)),
+            None
+        );
+        assert_eq!(
+            timer.echo_received(2.0, sent_at + Duration::from_millis(42)),
+            Some(42)
+        );
+        assert_eq!(
+            timer.echo_received(2.0, sent_at + Duration::from_millis(50)),
+            None
+        );
+
+        timer.pong_sent(&pong(3.0), sent_at);
+        let too_late = sent_at + Duration::from_millis(MAX_ROUND_TRIP_TIME as u64 + 1);
+        assert_eq!(timer.echo_received(3.0, too_late), None);
+    }
+}
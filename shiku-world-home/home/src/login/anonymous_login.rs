@@ -0,0 +1,119 @@
+use std::env;
+
+use log::debug;
+use uuid::Uuid;
+
+use crate::core::guest::{LoginData, LoginProvider};
+use crate::core::module::ProviderLoggedIn;
+use crate::login::guest_token::GuestTokenSigner;
+use crate::login::provider::{
+    LoginProviderBackend, LoginProviderError, PendingLogin, ProviderUser,
+};
+
+pub const ANONYMOUS_PROVIDER_ID_PREFIX: &str = "anonymous:";
+
+/// Lets guests play without an account. The guest token is sent as the auth code, without
+/// one a new anonymous guest is created. Either way a fresh token is sent back to be stored.
+pub struct AnonymousLogin {
+    signer: GuestTokenSigner,
+}
+
+impl AnonymousLogin {
+    pub fn new(signer: GuestTokenSigner) -> AnonymousLogin {
+        AnonymousLogin { signer }
+    }
+
+    pub fn from_env() -> Option<AnonymousLogin> {
+        let secret = env::var("GUEST_TOKEN_SECRET").ok()?;
+        if secret.is_empty() {
+            return None;
+        }
+
+        Some(AnonymousLogin::new(GuestTokenSigner::new(&secret)))
+    }
+
+    fn provider_user(
+        &self,
+        guest_token: Option<String>,
+    ) -> Result<ProviderUser, LoginProviderError> {
+        let guest_uuid = match &guest_token {
+            Some(guest_token) => self
+                .signer
+                .verify(guest_token)
+                .ok_or(LoginProviderError::InvalidGuestToken)?,
+            None => Uuid::new_v4(),
+        };
+        let guest_token = self.signer.issue(&guest_uuid).map_err(|err| {
+            debug!("Could not issue guest token {:?}", err);
+            LoginProviderError::InvalidGuestToken
+        })?;
+        let simple_uuid = guest_uuid.simple().to_string();
+
+        Ok(ProviderUser {
+            login_data: LoginData {
+                provider_user_id: format!("{}{}", ANONYMOUS_PROVIDER_ID_PREFIX, simple_uuid),
+                display_name: format!("Guest-{}", &simple_uuid[..6]),
+                views: None,
+                provider: LoginProvider::Anonymous,
+            },
+            created_at: None,
+            guest_token: Some(guest_token),
+        })
+    }
+}
+
+impl LoginProviderBackend for AnonymousLogin {
+    fn provider(&self) -> LoginProvider {
+        LoginProvider::Anonymous
+    }
+
+    fn login(&self, provider_logged_in: ProviderLoggedIn) -> PendingLogin {
+        PendingLogin::ready(
+            self.provider_user(
+                provider_logged_in
+                    .auth_code
+                    .filter(|guest_token| !guest_token.is_empty()),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login(anonymous_login: &AnonymousLogin, guest_token: Option<String>) -> PendingLogin {
+        anonymous_login.login(ProviderLoggedIn {
+            auth_code: guest_token,
+            access_token: None,
+            login_provider: LoginProvider::Anonymous,
+        })
+    }
+
+    #[test]
+    fn test_guest_token_keeps_the_identity() {
+        let anonymous_login = AnonymousLogin::new(GuestTokenSigner::new("secret"));
+
+        let first = login(&anonymous_login, None).result.unwrap().unwrap();
+        let guest_token = first.guest_token.clone().unwrap();
+        let again = login(&anonymous_login, Some(guest_token))
+            .result
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            again.login_data.provider_user_id,
+            first.login_data.provider_user_id
+        );
+
+        let other = login(&anonymous_login, None).result.unwrap().unwrap();
+        assert_ne!(
+            other.login_data.provider_user_id,
+            first.login_data.provider_user_id
+        );
+
+        assert!(matches!(
+            login(&anonymous_login, Some("forged".into())).result,
+            Some(Err(LoginProviderError::InvalidGuestToken))
+        ));
+    }
+}
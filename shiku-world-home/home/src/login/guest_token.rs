@@ -0,0 +1,84 @@
+use chrono::{Duration, Utc};
+use jsonwebtoken::{
+    decode, encode, errors::Error as JWTError, Algorithm, DecodingKey, EncodingKey, Header,
+    Validation,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::core::guest::GuestToken;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct GuestTokenClaims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Guests get a fresh token on every login, only guests gone this long lose their identity.
+pub const GUEST_TOKEN_LIFETIME_DAYS: i64 = 30;
+
+/// Signs the token an anonymous guest keeps client side, whoever holds it is that guest
+/// until it expires.
+pub struct GuestTokenSigner {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+impl GuestTokenSigner {
+    pub fn new(secret: &str) -> GuestTokenSigner {
+        GuestTokenSigner {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    pub fn issue(&self, guest_uuid: &Uuid) -> Result<GuestToken, JWTError> {
+        self.issue_at(guest_uuid, Utc::now().timestamp())
+    }
+
+    fn issue_at(&self, guest_uuid: &Uuid, issued_at: i64) -> Result<GuestToken, JWTError> {
+        encode(
+            &Header::new(Algorithm::HS256),
+            &GuestTokenClaims {
+                sub: guest_uuid.to_string(),
+                iat: issued_at,
+                exp: issued_at + Duration::days(GUEST_TOKEN_LIFETIME_DAYS).num_seconds(),
+            },
+            &self.encoding_key,
+        )
+    }
+
+    pub fn verify(&self, guest_token: &str) -> Option<Uuid> {
+        let token_data =
+            decode::<GuestTokenClaims>(guest_token, &self.decoding_key, &self.validation).ok()?;
+        Uuid::parse_str(&token_data.claims.sub).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_tokens_signed_with_the_same_secret_verify() {
+        let signer = GuestTokenSigner::new("secret");
+        let guest_uuid = Uuid::new_v4();
+        let guest_token = signer.issue(&guest_uuid).unwrap();
+
+        assert_eq!(signer.verify(&guest_token), Some(guest_uuid));
+        assert_eq!(GuestTokenSigner::new("other").verify(&guest_token), None);
+        assert_eq!(signer.verify("garbage"), None);
+
+        let expired_token = signer
+            .issue_at(
+                &guest_uuid,
+                Utc::now().timestamp()
+                    - Duration::days(GUEST_TOKEN_LIFETIME_DAYS + 1).num_seconds(),
+            )
+            .unwrap();
+        assert_eq!(signer.verify(&expired_token), None);
+    }
+}
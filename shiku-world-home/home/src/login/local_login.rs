@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use std::env;
+
+use log::warn;
+
+use crate::core::guest::{LoginData, LoginProvider};
+use crate::core::module::ProviderLoggedIn;
+use crate::login::provider::{
+    LoginProviderBackend, LoginProviderError, PendingLogin, ProviderUser,
+};
+
+/// Lets configured usernames in without any network access, meant for local development.
+/// The username is sent as the auth code. Only compiled into debug builds or with the `local`
+/// feature.
+pub struct LocalLogin {
+    usernames: HashSet<String>,
+}
+
+impl LocalLogin {
+    pub fn new(usernames: HashSet<String>) -> LocalLogin {
+        LocalLogin { usernames }
+    }
+
+    pub fn from_env() -> Option<LocalLogin> {
+        let usernames: HashSet<String> = env::var("LOCAL_LOGIN_USERS")
+            .ok()?
+            .split(',')
+            .map(|username| username.trim().to_string())
+            .filter(|username| !username.is_empty())
+            .collect();
+
+        if usernames.is_empty() {
+            return None;
+        }
+
+        warn!(
+            "Local login is enabled, anyone can log in as {:?} without a password!",
+            usernames
+        );
+        Some(LocalLogin::new(usernames))
+    }
+}
+
+impl LoginProviderBackend for LocalLogin {
+    fn provider(&self) -> LoginProvider {
+        LoginProvider::Local
+    }
+
+    fn login(&self, provider_logged_in: ProviderLoggedIn) -> PendingLogin {
+        let username = provider_logged_in.auth_code.unwrap_or_default();
+        if !self.usernames.contains(&username) {
+            return PendingLogin::ready(Err(LoginProviderError::UnknownLocalUser(username)));
+        }
+
+        PendingLogin::ready(Ok(ProviderUser {
+            login_data: LoginData {
+                provider_user_id: format!("local:{}", username),
+                display_name: username,
+                views: None,
+                provider: LoginProvider::Local,
+            },
+            created_at: None,
+            guest_token: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login(local_login: &LocalLogin, username: &str) -> PendingLogin {
+        local_login.login(ProviderLoggedIn {
+            auth_code: Some(username.to_string()),
+            access_token: None,
+            login_provider: LoginProvider::Local,
+        })
+    }
+
+    #[test]
+    fn test_only_configured_usernames_can_login() {
+        let local_login = LocalLogin::new(HashSet::from(["shiku".to_string()]));
+
+        let mut pending_login = login(&local_login, "shiku");
+        assert!(pending_login.poll_result());
+        let provider_user = pending_login.result.unwrap().unwrap();
+        assert_eq!(provider_user.login_data.provider_user_id, "local:shiku");
+        assert_eq!(provider_user.login_data.display_name, "shiku");
+
+        assert!(matches!(
+            login(&local_login, "someone").result,
+            Some(Err(LoginProviderError::UnknownLocalUser(_)))
+        ));
+    }
+}
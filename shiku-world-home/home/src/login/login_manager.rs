@@ -1,131 +1,147 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
-use log::debug;
-use reqwest::blocking::Client as BlockingClient;
-use reqwest::Url;
+use chrono::Utc;
+use log::{debug, warn};
 
 use crate::core::guest::ActorId;
-use crate::core::guest::{LoginData, LoginProvider};
+use crate::core::guest::LoginProvider;
 use crate::core::module::ProviderLoggedIn;
-use crate::login::twitch_login::{
-    TwitchApiError, TwitchApiLogin, TwitchExtensionOauthTokenResponse,
+use crate::login::anonymous_login::AnonymousLogin;
+#[cfg(any(feature = "local", debug_assertions))]
+use crate::login::local_login::LocalLogin;
+use crate::login::oidc_login::OidcLogin;
+use crate::login::provider::{
+    LoginProviderBackend, LoginProviderError, PendingLogin, ProviderTokenStatus, ProviderUser,
 };
+use crate::login::twitch_login::TwitchApiLogin;
 
 pub struct LoginManager {
-    twitch_ext_access_token: String,
-    twitch_login_process_running: HashMap<ActorId, TwitchApiLogin>,
+    providers: HashMap<LoginProvider, Box<dyn LoginProviderBackend>>,
+    login_process_running: HashMap<ActorId, PendingLogin>,
     finished_logins: Vec<ActorId>,
 }
 
 pub enum LoginError {
     UserDidNotExistLongEnough(ActorId, i64),
-    TwitchApiError(ActorId, TwitchApiError),
+    ProviderError(ActorId, LoginProviderError),
 }
 
 const MIN_DAYS_SINCE_ACCOUNT_CREATION: i64 = 0;
 
 impl LoginManager {
     pub fn new() -> LoginManager {
-        let twitch_ext_access_token = tokio::task::block_in_place(|| {
-            let client_id = "uchpfk924k24ozzra8f6i7bpthn33r";
-            let client_secret = "0t6mrb7w4j6o8l5dq51rncmt8u91uu";
-
-            let client = BlockingClient::new();
-            let url = Url::parse_with_params(
-                "https://id.twitch.tv/oauth2/token",
-                &[
-                    ("client_id", client_id),
-                    ("client_secret", client_secret),
-                    ("grant_type", "client_credentials"),
-                ],
-            )
-            .unwrap();
-
-            let oauth_response_as_json = client
-                .post(url)
-                .send()
-                .unwrap()
-                .json::<TwitchExtensionOauthTokenResponse>()
-                .unwrap();
-
-            oauth_response_as_json.access_token
-        });
+        let mut providers: Vec<Box<dyn LoginProviderBackend>> = Vec::new();
+        if let Some(twitch_login) = TwitchApiLogin::from_env() {
+            providers.push(Box::new(twitch_login));
+        }
+        if let Some(oidc_login) = OidcLogin::from_env() {
+            providers.push(Box::new(oidc_login));
+        }
+        #[cfg(any(feature = "local", debug_assertions))]
+        if let Some(local_login) = LocalLogin::from_env() {
+            providers.push(Box::new(local_login));
+        }
+        if let Some(anonymous_login) = AnonymousLogin::from_env() {
+            providers.push(Box::new(anonymous_login));
+        }
+
+        Self::with_providers(providers)
+    }
+
+    pub fn with_providers(providers: Vec<Box<dyn LoginProviderBackend>>) -> LoginManager {
+        if providers.is_empty() {
+            warn!("No login provider is configured, nobody will be able to login!");
+        }
 
         LoginManager {
+            providers: providers
+                .into_iter()
+                .map(|provider| {
+                    debug!("Login provider {:?} is enabled.", provider.provider());
+                    (provider.provider(), provider)
+                })
+                .collect(),
+            login_process_running: HashMap::new(),
             finished_logins: Vec::new(),
-            twitch_ext_access_token,
-            twitch_login_process_running: HashMap::new(),
         }
     }
 
+    pub fn token_statuses(&self) -> Vec<(LoginProvider, ProviderTokenStatus)> {
+        self.providers
+            .iter()
+            .filter_map(|(login_provider, provider)| {
+                provider
+                    .token_status()
+                    .map(|token_status| (login_provider.clone(), token_status))
+            })
+            .collect()
+    }
+
     pub fn process_running_logins<F>(&mut self, mut callback: F)
     where
-        F: FnMut(Result<(ActorId, LoginData), LoginError>),
+        F: FnMut(Result<(ActorId, ProviderUser), LoginError>),
     {
         self.finished_logins
-            .extend(self.twitch_login_process_running.iter_mut().filter_map(
-                |(guest_id, process)| {
-                    if process.poll_result() {
-                        Some(*guest_id)
-                    } else {
-                        None
-                    }
-                },
-            ));
+            .extend(
+                self.login_process_running
+                    .iter_mut()
+                    .filter_map(|(guest_id, process)| {
+                        if process.poll_result() {
+                            Some(*guest_id)
+                        } else {
+                            None
+                        }
+                    }),
+            );
 
         for guest_id in self.finished_logins.drain(..) {
-            if let Some(login) = self.twitch_login_process_running.remove(&guest_id) {
+            if let Some(login) = self.login_process_running.remove(&guest_id) {
                 if let Some(result) = login.result {
                     match result {
-                        Ok(user_response_data) => {
-                            if let Ok(parsed_date) =
-                                DateTime::parse_from_rfc3339(user_response_data.created_at.as_str())
-                            {
-                                let days_since_account_creation =
-                                    Utc::now().signed_duration_since(parsed_date).num_days();
-
-                                if days_since_account_creation < MIN_DAYS_SINCE_ACCOUNT_CREATION {
-                                    callback(Err(LoginError::UserDidNotExistLongEnough(
-                                        guest_id,
-                                        MIN_DAYS_SINCE_ACCOUNT_CREATION,
-                                    )));
-                                } else {
-                                    callback(Ok((
-                                        guest_id,
-                                        LoginData {
-                                            provider_user_id: user_response_data.id,
-                                            display_name: user_response_data.display_name,
-                                            views: Some(user_response_data.view_count),
-                                            provider: LoginProvider::Twitch,
-                                        },
-                                    )));
-                                }
+                        Ok(provider_user) => {
+                            let days_since_account_creation = provider_user
+                                .created_at
+                                .map(|created_at| {
+                                    Utc::now().signed_duration_since(created_at).num_days()
+                                })
+                                .unwrap_or(MIN_DAYS_SINCE_ACCOUNT_CREATION);
+
+                            if days_since_account_creation < MIN_DAYS_SINCE_ACCOUNT_CREATION {
+                                callback(Err(LoginError::UserDidNotExistLongEnough(
+                                    guest_id,
+                                    MIN_DAYS_SINCE_ACCOUNT_CREATION,
+                                )));
+                            } else {
+                                callback(Ok((guest_id, provider_user)));
                             }
                         }
                         Err(err) => {
-                            callback(Err(LoginError::TwitchApiError(guest_id, err)));
+                            callback(Err(LoginError::ProviderError(guest_id, err)));
                         }
                     }
                 }
             }
         }
     }
+
     pub fn add_provider_login(&mut self, guest_id: ActorId, provider_logged_in: ProviderLoggedIn) {
-        match provider_logged_in.login_provider {
-            LoginProvider::Twitch => {
-                self.twitch_login_process_running.insert(
+        match self.providers.get(&provider_logged_in.login_provider) {
+            Some(provider) => {
+                self.login_process_running
+                    .insert(guest_id, provider.login(provider_logged_in));
+            }
+            None => {
+                debug!(
+                    "Login provider {:?} is not configured!",
+                    provider_logged_in.login_provider
+                );
+                self.login_process_running.insert(
                     guest_id,
-                    TwitchApiLogin::login(
-                        provider_logged_in.auth_code,
-                        provider_logged_in.access_token,
-                        self.twitch_ext_access_token.clone(),
-                    ),
+                    PendingLogin::ready(Err(LoginProviderError::NotConfigured(
+                        provider_logged_in.login_provider,
+                    ))),
                 );
             }
-            LoginProvider::Google => {
-                debug!("Implement google provider!");
-            }
         }
     }
 }
@@ -1,3 +1,10 @@
 pub mod twitch_login;
 
+pub mod anonymous_login;
+pub mod guest_token;
+#[cfg(any(feature = "local", debug_assertions))]
+pub mod local_login;
 pub mod login_manager;
+pub mod oidc_login;
+pub mod provider;
+pub mod session_token;
@@ -0,0 +1,170 @@
+use std::env;
+use std::sync::Arc;
+
+use log::debug;
+use reqwest::{Client, Error as ReqwestError};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use crate::core::guest::{LoginData, LoginProvider};
+use crate::core::module::ProviderLoggedIn;
+use crate::login::provider::{
+    LoginProviderBackend, LoginProviderError, PendingLogin, ProviderUser,
+};
+
+pub struct OidcLoginConfig {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+/// Generic OpenID Connect login. Only authorization codes are taken, exchanging them needs our
+/// client secret. An access token could have been issued to any other client of the issuer.
+pub struct OidcLogin {
+    config: Arc<OidcLoginConfig>,
+    discovery: Arc<OnceCell<OidcDiscovery>>,
+}
+
+#[derive(Debug)]
+pub enum OidcError {
+    ReqwestError(ReqwestError),
+    NoAuthCodeProvided,
+}
+
+impl From<ReqwestError> for OidcError {
+    fn from(err: ReqwestError) -> Self {
+        OidcError::ReqwestError(err)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct OidcDiscovery {
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OidcTokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OidcUserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    name: Option<String>,
+}
+
+impl OidcLogin {
+    pub fn from_env() -> Option<OidcLogin> {
+        let (Ok(issuer), Ok(client_id), Ok(client_secret), Ok(redirect_uri)) = (
+            env::var("OIDC_ISSUER"),
+            env::var("OIDC_CLIENT_ID"),
+            env::var("OIDC_CLIENT_SECRET"),
+            env::var("OIDC_REDIRECT_URI"),
+        ) else {
+            return None;
+        };
+
+        Some(OidcLogin {
+            config: Arc::new(OidcLoginConfig {
+                issuer: issuer.trim_end_matches('/').to_string(),
+                client_id,
+                client_secret,
+                redirect_uri,
+            }),
+            discovery: Arc::new(OnceCell::new()),
+        })
+    }
+
+    /// Fetched on the first login, a failed fetch is retried by the next one.
+    async fn discover<'a>(
+        client: &Client,
+        config: &OidcLoginConfig,
+        discovery: &'a OnceCell<OidcDiscovery>,
+    ) -> Result<&'a OidcDiscovery, OidcError> {
+        discovery
+            .get_or_try_init(|| async {
+                Ok(client
+                    .get(format!(
+                        "{}/.well-known/openid-configuration",
+                        config.issuer
+                    ))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<OidcDiscovery>()
+                    .await?)
+            })
+            .await
+    }
+
+    async fn _login(
+        config: &OidcLoginConfig,
+        discovery: &OnceCell<OidcDiscovery>,
+        auth_code_option: Option<String>,
+    ) -> Result<OidcUserInfo, OidcError> {
+        let auth_code = auth_code_option.ok_or(OidcError::NoAuthCodeProvided)?;
+        let client = Client::new();
+        let discovery = Self::discover(&client, config, discovery).await?;
+
+        let access_token = client
+            .post(&discovery.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", auth_code.as_str()),
+                ("redirect_uri", config.redirect_uri.as_str()),
+                ("client_id", config.client_id.as_str()),
+                ("client_secret", config.client_secret.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OidcTokenResponse>()
+            .await?
+            .access_token;
+
+        Ok(client
+            .get(&discovery.userinfo_endpoint)
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OidcUserInfo>()
+            .await?)
+    }
+}
+
+impl LoginProviderBackend for OidcLogin {
+    fn provider(&self) -> LoginProvider {
+        LoginProvider::Oidc
+    }
+
+    fn login(&self, provider_logged_in: ProviderLoggedIn) -> PendingLogin {
+        let config = self.config.clone();
+        let discovery = self.discovery.clone();
+        PendingLogin::spawn(async move {
+            let user_info = Self::_login(&config, &discovery, provider_logged_in.auth_code)
+                .await
+                .map_err(|err| {
+                    debug!("Oidc error {:?}", err);
+                    LoginProviderError::OidcError(err)
+                })?;
+
+            Ok(ProviderUser {
+                login_data: LoginData {
+                    display_name: user_info
+                        .preferred_username
+                        .or(user_info.name)
+                        .unwrap_or_else(|| user_info.sub.clone()),
+                    provider_user_id: format!("oidc:{}", user_info.sub),
+                    views: None,
+                    provider: LoginProvider::Oidc,
+                },
+                created_at: None,
+                guest_token: None,
+            })
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use flume::{unbounded, Receiver};
+use log::error;
+use serde::Serialize;
+
+use crate::core::guest::{GuestToken, LoginData, LoginProvider};
+use crate::core::module::ProviderLoggedIn;
+use crate::login::oidc_login::OidcError;
+use crate::login::twitch_login::TwitchApiError;
+
+pub trait LoginProviderBackend {
+    fn provider(&self) -> LoginProvider;
+
+    fn login(&self, provider_logged_in: ProviderLoggedIn) -> PendingLogin;
+
+    /// Only providers that hold a server side token report its status.
+    fn token_status(&self) -> Option<ProviderTokenStatus> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ProviderTokenStatus {
+    NotFetched,
+    Valid,
+    Expired,
+    RefreshFailed,
+}
+
+impl ProviderTokenStatus {
+    /// Missing and expired tokens are fetched on the next login, only a failed refresh
+    /// means logins are broken.
+    pub fn is_healthy(&self) -> bool {
+        *self != ProviderTokenStatus::RefreshFailed
+    }
+}
+
+#[derive(Debug)]
+pub struct ProviderUser {
+    pub login_data: LoginData,
+    pub created_at: Option<DateTime<Utc>>,
+    pub guest_token: Option<GuestToken>,
+}
+
+#[derive(Debug)]
+pub enum LoginProviderError {
+    TwitchApiError(TwitchApiError),
+    OidcError(OidcError),
+    UnknownLocalUser(String),
+    InvalidGuestToken,
+    NotConfigured(LoginProvider),
+}
+
+pub struct PendingLogin {
+    receiver: Receiver<Result<ProviderUser, LoginProviderError>>,
+    pub result: Option<Result<ProviderUser, LoginProviderError>>,
+}
+
+impl PendingLogin {
+    pub fn ready(result: Result<ProviderUser, LoginProviderError>) -> PendingLogin {
+        let (_, receiver) = unbounded();
+        PendingLogin {
+            receiver,
+            result: Some(result),
+        }
+    }
+
+    pub fn spawn<F>(login: F) -> PendingLogin
+    where
+        F: Future<Output = Result<ProviderUser, LoginProviderError>> + Send + 'static,
+    {
+        let (sender, receiver) = unbounded();
+        tokio::spawn(async move {
+            if let Err(err) = sender.send(login.await) {
+                error!(
+                    "Could not send provider login result to be polled?! {:?}",
+                    err
+                );
+            }
+        });
+
+        PendingLogin {
+            receiver,
+            result: None,
+        }
+    }
+
+    pub fn poll_result(&mut self) -> bool {
+        for received_result in self.receiver.try_iter() {
+            self.result = Some(received_result);
+        }
+
+        self.result.is_some()
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::env;
+
+use chrono::Utc;
+use jsonwebtoken::{
+    decode, encode, errors::Error as JWTError, Algorithm, DecodingKey, EncodingKey, Header,
+    Validation,
+};
+use log::{debug, warn};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::guest::{SessionId, SessionToken};
+
+const DEFAULT_SESSION_TOKEN_TTL_SECONDS: i64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionClaims {
+    pub sub: SessionId,
+    pub admin: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Issues the tokens a connection presents in its `Ticket` to pick up its session again.
+/// Revoked sessions are remembered until every token issued for them has expired.
+pub struct SessionTokenManager {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+    ttl_seconds: i64,
+    revoked_sessions: HashMap<SessionId, i64>,
+}
+
+impl SessionTokenManager {
+    pub fn new(secret: &str, ttl_seconds: i64) -> SessionTokenManager {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+
+        SessionTokenManager {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            validation,
+            ttl_seconds,
+            revoked_sessions: HashMap::new(),
+        }
+    }
+
+    pub fn from_env() -> SessionTokenManager {
+        let secret = env::var("SESSION_TOKEN_SECRET").unwrap_or_else(|_| {
+            warn!("SESSION_TOKEN_SECRET is not set, sessions will not survive a restart.");
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(64)
+                .map(char::from)
+                .collect()
+        });
+        let ttl_seconds = env::var("SESSION_TOKEN_TTL_SECONDS")
+            .ok()
+            .and_then(|ttl_seconds| ttl_seconds.parse().ok())
+            .unwrap_or(DEFAULT_SESSION_TOKEN_TTL_SECONDS);
+
+        SessionTokenManager::new(&secret, ttl_seconds)
+    }
+
+    pub fn issue(&self, session_id: &SessionId, admin: bool) -> Result<SessionToken, JWTError> {
+        let now = Utc::now().timestamp();
+        encode(
+            &Header::new(Algorithm::HS256),
+            &SessionClaims {
+                sub: session_id.clone(),
+                admin,
+                iat: now,
+                exp: now + self.ttl_seconds,
+            },
+            &self.encoding_key,
+        )
+    }
+
+    pub fn verify(&self, session_token: &str) -> Option<SessionClaims> {
+        let claims =
+            match decode::<SessionClaims>(session_token, &self.decoding_key, &self.validation) {
+                Ok(token_data) => token_data.claims,
+                Err(err) => {
+                    debug!("Session token was rejected {:?}", err);
+                    return None;
+                }
+            };
+
+        if self.revoked_sessions.contains_key(&claims.sub) {
+            debug!("Session {} was revoked.", claims.sub);
+            return None;
+        }
+
+        Some(claims)
+    }
+
+    pub fn revoke(&mut self, session_id: &SessionId) {
+        let now = Utc::now().timestamp();
+        self.revoked_sessions
+            .retain(|_, revoked_until| *revoked_until > now);
+        self.revoked_sessions
+            .insert(session_id.clone(), now + self.ttl_seconds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_verify_until_revoked() {
+        let mut session_tokens = SessionTokenManager::new("secret", 60);
+        let session_id: SessionId = "1".into();
+        let session_token = session_tokens.issue(&session_id, true).unwrap();
+
+        let claims = session_tokens.verify(&session_token).unwrap();
+        assert_eq!(claims.sub, session_id);
+        assert!(claims.admin);
+        assert_eq!(
+            SessionTokenManager::new("other", 60).verify(&session_token),
+            None
+        );
+
+        session_tokens.revoke(&session_id);
+        assert_eq!(session_tokens.verify(&session_token), None);
+        let other_token = session_tokens.issue(&"2".into(), false).unwrap();
+        assert!(session_tokens.verify(&other_token).is_some());
+    }
+
+    #[test]
+    fn test_expired_tokens_are_rejected() {
+        let session_tokens = SessionTokenManager::new("secret", -1);
+        let session_token = session_tokens.issue(&"1".into(), false).unwrap();
+
+        assert_eq!(session_tokens.verify(&session_token), None);
+    }
+}
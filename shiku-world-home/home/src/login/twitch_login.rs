@@ -1,18 +1,35 @@
-use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-use flume::{unbounded, Receiver};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, errors::Error as JWTError, Algorithm, DecodingKey, Validation};
-use log::{debug, error};
+use log::debug;
 use reqwest::{Client, Error as ReqwestError, Url};
 use serde::Deserialize;
 use url::ParseError as UrlParseError;
 
-use crate::core::guest::ActorId;
-use crate::core::module::{ModuleInputReceiver, ModuleOutputSender};
+use crate::core::guest::{LoginData, LoginProvider};
+use crate::core::module::ProviderLoggedIn;
+use crate::login::provider::{
+    LoginProviderBackend, LoginProviderError, PendingLogin, ProviderTokenStatus, ProviderUser,
+};
+
+pub struct TwitchLoginConfig {
+    client_id: String,
+    client_secret: String,
+    extension_secret: Option<String>,
+    redirect_uri: String,
+}
+
+#[derive(Default)]
+struct ExtensionAccessToken {
+    token: Option<(String, DateTime<Utc>)>,
+    refresh_failed: bool,
+}
 
 pub struct TwitchApiLogin {
-    receiver: Receiver<Result<TwitchUserResponseData, TwitchApiError>>,
-    pub result: Option<Result<TwitchUserResponseData, TwitchApiError>>,
+    config: Arc<TwitchLoginConfig>,
+    extension_access_token: Arc<Mutex<ExtensionAccessToken>>,
 }
 
 #[derive(Debug)]
@@ -22,6 +39,7 @@ pub enum TwitchApiError {
     JWTError(JWTError),
     InvalidResponse(String),
     NoAuthCodeProvided,
+    ExtensionNotConfigured,
 }
 
 impl From<ReqwestError> for TwitchApiError {
@@ -45,7 +63,6 @@ impl From<JWTError> for TwitchApiError {
 #[derive(Deserialize, Debug)]
 pub struct TwitchExtensionOauthTokenResponse {
     pub access_token: String,
-    #[allow(dead_code)]
     pub expires_in: i32,
     /*scope: Option<Vec<String>>,
     token_type: String,*/
@@ -94,33 +111,108 @@ struct TwitchJWTClaims {
 }
 
 impl TwitchApiLogin {
+    pub fn from_env() -> Option<TwitchApiLogin> {
+        let (Ok(client_id), Ok(client_secret)) = (
+            env::var("TWITCH_CLIENT_ID"),
+            env::var("TWITCH_CLIENT_SECRET"),
+        ) else {
+            return None;
+        };
+
+        Some(TwitchApiLogin {
+            config: Arc::new(TwitchLoginConfig {
+                client_id,
+                client_secret,
+                extension_secret: env::var("TWITCH_EXTENSION_SECRET").ok(),
+                redirect_uri: env::var("TWITCH_REDIRECT_URI")
+                    .unwrap_or_else(|_| "https://localhost:8080".to_string()),
+            }),
+            extension_access_token: Arc::new(Mutex::new(ExtensionAccessToken::default())),
+        })
+    }
+
+    fn lock_extension_access_token(
+        extension_access_token: &Mutex<ExtensionAccessToken>,
+    ) -> MutexGuard<'_, ExtensionAccessToken> {
+        extension_access_token
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn get_extension_access_token(
+        config: &TwitchLoginConfig,
+        extension_access_token: &Mutex<ExtensionAccessToken>,
+    ) -> Result<String, TwitchApiError> {
+        if let Some((access_token, expires_at)) =
+            &Self::lock_extension_access_token(extension_access_token).token
+        {
+            if *expires_at > Utc::now() {
+                return Ok(access_token.clone());
+            }
+        }
+
+        let result = Self::fetch_extension_access_token(config).await;
+        let mut extension_access_token = Self::lock_extension_access_token(extension_access_token);
+        extension_access_token.refresh_failed = result.is_err();
+        let oauth_response_as_json = result?;
+        extension_access_token.token = Some((
+            oauth_response_as_json.access_token.clone(),
+            Utc::now() + Duration::seconds(oauth_response_as_json.expires_in.into()),
+        ));
+
+        Ok(oauth_response_as_json.access_token)
+    }
+
+    async fn fetch_extension_access_token(
+        config: &TwitchLoginConfig,
+    ) -> Result<TwitchExtensionOauthTokenResponse, TwitchApiError> {
+        let url = Url::parse_with_params(
+            "https://id.twitch.tv/oauth2/token",
+            &[
+                ("client_id", config.client_id.as_str()),
+                ("client_secret", config.client_secret.as_str()),
+                ("grant_type", "client_credentials"),
+            ],
+        )?;
+
+        Ok(Client::new()
+            .post(url)
+            .send()
+            .await?
+            .json::<TwitchExtensionOauthTokenResponse>()
+            .await?)
+    }
+
     async fn _login(
-        client_id: &str,
-        client_secret: &str,
-        redirect_uri: &str,
-        extension_secret: &str,
+        config: &TwitchLoginConfig,
+        extension_access_token: &Mutex<ExtensionAccessToken>,
         auth_code_option: Option<String>,
         jwt_token_option: Option<String>,
-        extension_access_token: String,
     ) -> Result<TwitchUserResponseData, TwitchApiError> {
         let client = Client::new();
 
         let (access_token, user_id_option) = if let Some(jwt_token) = jwt_token_option {
+            let Some(extension_secret) = &config.extension_secret else {
+                return Err(TwitchApiError::ExtensionNotConfigured);
+            };
             let token = decode::<TwitchJWTClaims>(
                 &jwt_token,
                 &DecodingKey::from_base64_secret(extension_secret)?,
                 &Validation::new(Algorithm::HS256),
             )?;
 
-            (extension_access_token, Some(token.claims.user_id))
+            (
+                Self::get_extension_access_token(config, extension_access_token).await?,
+                Some(token.claims.user_id),
+            )
         } else if let Some(auth_code) = auth_code_option {
             let url = Url::parse_with_params(
                 "https://id.twitch.tv/oauth2/token",
                 &[
-                    ("client_id", client_id),
-                    ("client_secret", client_secret),
+                    ("client_id", config.client_id.as_str()),
+                    ("client_secret", config.client_secret.as_str()),
                     ("grant_type", "authorization_code"),
-                    ("redirect_uri", redirect_uri),
+                    ("redirect_uri", config.redirect_uri.as_str()),
                     ("code", &auth_code),
                 ],
             )?;
@@ -141,7 +233,7 @@ impl TwitchApiLogin {
         let mut request = client
             .get("https://api.twitch.tv/helix/users")
             .header("Authorization", format!("Bearer {}", access_token.clone()))
-            .header("Client-Id", client_id);
+            .header("Client-Id", config.client_id.as_str());
 
         if let Some(user_id) = user_id_option {
             request = request.query(&[("id", user_id)]);
@@ -164,49 +256,63 @@ impl TwitchApiLogin {
         }
     }
 
-    pub fn login(
-        auth_code: Option<String>,
-        jwt_token: Option<String>,
-        extension_access_token: String,
-    ) -> TwitchApiLogin {
-        let (sender, receiver) = unbounded();
-        tokio::spawn(async move {
-            let client_id = "uchpfk924k24ozzra8f6i7bpthn33r";
-            let client_secret = "0t6mrb7w4j6o8l5dq51rncmt8u91uu";
-            let extension_secret = "2Z0pxwocuMUO6IPSfQVA5xYK/ZgDat164xjSONkG6nI=";
-            let redirect_uri = "https://localhost:8080";
-            if let Err(err) = match Self::_login(
-                client_id,
-                client_secret,
-                redirect_uri,
-                extension_secret,
-                auth_code,
-                jwt_token,
-                extension_access_token,
-            )
-            .await
-            {
-                Ok(twitch_user_data) => sender.send(Ok(twitch_user_data)),
-                Err(err) => {
-                    debug!("Sending twitch api error");
-                    sender.send(Err(err))
-                }
-            } {
-                error!("Could not send twitch login data to be polled?! {:?}", err);
-            }
-        });
+    fn to_provider_user(
+        user_response_data: TwitchUserResponseData,
+    ) -> Result<ProviderUser, TwitchApiError> {
+        let created_at = DateTime::parse_from_rfc3339(user_response_data.created_at.as_str())
+            .map_err(|_| {
+                TwitchApiError::InvalidResponse(format!(
+                    "Could not parse created_at {}",
+                    user_response_data.created_at
+                ))
+            })?;
 
-        TwitchApiLogin {
-            receiver,
-            result: None,
-        }
+        Ok(ProviderUser {
+            login_data: LoginData {
+                provider_user_id: user_response_data.id,
+                display_name: user_response_data.display_name,
+                views: Some(user_response_data.view_count),
+                provider: LoginProvider::Twitch,
+            },
+            created_at: Some(created_at.with_timezone(&Utc)),
+            guest_token: None,
+        })
     }
+}
 
-    pub fn poll_result(&mut self) -> bool {
-        for received_result in self.receiver.try_iter() {
-            self.result = Some(received_result);
-        }
+impl LoginProviderBackend for TwitchApiLogin {
+    fn provider(&self) -> LoginProvider {
+        LoginProvider::Twitch
+    }
+
+    fn login(&self, provider_logged_in: ProviderLoggedIn) -> PendingLogin {
+        let config = self.config.clone();
+        let extension_access_token = self.extension_access_token.clone();
+        PendingLogin::spawn(async move {
+            Self::_login(
+                &config,
+                &extension_access_token,
+                provider_logged_in.auth_code,
+                provider_logged_in.access_token,
+            )
+            .await
+            .and_then(Self::to_provider_user)
+            .map_err(|err| {
+                debug!("Twitch api error {:?}", err);
+                LoginProviderError::TwitchApiError(err)
+            })
+        })
+    }
 
-        self.result.is_some()
+    fn token_status(&self) -> Option<ProviderTokenStatus> {
+        self.config.extension_secret.as_ref()?;
+        let extension_access_token =
+            Self::lock_extension_access_token(&self.extension_access_token);
+        Some(match &extension_access_token.token {
+            _ if extension_access_token.refresh_failed => ProviderTokenStatus::RefreshFailed,
+            None => ProviderTokenStatus::NotFetched,
+            Some((_, expires_at)) if *expires_at <= Utc::now() => ProviderTokenStatus::Expired,
+            Some(_) => ProviderTokenStatus::Valid,
+        })
     }
 }
@@ -5,16 +5,19 @@ extern crate thiserror;
 use dotenv::dotenv;
 use env_logger::Builder;
 use log::LevelFilter;
+use std::env;
 use std::io::Write;
+use std::path::Path;
 use std::time::Duration;
 
 use crate::conductor_module::def::ConductorModule;
 use crate::core::blueprint::def::{BlueprintError, BlueprintService};
 use crate::core::blueprint::resource_cache::init_resource_cache;
 use crate::core::module::SystemModule;
+use crate::core::module_system::replay::Replay;
 use crate::core::{blueprint, TARGET_FPS};
 use crate::resource_module::def::ResourceModule;
-use crate::websocket_module::WebsocketModule;
+use crate::websocket_module::{WebsocketLimits, WebsocketModule};
 
 mod conductor_module;
 mod core;
@@ -37,7 +40,19 @@ async fn main() {
 
     init_resource_cache().expect("Resource cache should initialize without problems.");
 
-    let mut websocket_module = WebsocketModule::new();
+    if let [_, command, replay_path] = env::args().collect::<Vec<String>>().as_slice() {
+        if command == "replay" {
+            let world_state =
+                Replay::play_file(Path::new(replay_path)).expect("Replay should be playable.");
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&world_state).expect("World state is serializable.")
+            );
+            return;
+        }
+    }
+
+    let mut websocket_module = WebsocketModule::new(WebsocketLimits::from_env());
     websocket_module.start();
 
     let blueprint_service =
@@ -0,0 +1,309 @@
+use chrono::NaiveDateTime;
+
+use crate::core::audit_log::{AuditLogEntry, AuditLogQuery, NewAuditLogEntry};
+use crate::core::blueprint::def::{AchievementId, LeaderboardId, ModuleId};
+use crate::core::guest::{AdminRoleGrant, GuestBan};
+use crate::core::leaderboard::LeaderboardEntry;
+use crate::persistence_module::models::{
+    FoundSecret, NewGuestModuleData, PersistedGuest, UnlockedAchievement, UpdatePersistedGuestState,
+};
+use crate::persistence_module::PersistenceError;
+
+pub trait PersistenceBackend: Send + Sync {
+    fn lazy_get_persisted_guest_by_provider_id(
+        &self,
+        provider_id: &str,
+        display_name: &str,
+    ) -> Result<PersistedGuest, PersistenceError>;
+
+    /// Attaches `provider_id` to the guest known as `anonymous_provider_id`. If the provider
+    /// identity already has its own guest, the anonymous progress is merged into that one.
+    fn link_provider_identity(
+        &self,
+        anonymous_provider_id: &str,
+        provider_id: &str,
+        display_name: &str,
+    ) -> Result<PersistedGuest, PersistenceError>;
+
+    /// Every provider id of the guest known as `provider_id`, including itself.
+    fn get_provider_ids_of_guest(&self, provider_id: &str)
+        -> Result<Vec<String>, PersistenceError>;
+
+    fn update_persisted_guest_state(
+        &self,
+        update_persisted_guest_state: UpdatePersistedGuestState,
+    ) -> Result<usize, PersistenceError>;
+
+    fn add_secret_found(
+        &self,
+        name: String,
+        persisted_guest_state_id: i32,
+    ) -> Result<FoundSecret, PersistenceError>;
+
+    fn write_guest_module_data(
+        &self,
+        writes: &[NewGuestModuleData],
+    ) -> Result<usize, PersistenceError>;
+
+    fn add_achievement_unlocked(
+        &self,
+        module_id: ModuleId,
+        achievement_id: AchievementId,
+        persisted_guest_state_id: i32,
+    ) -> Result<UnlockedAchievement, PersistenceError>;
+
+    fn get_leaderboard_entries(
+        &self,
+        module_id: &ModuleId,
+        leaderboard_id: &LeaderboardId,
+        period_start: NaiveDateTime,
+    ) -> Result<Vec<LeaderboardEntry>, PersistenceError>;
+
+    fn upsert_leaderboard_entry(
+        &self,
+        module_id: ModuleId,
+        leaderboard_id: LeaderboardId,
+        period_start: NaiveDateTime,
+        entry: &LeaderboardEntry,
+    ) -> Result<(), PersistenceError>;
+
+    fn get_admin_role_grants(&self) -> Result<Vec<AdminRoleGrant>, PersistenceError>;
+
+    fn add_admin_role_grant(&self, grant: &AdminRoleGrant) -> Result<(), PersistenceError>;
+
+    fn remove_admin_role_grant(&self, grant: &AdminRoleGrant) -> Result<usize, PersistenceError>;
+
+    fn append_audit_log_entry(
+        &self,
+        entry: &NewAuditLogEntry,
+    ) -> Result<AuditLogEntry, PersistenceError>;
+
+    fn get_audit_log_entries(
+        &self,
+        query: &AuditLogQuery,
+    ) -> Result<Vec<AuditLogEntry>, PersistenceError>;
+
+    fn get_guest_bans(&self) -> Result<Vec<GuestBan>, PersistenceError>;
+
+    /// Replaces an existing ban of the same provider user.
+    fn add_guest_ban(&self, ban: &GuestBan) -> Result<(), PersistenceError>;
+
+    fn remove_guest_ban(&self, provider_user_id: &str) -> Result<usize, PersistenceError>;
+
+    /// Fails when the storage can not be reached right now.
+    fn ping(&self) -> Result<(), PersistenceError>;
+}
+
+/// Every backend has to pass the same checks, so they behave the same in production and tests.
+#[cfg(test)]
+pub mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::core::guest::AdminRole;
+
+    use super::*;
+
+    pub fn check_backend(backend: &dyn PersistenceBackend) {
+        let guest = backend
+            .lazy_get_persisted_guest_by_provider_id("1234", "Tester")
+            .unwrap();
+        assert_eq!(guest.info.display_name, "Tester");
+        assert!(guest.secrets_found.is_empty());
+
+        let same_guest = backend
+            .lazy_get_persisted_guest_by_provider_id("1234", "Renamed")
+            .unwrap();
+        assert_eq!(same_guest.info.id, guest.info.id);
+
+        let id = guest.info.id;
+        assert_eq!(
+            backend
+                .update_persisted_guest_state(UpdatePersistedGuestState {
+                    id,
+                    is_observer: None,
+                    is_tester: Some(true),
+                    last_time_joined: None,
+                    times_joined: Some(3),
+                })
+                .unwrap(),
+            1
+        );
+
+        backend.add_secret_found("cave".into(), id).unwrap();
+        assert!(backend.add_secret_found("cave".into(), id).is_err());
+
+        let write = |value| NewGuestModuleData {
+            persisted_guest_state_id: id,
+            module_id: "module".into(),
+            key: "coins".into(),
+            value,
+            updated_at: Utc::now().naive_utc(),
+        };
+        backend.write_guest_module_data(&[write(json!(1))]).unwrap();
+        backend.write_guest_module_data(&[write(json!(5))]).unwrap();
+
+        backend
+            .add_achievement_unlocked("module".into(), "first".into(), id)
+            .unwrap();
+        assert!(backend
+            .add_achievement_unlocked("module".into(), "first".into(), id)
+            .is_err());
+
+        let guest = backend
+            .lazy_get_persisted_guest_by_provider_id("1234", "Tester")
+            .unwrap();
+        assert!(guest.info.is_tester);
+        assert_eq!(guest.info.times_joined, 3);
+        assert_eq!(guest.secrets_found.len(), 1);
+        assert_eq!(guest.module_data["module"]["coins"], json!(5));
+        assert_eq!(guest.achievements_unlocked.len(), 1);
+
+        let period_start = NaiveDateTime::default();
+        let entry = |score| LeaderboardEntry {
+            persisted_guest_state_id: id,
+            display_name: String::new(),
+            score,
+            updated_at: 0,
+        };
+        backend
+            .upsert_leaderboard_entry("module".into(), "board".into(), period_start, &entry(3.0))
+            .unwrap();
+        backend
+            .upsert_leaderboard_entry("module".into(), "board".into(), period_start, &entry(7.5))
+            .unwrap();
+        let entries = backend
+            .get_leaderboard_entries(&"module".into(), &"board".into(), period_start)
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].score, 7.5);
+        assert_eq!(entries[0].display_name, "Tester");
+
+        let anonymous = backend
+            .lazy_get_persisted_guest_by_provider_id("anonymous:a", "Guest-a")
+            .unwrap();
+        backend
+            .add_secret_found("cave".into(), anonymous.info.id)
+            .unwrap();
+        backend
+            .add_secret_found("lake".into(), anonymous.info.id)
+            .unwrap();
+        let linked = backend
+            .link_provider_identity("anonymous:a", "1234", "Tester")
+            .unwrap();
+        assert_eq!(linked.info.id, id);
+        assert_eq!(linked.secrets_found.len(), 2);
+        assert_eq!(
+            backend
+                .lazy_get_persisted_guest_by_provider_id("anonymous:a", "Guest-a")
+                .unwrap()
+                .info
+                .id,
+            id
+        );
+        let mut provider_ids = backend.get_provider_ids_of_guest("anonymous:a").unwrap();
+        provider_ids.sort();
+        assert_eq!(provider_ids, vec!["1234", "anonymous:a"]);
+        assert!(backend
+            .get_provider_ids_of_guest("unknown")
+            .unwrap()
+            .is_empty());
+
+        let anonymous = backend
+            .lazy_get_persisted_guest_by_provider_id("anonymous:b", "Guest-b")
+            .unwrap();
+        backend
+            .add_secret_found("cave".into(), anonymous.info.id)
+            .unwrap();
+        let linked = backend
+            .link_provider_identity("anonymous:b", "5678", "Other")
+            .unwrap();
+        assert_eq!(linked.info.id, anonymous.info.id);
+        assert_eq!(linked.info.display_name, "Other");
+        assert_eq!(linked.secrets_found.len(), 1);
+
+        let grant = |module_id: Option<&str>| AdminRoleGrant {
+            provider_user_id: "1234".into(),
+            role: AdminRole::Editor,
+            module_id: module_id.map(String::from),
+        };
+        backend
+            .add_admin_role_grant(&grant(Some("module")))
+            .unwrap();
+        backend.add_admin_role_grant(&grant(None)).unwrap();
+        assert_eq!(backend.get_admin_role_grants().unwrap().len(), 2);
+        assert_eq!(backend.remove_admin_role_grant(&grant(None)).unwrap(), 1);
+        assert_eq!(
+            backend.get_admin_role_grants().unwrap(),
+            vec![grant(Some("module"))]
+        );
+
+        let audit = |provider_user_id: &str, module_ids: &[&str]| NewAuditLogEntry {
+            provider_user_id: provider_user_id.into(),
+            action: "UpdateScript".into(),
+            module_ids: module_ids
+                .iter()
+                .map(|module_id| module_id.to_string())
+                .collect(),
+            resource: None,
+            diff: json!({"content": {"from": "a", "to": "b"}}),
+        };
+        let first = backend
+            .append_audit_log_entry(&audit("1234", &["module"]))
+            .unwrap();
+        backend
+            .append_audit_log_entry(&audit("5678", &["other", "module"]))
+            .unwrap();
+        backend.append_audit_log_entry(&audit("1234", &[])).unwrap();
+        assert_eq!(
+            backend
+                .get_audit_log_entries(&AuditLogQuery::default())
+                .unwrap()
+                .len(),
+            3
+        );
+        let in_module = backend
+            .get_audit_log_entries(&AuditLogQuery {
+                module_id: Some("module".into()),
+                ..AuditLogQuery::default()
+            })
+            .unwrap();
+        assert_eq!(in_module.len(), 2);
+        assert_eq!(in_module[0].module_ids, vec!["other", "module"]);
+        assert!(backend
+            .get_audit_log_entries(&AuditLogQuery {
+                module_id: Some("modul_".into()),
+                ..AuditLogQuery::default()
+            })
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            backend
+                .get_audit_log_entries(&AuditLogQuery {
+                    module_id: Some("module".into()),
+                    provider_user_id: Some("1234".into()),
+                    ..AuditLogQuery::default()
+                })
+                .unwrap(),
+            vec![first.clone()]
+        );
+        assert!(backend
+            .get_audit_log_entries(&AuditLogQuery {
+                before_id: Some(first.id),
+                ..AuditLogQuery::default()
+            })
+            .unwrap()
+            .is_empty());
+
+        let ban = |banned_until| GuestBan {
+            provider_user_id: "5678".into(),
+            reason: "griefing".into(),
+            banned_until,
+        };
+        backend.add_guest_ban(&ban(Some(1_700_000_000))).unwrap();
+        backend.add_guest_ban(&ban(None)).unwrap();
+        assert_eq!(backend.get_guest_bans().unwrap(), vec![ban(None)]);
+        assert_eq!(backend.remove_guest_ban("5678").unwrap(), 1);
+        assert!(backend.get_guest_bans().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,773 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel::upsert::excluded;
+use log::warn;
+use serde_json::Value as JsonValue;
+
+use crate::core::audit_log::{
+    join_module_ids, module_id_like_pattern, AuditLogEntry, AuditLogQuery, NewAuditLogEntry,
+};
+use crate::core::blueprint::def::{AchievementId, LeaderboardId, ModuleId};
+use crate::core::guest::{AdminRoleGrant, GuestBan};
+use crate::core::leaderboard::LeaderboardEntry;
+use crate::persistence_module::backend::PersistenceBackend;
+use crate::persistence_module::models::{
+    AdminRoleGrantRow, AuditLogEntryRow, FoundSecret, GuestBanRow, GuestModuleData,
+    LeaderboardEntryRow, NewAdminRoleGrantRow, NewAuditLogEntryRow, NewFoundSecret, NewGuestBanRow,
+    NewGuestIdentity, NewGuestModuleData, NewLeaderboardEntryRow, NewPersistedGuestState,
+    NewUnlockedAchievement, PersistedGuest, PersistedGuestState, UnlockedAchievement,
+    UpdatePersistedGuestState,
+};
+use crate::persistence_module::schema::{
+    admin_role_grants, audit_log_entries, found_secrets, guest_bans, guest_identities,
+    guest_module_data, leaderboard_entries, persisted_guest_states, unlocked_achievements,
+};
+use crate::persistence_module::PersistenceError;
+
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Both SQL backends share everything but the connection type and the storage of
+/// `guest_module_data` values, which each backend implements on its own.
+macro_rules! diesel_persistence_backend {
+    ($backend:ident, $connection:ty) => {
+        pub struct $backend {
+            connection_pool: Pool<ConnectionManager<$connection>>,
+        }
+
+        impl $backend {
+            fn get_connection(
+                &self,
+            ) -> Result<PooledConnection<ConnectionManager<$connection>>, PersistenceError> {
+                self.connection_pool
+                    .get()
+                    .map_err(|err| PersistenceError::R2D2Error(err.to_string()))
+            }
+
+            fn find_persisted_guest_state_by_provider_id(
+                connection: &mut $connection,
+                provider_id: &str,
+            ) -> Result<Option<PersistedGuestState>, PersistenceError> {
+                Ok(guest_identities::table
+                    .inner_join(persisted_guest_states::table)
+                    .filter(guest_identities::provider_user_id.eq(provider_id))
+                    .select(persisted_guest_states::all_columns)
+                    .first::<PersistedGuestState>(connection)
+                    .optional()?)
+            }
+
+            fn add_guest_identity(
+                connection: &mut $connection,
+                persisted_guest_state_id: i32,
+                provider_id: &str,
+            ) -> Result<(), PersistenceError> {
+                diesel::insert_into(guest_identities::table)
+                    .values(&NewGuestIdentity {
+                        persisted_guest_state_id,
+                        provider_user_id: provider_id,
+                        created_at: Utc::now().naive_utc(),
+                    })
+                    .execute(connection)?;
+
+                Ok(())
+            }
+
+            fn lazy_get_persisted_guest_state_by_provider_id(
+                connection: &mut $connection,
+                provider_id: &str,
+                display_name: &str,
+            ) -> Result<PersistedGuestState, PersistenceError> {
+                connection.transaction(|connection| {
+                    if let Some(persisted_guest_state) =
+                        Self::find_persisted_guest_state_by_provider_id(connection, provider_id)?
+                    {
+                        return Ok(persisted_guest_state);
+                    }
+
+                    let new_persisted_guest_state = NewPersistedGuestState {
+                        display_name: display_name.to_string(),
+                        is_observer: false,
+                        is_tester: false,
+                    };
+                    let persisted_guest_state: PersistedGuestState =
+                        diesel::insert_into(persisted_guest_states::table)
+                            .values(&new_persisted_guest_state)
+                            .get_result(connection)?;
+                    Self::add_guest_identity(connection, persisted_guest_state.id, provider_id)?;
+
+                    Ok(persisted_guest_state)
+                })
+            }
+
+            /// Moves everything of `from_id` that does not collide with what `into_id` already
+            /// has, the rest goes away together with the `from_id` state.
+            fn merge_persisted_guest_states(
+                connection: &mut $connection,
+                from_id: i32,
+                into_id: i32,
+            ) -> Result<(), PersistenceError> {
+                let statements = [
+                    format!(
+                        "UPDATE found_secrets SET persisted_guest_state_id = {into_id} \
+                         WHERE persisted_guest_state_id = {from_id} AND name NOT IN \
+                         (SELECT name FROM found_secrets WHERE persisted_guest_state_id = {into_id})"
+                    ),
+                    format!(
+                        "UPDATE unlocked_achievements SET persisted_guest_state_id = {into_id} \
+                         WHERE persisted_guest_state_id = {from_id} AND NOT EXISTS \
+                         (SELECT 1 FROM unlocked_achievements existing \
+                         WHERE existing.persisted_guest_state_id = {into_id} \
+                         AND existing.module_id = unlocked_achievements.module_id \
+                         AND existing.achievement_id = unlocked_achievements.achievement_id)"
+                    ),
+                    format!(
+                        "DELETE FROM guest_module_data WHERE persisted_guest_state_id = {into_id} \
+                         AND EXISTS (SELECT 1 FROM guest_module_data newer \
+                         WHERE newer.persisted_guest_state_id = {from_id} \
+                         AND newer.module_id = guest_module_data.module_id \
+                         AND newer.key = guest_module_data.key \
+                         AND newer.updated_at > guest_module_data.updated_at)"
+                    ),
+                    format!(
+                        "UPDATE guest_module_data SET persisted_guest_state_id = {into_id} \
+                         WHERE persisted_guest_state_id = {from_id} AND NOT EXISTS \
+                         (SELECT 1 FROM guest_module_data existing \
+                         WHERE existing.persisted_guest_state_id = {into_id} \
+                         AND existing.module_id = guest_module_data.module_id \
+                         AND existing.key = guest_module_data.key)"
+                    ),
+                    format!(
+                        "UPDATE leaderboard_entries SET persisted_guest_state_id = {into_id} \
+                         WHERE persisted_guest_state_id = {from_id} AND NOT EXISTS \
+                         (SELECT 1 FROM leaderboard_entries existing \
+                         WHERE existing.persisted_guest_state_id = {into_id} \
+                         AND existing.module_id = leaderboard_entries.module_id \
+                         AND existing.leaderboard_id = leaderboard_entries.leaderboard_id \
+                         AND existing.period_start = leaderboard_entries.period_start)"
+                    ),
+                    format!(
+                        "UPDATE guest_identities SET persisted_guest_state_id = {into_id} \
+                         WHERE persisted_guest_state_id = {from_id}"
+                    ),
+                    format!("DELETE FROM persisted_guest_states WHERE id = {from_id}"),
+                ];
+                for statement in statements {
+                    diesel::sql_query(statement).execute(connection)?;
+                }
+
+                Ok(())
+            }
+
+            fn load_persisted_guest(
+                connection: &mut $connection,
+                persisted_guest_state: PersistedGuestState,
+            ) -> Result<PersistedGuest, PersistenceError> {
+                let secrets_found =
+                    FoundSecret::belonging_to(&persisted_guest_state).get_results(connection)?;
+
+                let module_data = Self::load_guest_module_data(connection, persisted_guest_state.id)?;
+
+                let achievements_unlocked = UnlockedAchievement::belonging_to(&persisted_guest_state)
+                    .get_results(connection)?;
+
+                Ok(PersistedGuest {
+                    info: persisted_guest_state,
+                    secrets_found,
+                    module_data,
+                    achievements_unlocked,
+                })
+            }
+        }
+
+        impl PersistenceBackend for $backend {
+            fn lazy_get_persisted_guest_by_provider_id(
+                &self,
+                provider_id: &str,
+                display_name: &str,
+            ) -> Result<PersistedGuest, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let persisted_guest_state = Self::lazy_get_persisted_guest_state_by_provider_id(
+                    &mut connection,
+                    provider_id,
+                    display_name,
+                )?;
+
+                Self::load_persisted_guest(&mut connection, persisted_guest_state)
+            }
+
+            fn link_provider_identity(
+                &self,
+                anonymous_provider_id: &str,
+                provider_id: &str,
+                display_name: &str,
+            ) -> Result<PersistedGuest, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let persisted_guest_state = connection.transaction(|connection| {
+                    let anonymous_state = Self::find_persisted_guest_state_by_provider_id(
+                        connection,
+                        anonymous_provider_id,
+                    )?;
+                    let provider_state =
+                        Self::find_persisted_guest_state_by_provider_id(connection, provider_id)?;
+
+                    match (anonymous_state, provider_state) {
+                        (Some(anonymous_state), None) => {
+                            Self::add_guest_identity(connection, anonymous_state.id, provider_id)?;
+                            Ok::<PersistedGuestState, PersistenceError>(
+                                diesel::update(persisted_guest_states::table.find(anonymous_state.id))
+                                    .set(persisted_guest_states::display_name.eq(display_name))
+                                    .get_result(connection)?,
+                            )
+                        }
+                        (Some(anonymous_state), Some(provider_state))
+                            if anonymous_state.id != provider_state.id =>
+                        {
+                            Self::merge_persisted_guest_states(
+                                connection,
+                                anonymous_state.id,
+                                provider_state.id,
+                            )?;
+                            Ok(provider_state)
+                        }
+                        _ => Self::lazy_get_persisted_guest_state_by_provider_id(
+                            connection,
+                            provider_id,
+                            display_name,
+                        ),
+                    }
+                })?;
+
+                Self::load_persisted_guest(&mut connection, persisted_guest_state)
+            }
+
+            fn get_provider_ids_of_guest(
+                &self,
+                provider_id: &str,
+            ) -> Result<Vec<String>, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let Some(persisted_guest_state) =
+                    Self::find_persisted_guest_state_by_provider_id(&mut connection, provider_id)?
+                else {
+                    return Ok(Vec::new());
+                };
+
+                Ok(guest_identities::table
+                    .filter(guest_identities::persisted_guest_state_id.eq(persisted_guest_state.id))
+                    .select(guest_identities::provider_user_id)
+                    .load::<String>(&mut connection)?)
+            }
+
+            fn update_persisted_guest_state(
+                &self,
+                update_persisted_guest_state: UpdatePersistedGuestState,
+            ) -> Result<usize, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let target = persisted_guest_states::dsl::persisted_guest_states
+                    .filter(persisted_guest_states::dsl::id.eq(update_persisted_guest_state.id));
+
+                Ok(diesel::update(target)
+                    .set(&update_persisted_guest_state)
+                    .execute(&mut connection)?)
+            }
+
+            fn add_secret_found(
+                &self,
+                name: String,
+                persisted_guest_state_id: i32,
+            ) -> Result<FoundSecret, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let new_secret_found = NewFoundSecret {
+                    name,
+                    date: Utc::now().naive_utc(),
+                    persisted_guest_state_id,
+                };
+
+                Ok(diesel::insert_into(found_secrets::table)
+                    .values(&new_secret_found)
+                    .get_result(&mut connection)?)
+            }
+
+            fn write_guest_module_data(
+                &self,
+                writes: &[NewGuestModuleData],
+            ) -> Result<usize, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                Self::write_guest_module_data_rows(&mut connection, writes)
+            }
+
+            fn add_achievement_unlocked(
+                &self,
+                module_id: ModuleId,
+                achievement_id: AchievementId,
+                persisted_guest_state_id: i32,
+            ) -> Result<UnlockedAchievement, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let new_unlocked_achievement = NewUnlockedAchievement {
+                    module_id,
+                    achievement_id,
+                    unlocked_at: Utc::now().naive_utc(),
+                    persisted_guest_state_id,
+                };
+
+                Ok(diesel::insert_into(unlocked_achievements::table)
+                    .values(&new_unlocked_achievement)
+                    .get_result(&mut connection)?)
+            }
+
+            fn get_leaderboard_entries(
+                &self,
+                module_id: &ModuleId,
+                leaderboard_id: &LeaderboardId,
+                period_start: NaiveDateTime,
+            ) -> Result<Vec<LeaderboardEntry>, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let rows: Vec<(LeaderboardEntryRow, String)> = leaderboard_entries::table
+                    .inner_join(persisted_guest_states::table)
+                    .filter(leaderboard_entries::module_id.eq(module_id))
+                    .filter(leaderboard_entries::leaderboard_id.eq(leaderboard_id))
+                    .filter(leaderboard_entries::period_start.eq(period_start))
+                    .select((
+                        leaderboard_entries::all_columns,
+                        persisted_guest_states::display_name,
+                    ))
+                    .load(&mut connection)?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(row, display_name)| LeaderboardEntry {
+                        persisted_guest_state_id: row.persisted_guest_state_id,
+                        display_name,
+                        score: row.score,
+                        updated_at: row.updated_at.timestamp(),
+                    })
+                    .collect())
+            }
+
+            fn upsert_leaderboard_entry(
+                &self,
+                module_id: ModuleId,
+                leaderboard_id: LeaderboardId,
+                period_start: NaiveDateTime,
+                entry: &LeaderboardEntry,
+            ) -> Result<(), PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let new_leaderboard_entry = NewLeaderboardEntryRow {
+                    persisted_guest_state_id: entry.persisted_guest_state_id,
+                    module_id,
+                    leaderboard_id,
+                    period_start,
+                    score: entry.score,
+                    updated_at: Utc::now().naive_utc(),
+                };
+
+                diesel::insert_into(leaderboard_entries::table)
+                    .values(&new_leaderboard_entry)
+                    .on_conflict((
+                        leaderboard_entries::persisted_guest_state_id,
+                        leaderboard_entries::module_id,
+                        leaderboard_entries::leaderboard_id,
+                        leaderboard_entries::period_start,
+                    ))
+                    .do_update()
+                    .set((
+                        leaderboard_entries::score.eq(excluded(leaderboard_entries::score)),
+                        leaderboard_entries::updated_at
+                            .eq(excluded(leaderboard_entries::updated_at)),
+                    ))
+                    .execute(&mut connection)?;
+
+                Ok(())
+            }
+
+            fn get_admin_role_grants(&self) -> Result<Vec<AdminRoleGrant>, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let rows: Vec<AdminRoleGrantRow> =
+                    admin_role_grants::table.load(&mut connection)?;
+
+                Ok(rows
+                    .into_iter()
+                    .filter_map(|row| match row.role.parse() {
+                        Ok(role) => Some(AdminRoleGrant {
+                            provider_user_id: row.provider_user_id,
+                            role,
+                            module_id: row.module_id,
+                        }),
+                        Err(err) => {
+                            warn!("Skipping admin role grant {}: {}", row.id, err);
+                            None
+                        }
+                    })
+                    .collect())
+            }
+
+            fn add_admin_role_grant(&self, grant: &AdminRoleGrant) -> Result<(), PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                diesel::insert_into(admin_role_grants::table)
+                    .values(&NewAdminRoleGrantRow {
+                        provider_user_id: &grant.provider_user_id,
+                        role: grant.role.as_str(),
+                        module_id: grant.module_id.as_deref(),
+                        granted_at: Utc::now().naive_utc(),
+                    })
+                    .execute(&mut connection)?;
+
+                Ok(())
+            }
+
+            fn remove_admin_role_grant(
+                &self,
+                grant: &AdminRoleGrant,
+            ) -> Result<usize, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let grants_of_role = admin_role_grants::table
+                    .filter(admin_role_grants::provider_user_id.eq(&grant.provider_user_id))
+                    .filter(admin_role_grants::role.eq(grant.role.as_str()));
+
+                Ok(match &grant.module_id {
+                    Some(module_id) => diesel::delete(
+                        grants_of_role.filter(admin_role_grants::module_id.eq(module_id)),
+                    )
+                    .execute(&mut connection)?,
+                    None => diesel::delete(grants_of_role.filter(admin_role_grants::module_id.is_null()))
+                        .execute(&mut connection)?,
+                })
+            }
+
+            fn append_audit_log_entry(
+                &self,
+                entry: &NewAuditLogEntry,
+            ) -> Result<AuditLogEntry, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let row: AuditLogEntryRow = diesel::insert_into(audit_log_entries::table)
+                    .values(&NewAuditLogEntryRow {
+                        provider_user_id: &entry.provider_user_id,
+                        action: &entry.action,
+                        module_ids: join_module_ids(&entry.module_ids),
+                        resource: entry.resource.as_deref(),
+                        diff: serde_json::to_string(&entry.diff)?,
+                        created_at: Utc::now().naive_utc(),
+                    })
+                    .get_result(&mut connection)?;
+
+                Ok(row.into())
+            }
+
+            fn get_audit_log_entries(
+                &self,
+                query: &AuditLogQuery,
+            ) -> Result<Vec<AuditLogEntry>, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let mut statement = audit_log_entries::table
+                    .order(audit_log_entries::id.desc())
+                    .limit(query.limit() as i64)
+                    .into_boxed();
+                if let Some(module_id) = &query.module_id {
+                    statement = statement.filter(
+                        audit_log_entries::module_ids
+                            .like(module_id_like_pattern(module_id))
+                            .escape('\\'),
+                    );
+                }
+                if let Some(provider_user_id) = &query.provider_user_id {
+                    statement =
+                        statement.filter(audit_log_entries::provider_user_id.eq(provider_user_id));
+                }
+                if let Some(before_id) = query.before_id {
+                    statement = statement.filter(audit_log_entries::id.lt(before_id));
+                }
+
+                Ok(statement
+                    .load::<AuditLogEntryRow>(&mut connection)?
+                    .into_iter()
+                    .map(AuditLogEntry::from)
+                    .collect())
+            }
+
+            fn ping(&self) -> Result<(), PersistenceError> {
+                let mut connection = self
+                    .connection_pool
+                    .get_timeout(PING_TIMEOUT)
+                    .map_err(|err| PersistenceError::R2D2Error(err.to_string()))?;
+
+                diesel::sql_query("SELECT 1").execute(&mut connection)?;
+                Ok(())
+            }
+
+            fn get_guest_bans(&self) -> Result<Vec<GuestBan>, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                Ok(guest_bans::table
+                    .load::<GuestBanRow>(&mut connection)?
+                    .into_iter()
+                    .map(GuestBan::from)
+                    .collect())
+            }
+
+            fn add_guest_ban(&self, ban: &GuestBan) -> Result<(), PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                let new_guest_ban = NewGuestBanRow {
+                    provider_user_id: &ban.provider_user_id,
+                    reason: &ban.reason,
+                    banned_until: ban
+                        .banned_until
+                        .and_then(|banned_until| NaiveDateTime::from_timestamp_opt(banned_until, 0)),
+                    created_at: Utc::now().naive_utc(),
+                };
+
+                diesel::insert_into(guest_bans::table)
+                    .values(&new_guest_ban)
+                    .on_conflict(guest_bans::provider_user_id)
+                    .do_update()
+                    .set((
+                        guest_bans::reason.eq(excluded(guest_bans::reason)),
+                        guest_bans::banned_until.eq(excluded(guest_bans::banned_until)),
+                        guest_bans::created_at.eq(excluded(guest_bans::created_at)),
+                    ))
+                    .execute(&mut connection)?;
+
+                Ok(())
+            }
+
+            fn remove_guest_ban(&self, provider_user_id: &str) -> Result<usize, PersistenceError> {
+                let mut connection = self.get_connection()?;
+
+                Ok(diesel::delete(
+                    guest_bans::table.filter(guest_bans::provider_user_id.eq(provider_user_id)),
+                )
+                .execute(&mut connection)?)
+            }
+        }
+    };
+}
+
+diesel_persistence_backend!(PgPersistenceBackend, PgConnection);
+
+impl PgPersistenceBackend {
+    pub fn new(database_url: String) -> PgPersistenceBackend {
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+
+        PgPersistenceBackend {
+            connection_pool: Pool::builder()
+                .max_size(2)
+                .build(manager)
+                .expect("Could not establish connection pool"),
+        }
+    }
+
+    fn load_guest_module_data(
+        connection: &mut PgConnection,
+        persisted_guest_state_id: i32,
+    ) -> Result<HashMap<ModuleId, HashMap<String, JsonValue>>, PersistenceError> {
+        let mut module_data: HashMap<ModuleId, HashMap<String, JsonValue>> = HashMap::new();
+        for entry in guest_module_data::table
+            .filter(guest_module_data::persisted_guest_state_id.eq(persisted_guest_state_id))
+            .get_results::<GuestModuleData>(connection)?
+        {
+            module_data
+                .entry(entry.module_id)
+                .or_default()
+                .insert(entry.key, entry.value);
+        }
+
+        Ok(module_data)
+    }
+
+    fn write_guest_module_data_rows(
+        connection: &mut PgConnection,
+        writes: &[NewGuestModuleData],
+    ) -> Result<usize, PersistenceError> {
+        Ok(diesel::insert_into(guest_module_data::table)
+            .values(writes)
+            .on_conflict((
+                guest_module_data::persisted_guest_state_id,
+                guest_module_data::module_id,
+                guest_module_data::key,
+            ))
+            .do_update()
+            .set((
+                guest_module_data::value.eq(excluded(guest_module_data::value)),
+                guest_module_data::updated_at.eq(excluded(guest_module_data::updated_at)),
+            ))
+            .execute(connection)?)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqlitePersistenceBackend;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDateTime;
+    use diesel::connection::SimpleConnection;
+    use diesel::prelude::*;
+    use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+    use diesel::result::Error as DieselResultError;
+    use diesel::sqlite::SqliteConnection;
+    use diesel::upsert::excluded;
+    use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+
+    /// Generated from `migrations/` by the build script.
+    pub const SQLITE_MIGRATIONS: EmbeddedMigrations =
+        include!(concat!(env!("OUT_DIR"), "/sqlite_migrations.rs"));
+
+    /// SQLite has no jsonb, module data values are stored as JSON text instead.
+    mod schema {
+        diesel::table! {
+            guest_module_data (id) {
+                id -> Integer,
+                persisted_guest_state_id -> Integer,
+                module_id -> Text,
+                key -> Text,
+                value -> Text,
+                updated_at -> Timestamp,
+            }
+        }
+    }
+
+    #[derive(Queryable)]
+    struct SqliteGuestModuleData {
+        _id: i32,
+        _persisted_guest_state_id: i32,
+        module_id: String,
+        key: String,
+        value: String,
+        _updated_at: NaiveDateTime,
+    }
+
+    #[derive(Insertable)]
+    #[table_name = "guest_module_data"]
+    struct NewSqliteGuestModuleData<'a> {
+        persisted_guest_state_id: i32,
+        module_id: &'a str,
+        key: &'a str,
+        value: String,
+        updated_at: NaiveDateTime,
+    }
+
+    use schema::guest_module_data;
+
+    #[derive(Debug)]
+    struct SqliteConnectionCustomizer;
+
+    impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqliteConnectionCustomizer {
+        fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+            connection
+                .batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+                .map_err(diesel::r2d2::Error::QueryError)
+        }
+    }
+
+    diesel_persistence_backend!(SqlitePersistenceBackend, SqliteConnection);
+
+    impl SqlitePersistenceBackend {
+        pub fn new(database_url: String) -> SqlitePersistenceBackend {
+            let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+            let connection_pool = Pool::builder()
+                .max_size(1)
+                .connection_customizer(Box::new(SqliteConnectionCustomizer))
+                .build(manager)
+                .expect("Could not establish connection pool");
+            connection_pool
+                .get()
+                .expect("Could not get connection to run migrations")
+                .run_pending_migrations(SQLITE_MIGRATIONS)
+                .expect("Could not run sqlite migrations");
+
+            SqlitePersistenceBackend { connection_pool }
+        }
+
+        fn load_guest_module_data(
+            connection: &mut SqliteConnection,
+            persisted_guest_state_id: i32,
+        ) -> Result<HashMap<ModuleId, HashMap<String, JsonValue>>, PersistenceError> {
+            let mut module_data: HashMap<ModuleId, HashMap<String, JsonValue>> = HashMap::new();
+            for entry in guest_module_data::table
+                .filter(guest_module_data::persisted_guest_state_id.eq(persisted_guest_state_id))
+                .get_results::<SqliteGuestModuleData>(connection)?
+            {
+                module_data
+                    .entry(entry.module_id)
+                    .or_default()
+                    .insert(entry.key, serde_json::from_str(&entry.value)?);
+            }
+
+            Ok(module_data)
+        }
+
+        fn write_guest_module_data_rows(
+            connection: &mut SqliteConnection,
+            writes: &[NewGuestModuleData],
+        ) -> Result<usize, PersistenceError> {
+            let rows = writes
+                .iter()
+                .map(|write| {
+                    Ok(NewSqliteGuestModuleData {
+                        persisted_guest_state_id: write.persisted_guest_state_id,
+                        module_id: &write.module_id,
+                        key: &write.key,
+                        value: serde_json::to_string(&write.value)?,
+                        updated_at: write.updated_at,
+                    })
+                })
+                .collect::<Result<Vec<_>, PersistenceError>>()?;
+
+            connection
+                .transaction(|connection| {
+                    let mut written = 0;
+                    for row in &rows {
+                        written += diesel::insert_into(guest_module_data::table)
+                            .values(row)
+                            .on_conflict((
+                                guest_module_data::persisted_guest_state_id,
+                                guest_module_data::module_id,
+                                guest_module_data::key,
+                            ))
+                            .do_update()
+                            .set((
+                                guest_module_data::value.eq(excluded(guest_module_data::value)),
+                                guest_module_data::updated_at
+                                    .eq(excluded(guest_module_data::updated_at)),
+                            ))
+                            .execute(connection)?;
+                    }
+                    Ok::<usize, DieselResultError>(written)
+                })
+                .map_err(PersistenceError::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_backend() {
+        crate::persistence_module::backend::tests::check_backend(
+            &super::SqlitePersistenceBackend::new(":memory:".into()),
+        );
+    }
+}
@@ -0,0 +1,632 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::result::{DatabaseErrorKind, Error as DieselResultError};
+
+use crate::core::audit_log::{AuditLogEntry, AuditLogQuery, NewAuditLogEntry};
+use crate::core::blueprint::def::{AchievementId, LeaderboardId, ModuleId};
+use crate::core::guest::{AdminRoleGrant, GuestBan};
+use crate::core::leaderboard::LeaderboardEntry;
+use crate::persistence_module::backend::PersistenceBackend;
+use crate::persistence_module::models::{
+    FoundSecret, GuestIdentity, GuestModuleData, LeaderboardEntryRow, NewGuestModuleData,
+    PersistedGuest, PersistedGuestState, UnlockedAchievement, UpdatePersistedGuestState,
+};
+use crate::persistence_module::PersistenceError;
+
+#[derive(Default)]
+struct MemoryState {
+    next_id: i32,
+    persisted_guest_states: Vec<PersistedGuestState>,
+    guest_identities: Vec<GuestIdentity>,
+    found_secrets: Vec<FoundSecret>,
+    guest_module_data: Vec<GuestModuleData>,
+    unlocked_achievements: Vec<UnlockedAchievement>,
+    leaderboard_entries: Vec<LeaderboardEntryRow>,
+    admin_role_grants: Vec<AdminRoleGrant>,
+    audit_log_entries: Vec<AuditLogEntry>,
+    guest_bans: Vec<GuestBan>,
+}
+
+impl MemoryState {
+    fn next_id(&mut self) -> i32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn find_persisted_guest_state(&self, provider_id: &str) -> Option<PersistedGuestState> {
+        let identity = self
+            .guest_identities
+            .iter()
+            .find(|identity| identity.provider_user_id == provider_id)?;
+        self.persisted_guest_states
+            .iter()
+            .find(|persisted_guest_state| {
+                persisted_guest_state.id == identity.persisted_guest_state_id
+            })
+            .cloned()
+    }
+
+    fn add_guest_identity(&mut self, persisted_guest_state_id: i32, provider_id: &str) {
+        let id = self.next_id();
+        self.guest_identities.push(GuestIdentity {
+            id,
+            persisted_guest_state_id,
+            provider_user_id: provider_id.to_string(),
+            created_at: Utc::now().naive_utc(),
+        });
+    }
+
+    fn lazy_get_persisted_guest_state(
+        &mut self,
+        provider_id: &str,
+        display_name: &str,
+    ) -> PersistedGuestState {
+        if let Some(persisted_guest_state) = self.find_persisted_guest_state(provider_id) {
+            return persisted_guest_state;
+        }
+
+        let persisted_guest_state = PersistedGuestState {
+            id: self.next_id(),
+            display_name: display_name.to_string(),
+            is_observer: false,
+            is_tester: false,
+            last_time_joined: None,
+            times_joined: 0,
+            is_discord_admin: false,
+            is_discord_booster: false,
+            slime_skin_name: String::new(),
+        };
+        self.persisted_guest_states
+            .push(persisted_guest_state.clone());
+        self.add_guest_identity(persisted_guest_state.id, provider_id);
+        persisted_guest_state
+    }
+
+    fn merge_persisted_guest_states(&mut self, from_id: i32, into_id: i32) {
+        let secret_names: HashSet<String> = self
+            .found_secrets
+            .iter()
+            .filter(|secret| secret.persisted_guest_state_id == into_id)
+            .map(|secret| secret.name.clone())
+            .collect();
+        for secret in &mut self.found_secrets {
+            if secret.persisted_guest_state_id == from_id && !secret_names.contains(&secret.name) {
+                secret.persisted_guest_state_id = into_id;
+            }
+        }
+
+        let achievement_ids: HashSet<(ModuleId, AchievementId)> = self
+            .unlocked_achievements
+            .iter()
+            .filter(|achievement| achievement.persisted_guest_state_id == into_id)
+            .map(|achievement| {
+                (
+                    achievement.module_id.clone(),
+                    achievement.achievement_id.clone(),
+                )
+            })
+            .collect();
+        for achievement in &mut self.unlocked_achievements {
+            if achievement.persisted_guest_state_id == from_id
+                && !achievement_ids.contains(&(
+                    achievement.module_id.clone(),
+                    achievement.achievement_id.clone(),
+                ))
+            {
+                achievement.persisted_guest_state_id = into_id;
+            }
+        }
+
+        let newer_module_data: HashMap<(ModuleId, String), NaiveDateTime> = self
+            .guest_module_data
+            .iter()
+            .filter(|entry| entry.persisted_guest_state_id == from_id)
+            .map(|entry| {
+                (
+                    (entry.module_id.clone(), entry.key.clone()),
+                    entry.updated_at,
+                )
+            })
+            .collect();
+        self.guest_module_data.retain(|entry| {
+            entry.persisted_guest_state_id != into_id
+                || newer_module_data
+                    .get(&(entry.module_id.clone(), entry.key.clone()))
+                    .is_none_or(|updated_at| *updated_at <= entry.updated_at)
+        });
+        let module_data_keys: HashSet<(ModuleId, String)> = self
+            .guest_module_data
+            .iter()
+            .filter(|entry| entry.persisted_guest_state_id == into_id)
+            .map(|entry| (entry.module_id.clone(), entry.key.clone()))
+            .collect();
+        for entry in &mut self.guest_module_data {
+            if entry.persisted_guest_state_id == from_id
+                && !module_data_keys.contains(&(entry.module_id.clone(), entry.key.clone()))
+            {
+                entry.persisted_guest_state_id = into_id;
+            }
+        }
+
+        let leaderboard_keys: HashSet<(ModuleId, LeaderboardId, NaiveDateTime)> = self
+            .leaderboard_entries
+            .iter()
+            .filter(|row| row.persisted_guest_state_id == into_id)
+            .map(|row| {
+                (
+                    row.module_id.clone(),
+                    row.leaderboard_id.clone(),
+                    row.period_start,
+                )
+            })
+            .collect();
+        for row in &mut self.leaderboard_entries {
+            if row.persisted_guest_state_id == from_id
+                && !leaderboard_keys.contains(&(
+                    row.module_id.clone(),
+                    row.leaderboard_id.clone(),
+                    row.period_start,
+                ))
+            {
+                row.persisted_guest_state_id = into_id;
+            }
+        }
+
+        for identity in &mut self.guest_identities {
+            if identity.persisted_guest_state_id == from_id {
+                identity.persisted_guest_state_id = into_id;
+            }
+        }
+
+        // What could not be moved over is dropped, like the cascade on the SQL backends.
+        self.persisted_guest_states
+            .retain(|persisted_guest_state| persisted_guest_state.id != from_id);
+        self.found_secrets
+            .retain(|secret| secret.persisted_guest_state_id != from_id);
+        self.unlocked_achievements
+            .retain(|achievement| achievement.persisted_guest_state_id != from_id);
+        self.guest_module_data
+            .retain(|entry| entry.persisted_guest_state_id != from_id);
+        self.leaderboard_entries
+            .retain(|row| row.persisted_guest_state_id != from_id);
+    }
+
+    fn persisted_guest(&self, info: PersistedGuestState) -> PersistedGuest {
+        let mut module_data: HashMap<ModuleId, HashMap<String, _>> = HashMap::new();
+        for entry in self
+            .guest_module_data
+            .iter()
+            .filter(|entry| entry.persisted_guest_state_id == info.id)
+        {
+            module_data
+                .entry(entry.module_id.clone())
+                .or_default()
+                .insert(entry.key.clone(), entry.value.clone());
+        }
+
+        PersistedGuest {
+            secrets_found: self
+                .found_secrets
+                .iter()
+                .filter(|secret| secret.persisted_guest_state_id == info.id)
+                .cloned()
+                .collect(),
+            module_data,
+            achievements_unlocked: self
+                .unlocked_achievements
+                .iter()
+                .filter(|achievement| achievement.persisted_guest_state_id == info.id)
+                .cloned()
+                .collect(),
+            info,
+        }
+    }
+
+    fn guest_exists(&self, persisted_guest_state_id: i32) -> Result<(), PersistenceError> {
+        if self
+            .persisted_guest_states
+            .iter()
+            .any(|state| state.id == persisted_guest_state_id)
+        {
+            return Ok(());
+        }
+        Err(Self::database_error(
+            DatabaseErrorKind::ForeignKeyViolation,
+            format!(
+                "persisted guest state {} does not exist",
+                persisted_guest_state_id
+            ),
+        ))
+    }
+
+    fn database_error(kind: DatabaseErrorKind, message: String) -> PersistenceError {
+        PersistenceError::DieselResultError(DieselResultError::DatabaseError(
+            kind,
+            Box::new(message),
+        ))
+    }
+}
+
+/// Keeps everything in memory, meant for tests and throwaway local sessions.
+#[derive(Default)]
+pub struct MemoryPersistenceBackend {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryPersistenceBackend {
+    pub fn new() -> MemoryPersistenceBackend {
+        MemoryPersistenceBackend::default()
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, MemoryState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PersistenceBackend for MemoryPersistenceBackend {
+    fn lazy_get_persisted_guest_by_provider_id(
+        &self,
+        provider_id: &str,
+        display_name: &str,
+    ) -> Result<PersistedGuest, PersistenceError> {
+        let mut state = self.lock_state();
+
+        let info = state.lazy_get_persisted_guest_state(provider_id, display_name);
+
+        Ok(state.persisted_guest(info))
+    }
+
+    fn link_provider_identity(
+        &self,
+        anonymous_provider_id: &str,
+        provider_id: &str,
+        display_name: &str,
+    ) -> Result<PersistedGuest, PersistenceError> {
+        let mut state = self.lock_state();
+
+        let info = match (
+            state.find_persisted_guest_state(anonymous_provider_id),
+            state.find_persisted_guest_state(provider_id),
+        ) {
+            (Some(mut anonymous_state), None) => {
+                state.add_guest_identity(anonymous_state.id, provider_id);
+                anonymous_state.display_name = display_name.to_string();
+                if let Some(persisted_guest_state) = state
+                    .persisted_guest_states
+                    .iter_mut()
+                    .find(|persisted_guest_state| persisted_guest_state.id == anonymous_state.id)
+                {
+                    persisted_guest_state.display_name = display_name.to_string();
+                }
+                anonymous_state
+            }
+            (Some(anonymous_state), Some(provider_state))
+                if anonymous_state.id != provider_state.id =>
+            {
+                state.merge_persisted_guest_states(anonymous_state.id, provider_state.id);
+                provider_state
+            }
+            _ => state.lazy_get_persisted_guest_state(provider_id, display_name),
+        };
+
+        Ok(state.persisted_guest(info))
+    }
+
+    fn get_provider_ids_of_guest(
+        &self,
+        provider_id: &str,
+    ) -> Result<Vec<String>, PersistenceError> {
+        let state = self.lock_state();
+
+        let Some(persisted_guest_state) = state.find_persisted_guest_state(provider_id) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(state
+            .guest_identities
+            .iter()
+            .filter(|identity| identity.persisted_guest_state_id == persisted_guest_state.id)
+            .map(|identity| identity.provider_user_id.clone())
+            .collect())
+    }
+
+    fn update_persisted_guest_state(
+        &self,
+        update_persisted_guest_state: UpdatePersistedGuestState,
+    ) -> Result<usize, PersistenceError> {
+        let mut state = self.lock_state();
+
+        let Some(persisted_guest_state) =
+            state
+                .persisted_guest_states
+                .iter_mut()
+                .find(|persisted_guest_state| {
+                    persisted_guest_state.id == update_persisted_guest_state.id
+                })
+        else {
+            return Ok(0);
+        };
+
+        if let Some(is_observer) = update_persisted_guest_state.is_observer {
+            persisted_guest_state.is_observer = is_observer;
+        }
+        if let Some(is_tester) = update_persisted_guest_state.is_tester {
+            persisted_guest_state.is_tester = is_tester;
+        }
+        if let Some(last_time_joined) = update_persisted_guest_state.last_time_joined {
+            persisted_guest_state.last_time_joined = Some(last_time_joined);
+        }
+        if let Some(times_joined) = update_persisted_guest_state.times_joined {
+            persisted_guest_state.times_joined = times_joined;
+        }
+
+        Ok(1)
+    }
+
+    fn add_secret_found(
+        &self,
+        name: String,
+        persisted_guest_state_id: i32,
+    ) -> Result<FoundSecret, PersistenceError> {
+        let mut state = self.lock_state();
+        state.guest_exists(persisted_guest_state_id)?;
+
+        if state.found_secrets.iter().any(|secret| {
+            secret.persisted_guest_state_id == persisted_guest_state_id && secret.name == name
+        }) {
+            return Err(MemoryState::database_error(
+                DatabaseErrorKind::UniqueViolation,
+                "secret_only_found_once_per_guest".into(),
+            ));
+        }
+
+        let found_secret = FoundSecret {
+            id: state.next_id(),
+            persisted_guest_state_id,
+            name,
+            date: Utc::now().naive_utc(),
+        };
+        state.found_secrets.push(found_secret.clone());
+
+        Ok(found_secret)
+    }
+
+    fn write_guest_module_data(
+        &self,
+        writes: &[NewGuestModuleData],
+    ) -> Result<usize, PersistenceError> {
+        let mut state = self.lock_state();
+        for write in writes {
+            state.guest_exists(write.persisted_guest_state_id)?;
+        }
+
+        for write in writes {
+            match state.guest_module_data.iter_mut().find(|entry| {
+                entry.persisted_guest_state_id == write.persisted_guest_state_id
+                    && entry.module_id == write.module_id
+                    && entry.key == write.key
+            }) {
+                Some(entry) => {
+                    entry.value = write.value.clone();
+                    entry.updated_at = write.updated_at;
+                }
+                None => {
+                    let id = state.next_id();
+                    state.guest_module_data.push(GuestModuleData {
+                        id,
+                        persisted_guest_state_id: write.persisted_guest_state_id,
+                        module_id: write.module_id.clone(),
+                        key: write.key.clone(),
+                        value: write.value.clone(),
+                        updated_at: write.updated_at,
+                    });
+                }
+            }
+        }
+
+        Ok(writes.len())
+    }
+
+    fn add_achievement_unlocked(
+        &self,
+        module_id: ModuleId,
+        achievement_id: AchievementId,
+        persisted_guest_state_id: i32,
+    ) -> Result<UnlockedAchievement, PersistenceError> {
+        let mut state = self.lock_state();
+        state.guest_exists(persisted_guest_state_id)?;
+
+        if state.unlocked_achievements.iter().any(|achievement| {
+            achievement.persisted_guest_state_id == persisted_guest_state_id
+                && achievement.module_id == module_id
+                && achievement.achievement_id == achievement_id
+        }) {
+            return Err(MemoryState::database_error(
+                DatabaseErrorKind::UniqueViolation,
+                "achievement_only_unlocked_once_per_guest".into(),
+            ));
+        }
+
+        let unlocked_achievement = UnlockedAchievement {
+            id: state.next_id(),
+            persisted_guest_state_id,
+            module_id,
+            achievement_id,
+            unlocked_at: Utc::now().naive_utc(),
+        };
+        state
+            .unlocked_achievements
+            .push(unlocked_achievement.clone());
+
+        Ok(unlocked_achievement)
+    }
+
+    fn get_leaderboard_entries(
+        &self,
+        module_id: &ModuleId,
+        leaderboard_id: &LeaderboardId,
+        period_start: NaiveDateTime,
+    ) -> Result<Vec<LeaderboardEntry>, PersistenceError> {
+        let state = self.lock_state();
+
+        Ok(state
+            .leaderboard_entries
+            .iter()
+            .filter(|row| {
+                &row.module_id == module_id
+                    && &row.leaderboard_id == leaderboard_id
+                    && row.period_start == period_start
+            })
+            .filter_map(|row| {
+                state
+                    .persisted_guest_states
+                    .iter()
+                    .find(|persisted_guest_state| {
+                        persisted_guest_state.id == row.persisted_guest_state_id
+                    })
+                    .map(|persisted_guest_state| LeaderboardEntry {
+                        persisted_guest_state_id: row.persisted_guest_state_id,
+                        display_name: persisted_guest_state.display_name.clone(),
+                        score: row.score,
+                        updated_at: row.updated_at.timestamp(),
+                    })
+            })
+            .collect())
+    }
+
+    fn upsert_leaderboard_entry(
+        &self,
+        module_id: ModuleId,
+        leaderboard_id: LeaderboardId,
+        period_start: NaiveDateTime,
+        entry: &LeaderboardEntry,
+    ) -> Result<(), PersistenceError> {
+        let mut state = self.lock_state();
+        state.guest_exists(entry.persisted_guest_state_id)?;
+
+        let updated_at = Utc::now().naive_utc();
+        match state.leaderboard_entries.iter_mut().find(|row| {
+            row.persisted_guest_state_id == entry.persisted_guest_state_id
+                && row.module_id == module_id
+                && row.leaderboard_id == leaderboard_id
+                && row.period_start == period_start
+        }) {
+            Some(row) => {
+                row.score = entry.score;
+                row.updated_at = updated_at;
+            }
+            None => {
+                let id = state.next_id();
+                state.leaderboard_entries.push(LeaderboardEntryRow {
+                    id,
+                    persisted_guest_state_id: entry.persisted_guest_state_id,
+                    module_id,
+                    leaderboard_id,
+                    period_start,
+                    score: entry.score,
+                    updated_at,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_admin_role_grants(&self) -> Result<Vec<AdminRoleGrant>, PersistenceError> {
+        Ok(self.lock_state().admin_role_grants.clone())
+    }
+
+    fn add_admin_role_grant(&self, grant: &AdminRoleGrant) -> Result<(), PersistenceError> {
+        self.lock_state().admin_role_grants.push(grant.clone());
+
+        Ok(())
+    }
+
+    fn remove_admin_role_grant(&self, grant: &AdminRoleGrant) -> Result<usize, PersistenceError> {
+        let mut state = self.lock_state();
+        let grants_before = state.admin_role_grants.len();
+        state
+            .admin_role_grants
+            .retain(|existing_grant| existing_grant != grant);
+
+        Ok(grants_before - state.admin_role_grants.len())
+    }
+
+    fn append_audit_log_entry(
+        &self,
+        entry: &NewAuditLogEntry,
+    ) -> Result<AuditLogEntry, PersistenceError> {
+        let mut state = self.lock_state();
+        let audit_log_entry = AuditLogEntry {
+            id: state.next_id(),
+            provider_user_id: entry.provider_user_id.clone(),
+            action: entry.action.clone(),
+            module_ids: entry.module_ids.clone(),
+            resource: entry.resource.clone(),
+            diff: serde_json::to_string(&entry.diff)?,
+            created_at: Utc::now().timestamp(),
+        };
+        state.audit_log_entries.push(audit_log_entry.clone());
+
+        Ok(audit_log_entry)
+    }
+
+    fn get_audit_log_entries(
+        &self,
+        query: &AuditLogQuery,
+    ) -> Result<Vec<AuditLogEntry>, PersistenceError> {
+        Ok(self
+            .lock_state()
+            .audit_log_entries
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit() as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn ping(&self) -> Result<(), PersistenceError> {
+        Ok(())
+    }
+
+    fn get_guest_bans(&self) -> Result<Vec<GuestBan>, PersistenceError> {
+        Ok(self.lock_state().guest_bans.clone())
+    }
+
+    fn add_guest_ban(&self, ban: &GuestBan) -> Result<(), PersistenceError> {
+        let mut state = self.lock_state();
+        state
+            .guest_bans
+            .retain(|existing_ban| existing_ban.provider_user_id != ban.provider_user_id);
+        state.guest_bans.push(ban.clone());
+
+        Ok(())
+    }
+
+    fn remove_guest_ban(&self, provider_user_id: &str) -> Result<usize, PersistenceError> {
+        let mut state = self.lock_state();
+        let bans_before = state.guest_bans.len();
+        state
+            .guest_bans
+            .retain(|ban| ban.provider_user_id != provider_user_id);
+
+        Ok(bans_before - state.guest_bans.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistence_module::backend::tests::check_backend;
+
+    use super::*;
+
+    #[test]
+    fn test_memory_backend() {
+        check_backend(&MemoryPersistenceBackend::new());
+    }
+}
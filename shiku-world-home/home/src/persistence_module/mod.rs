@@ -1,29 +1,36 @@
 use std::env;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
-use chrono::Utc;
-use diesel::pg::PgConnection;
-use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
-use diesel::result::Error as DieselResultError;
+use chrono::{NaiveDateTime, Utc};
+use diesel::result::{DatabaseErrorKind, Error as DieselResultError};
+use flume::{unbounded, Drain, Receiver, Sender};
+use serde_json::Value as JsonValue;
 use thiserror::Error;
 
-use schema::found_secrets;
-use schema::persisted_guest_states;
-
-use crate::persistence_module::models::{
-    FoundSecret, NewFoundSecret, NewPersistedGuestState, PersistedGuest, PersistedGuestState,
-    UpdatePersistedGuestState,
-};
+use crate::core::blueprint::def::{LeaderboardId, ModuleId};
+use crate::core::guest::{AdminRoleGrant, GuestBan};
+use crate::core::leaderboard::LeaderboardEntry;
+use crate::persistence_module::backend::PersistenceBackend;
+use crate::persistence_module::diesel_backend::PgPersistenceBackend;
+use crate::persistence_module::memory_backend::MemoryPersistenceBackend;
+use crate::persistence_module::models::NewGuestModuleData;
+use crate::persistence_module::worker::{PersistenceJob, PersistenceResult, PersistenceWorker};
 use crate::SystemModule;
 
+pub mod backend;
+pub mod diesel_backend;
+pub mod memory_backend;
 pub mod models;
 pub mod schema;
+pub mod worker;
 
 #[derive(Error, Debug)]
 pub enum PersistenceError {
     DieselResultError(#[from] DieselResultError),
     R2D2Error(String),
+    SerdeJsonError(#[from] serde_json::Error),
+    WriteQueueClosed,
 }
 
 impl Display for PersistenceError {
@@ -35,132 +42,131 @@ impl Display for PersistenceError {
             PersistenceError::R2D2Error(err) => {
                 write!(f, "R2D2Error {:?}", err)
             }
+            PersistenceError::SerdeJsonError(err) => {
+                write!(f, "SerdeJsonError {:?}", err)
+            }
+            PersistenceError::WriteQueueClosed => {
+                write!(f, "WriteQueueClosed")
+            }
+        }
+    }
+}
+
+impl PersistenceError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            PersistenceError::DieselResultError(DieselResultError::DatabaseError(kind, _)) => {
+                matches!(
+                    kind,
+                    DatabaseErrorKind::UnableToSendCommand
+                        | DatabaseErrorKind::SerializationFailure
+                        | DatabaseErrorKind::ClosedConnection
+                )
+            }
+            PersistenceError::R2D2Error(_) => true,
+            _ => false,
         }
     }
 }
 
 pub struct PersistenceModule {
-    connection_pool: Pool<ConnectionManager<PgConnection>>,
+    backend: Arc<dyn PersistenceBackend>,
+    job_sender: Sender<PersistenceJob>,
+    result_receiver: Receiver<PersistenceResult>,
 }
 
 impl PersistenceModule {
     pub fn new() -> PersistenceModule {
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        Self::with_backend(Self::backend_from_env())
+    }
+
+    pub fn with_backend(backend: Arc<dyn PersistenceBackend>) -> PersistenceModule {
+        let (job_sender, job_receiver) = unbounded();
+        let (result_sender, result_receiver) = unbounded();
+        PersistenceWorker::spawn(backend.clone(), job_receiver, result_sender);
 
         PersistenceModule {
-            connection_pool: Pool::builder()
-                .max_size(1)
-                .build(manager)
-                .expect("Could not establish connection pool"),
+            backend,
+            job_sender,
+            result_receiver,
         }
     }
 
-    fn get_connection_from_pool(
-        connection_pool: &Pool<ConnectionManager<PgConnection>>,
-    ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, PersistenceError> {
-        match connection_pool.get() {
-            Ok(connection) => Ok(connection),
-            Err(err) => Err(PersistenceError::R2D2Error(err.to_string())),
+    fn backend_from_env() -> Arc<dyn PersistenceBackend> {
+        let default_backend = if cfg!(feature = "sqlite") {
+            "sqlite"
+        } else {
+            "postgres"
+        };
+        match env::var("PERSISTENCE_BACKEND")
+            .unwrap_or_else(|_| default_backend.into())
+            .as_str()
+        {
+            "postgres" => Arc::new(PgPersistenceBackend::new(
+                env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+            )),
+            "memory" => Arc::new(MemoryPersistenceBackend::new()),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Arc::new(diesel_backend::SqlitePersistenceBackend::new(
+                env::var("DATABASE_URL").unwrap_or_else(|_| "shiku.sqlite".into()),
+            )),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => {
+                panic!("PERSISTENCE_BACKEND=sqlite requires building with --features sqlite")
+            }
+            other => panic!(
+                "Unknown PERSISTENCE_BACKEND {}, expected postgres, sqlite or memory",
+                other
+            ),
         }
     }
 
-    pub fn lazy_get_persisted_guest_by_provider_id(
-        &self,
-        provider_id: &String,
-        display_name: &String,
-    ) -> Result<PersistedGuest, PersistenceError> {
-        let mut connection = Self::get_connection_from_pool(&self.connection_pool)?;
-
-        let persisted_guest_state = Self::lazy_get_persisted_guest_state_by_provider_id(
-            &mut connection,
-            provider_id,
-            display_name,
-        )?;
-
-        let secrets =
-            FoundSecret::belonging_to(&persisted_guest_state).get_results(&mut connection)?;
-
-        Ok(PersistedGuest {
-            info: persisted_guest_state,
-            secrets_found: secrets,
-        })
+    pub fn backend(&self) -> Arc<dyn PersistenceBackend> {
+        self.backend.clone()
     }
 
-    pub fn update_persisted_guest_state(
-        &self,
-        update_persisted_guest_state: UpdatePersistedGuestState,
-    ) -> Result<usize, PersistenceError> {
-        let mut connection = Self::get_connection_from_pool(&self.connection_pool)?;
-
-        let target = persisted_guest_states::dsl::persisted_guest_states
-            .filter(persisted_guest_states::dsl::id.eq(update_persisted_guest_state.id));
-
-        Ok(diesel::update(target)
-            .set(&update_persisted_guest_state)
-            .execute(&mut connection)?)
+    pub fn queue(&self, job: PersistenceJob) -> Result<(), PersistenceError> {
+        self.job_sender
+            .send(job)
+            .map_err(|_| PersistenceError::WriteQueueClosed)
     }
 
-    pub fn lazy_get_persisted_guest_state_by_provider_id(
-        connection: &mut PooledConnection<ConnectionManager<PgConnection>>,
-        provider_id: &String,
-        display_name: &String,
-    ) -> Result<PersistedGuestState, PersistenceError> {
-        let result = persisted_guest_states::dsl::persisted_guest_states
-            .filter(persisted_guest_states::dsl::twitch_id.eq(provider_id))
-            .first::<PersistedGuestState>(connection);
-
-        match result {
-            Ok(persisted_guest_state) => Ok(persisted_guest_state),
-            Err(DieselResultError::NotFound) => match Self::create_guest_persisted_state(
-                connection,
-                display_name.clone(),
-                provider_id.clone(),
-                false,
-                false,
-            ) {
-                Ok(persisted_guest_state) => Ok(persisted_guest_state),
-                Err(err) => Err(PersistenceError::DieselResultError(err)),
-            },
-            Err(err) => Err(PersistenceError::DieselResultError(err)),
-        }
+    pub fn queue_guest_module_data(
+        &self,
+        persisted_guest_state_id: i32,
+        module_id: ModuleId,
+        key: String,
+        value: JsonValue,
+    ) -> Result<(), PersistenceError> {
+        self.queue(PersistenceJob::WriteModuleData(vec![NewGuestModuleData {
+            persisted_guest_state_id,
+            module_id,
+            key,
+            value,
+            updated_at: Utc::now().naive_utc(),
+        }]))
     }
 
-    pub fn create_guest_persisted_state(
-        conn: &mut PgConnection,
-        display_name: String,
-        twitch_id: String,
-        is_observer: bool,
-        is_tester: bool,
-    ) -> Result<PersistedGuestState, DieselResultError> {
-        let new_persisted_guest_state = NewPersistedGuestState {
-            display_name,
-            twitch_id,
-            is_observer,
-            is_tester,
-        };
-
-        diesel::insert_into(persisted_guest_states::table)
-            .values(&new_persisted_guest_state)
-            .get_result(conn)
+    pub fn drain_results(&self) -> Drain<'_, PersistenceResult> {
+        self.result_receiver.drain()
     }
 
-    pub fn add_secret_found(
+    pub fn get_leaderboard_entries(
         &self,
-        name: String,
-        persisted_guest_state_id: i32,
-    ) -> Result<FoundSecret, PersistenceError> {
-        let mut conn = Self::get_connection_from_pool(&self.connection_pool)?;
+        module_id: &ModuleId,
+        leaderboard_id: &LeaderboardId,
+        period_start: NaiveDateTime,
+    ) -> Result<Vec<LeaderboardEntry>, PersistenceError> {
+        self.backend
+            .get_leaderboard_entries(module_id, leaderboard_id, period_start)
+    }
 
-        let new_secret_found = NewFoundSecret {
-            name,
-            date: Utc::now().naive_utc(),
-            persisted_guest_state_id,
-        };
+    pub fn get_admin_role_grants(&self) -> Result<Vec<AdminRoleGrant>, PersistenceError> {
+        self.backend.get_admin_role_grants()
+    }
 
-        Ok(diesel::insert_into(found_secrets::table)
-            .values(&new_secret_found)
-            .get_result(&mut conn)?)
+    pub fn get_guest_bans(&self) -> Result<Vec<GuestBan>, PersistenceError> {
+        self.backend.get_guest_bans()
     }
 }
 
@@ -1,11 +1,19 @@
-use super::schema::{found_secrets, persisted_guest_states};
+use super::schema::{
+    admin_role_grants, audit_log_entries, found_secrets, guest_bans, guest_identities,
+    guest_module_data, leaderboard_entries, persisted_guest_states, unlocked_achievements,
+};
 use chrono::NaiveDateTime;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 
-#[derive(Identifiable, Queryable, PartialEq, Debug)]
+use crate::core::audit_log::{split_module_ids, AuditLogEntry};
+use crate::core::blueprint::def::ModuleId;
+use crate::core::guest::GuestBan;
+
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
 #[table_name = "persisted_guest_states"]
 pub struct PersistedGuestState {
     pub id: i32,
-    pub twitch_id: String,
     pub display_name: String,
     pub is_observer: bool,
     pub is_tester: bool,
@@ -20,9 +28,11 @@ pub struct PersistedGuestState {
 pub struct PersistedGuest {
     pub info: PersistedGuestState,
     pub secrets_found: Vec<FoundSecret>,
+    pub module_data: HashMap<ModuleId, HashMap<String, JsonValue>>,
+    pub achievements_unlocked: Vec<UnlockedAchievement>,
 }
 
-#[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
 #[belongs_to(PersistedGuestState)]
 #[table_name = "found_secrets"]
 pub struct FoundSecret {
@@ -35,13 +45,12 @@ pub struct FoundSecret {
 #[derive(Insertable)]
 #[table_name = "persisted_guest_states"]
 pub struct NewPersistedGuestState {
-    pub twitch_id: String,
     pub display_name: String,
     pub is_observer: bool,
     pub is_tester: bool,
 }
 
-#[derive(AsChangeset)]
+#[derive(AsChangeset, Debug, Clone)]
 #[table_name = "persisted_guest_states"]
 pub struct UpdatePersistedGuestState {
     pub id: i32,
@@ -58,3 +67,174 @@ pub struct NewFoundSecret {
     pub name: String,
     pub date: NaiveDateTime,
 }
+
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[belongs_to(PersistedGuestState)]
+#[table_name = "guest_identities"]
+pub struct GuestIdentity {
+    pub id: i32,
+    pub persisted_guest_state_id: i32,
+    pub provider_user_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "guest_identities"]
+pub struct NewGuestIdentity<'a> {
+    pub persisted_guest_state_id: i32,
+    pub provider_user_id: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[belongs_to(PersistedGuestState)]
+#[table_name = "guest_module_data"]
+pub struct GuestModuleData {
+    pub id: i32,
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub key: String,
+    pub value: JsonValue,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "guest_module_data"]
+pub struct NewGuestModuleData {
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub key: String,
+    pub value: JsonValue,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[belongs_to(PersistedGuestState)]
+#[table_name = "unlocked_achievements"]
+pub struct UnlockedAchievement {
+    pub id: i32,
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub achievement_id: String,
+    pub unlocked_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "unlocked_achievements"]
+pub struct NewUnlockedAchievement {
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub achievement_id: String,
+    pub unlocked_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[belongs_to(PersistedGuestState)]
+#[table_name = "leaderboard_entries"]
+pub struct LeaderboardEntryRow {
+    pub id: i32,
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub leaderboard_id: String,
+    pub period_start: NaiveDateTime,
+    pub score: f64,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "leaderboard_entries"]
+pub struct NewLeaderboardEntryRow {
+    pub persisted_guest_state_id: i32,
+    pub module_id: String,
+    pub leaderboard_id: String,
+    pub period_start: NaiveDateTime,
+    pub score: f64,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
+#[table_name = "admin_role_grants"]
+pub struct AdminRoleGrantRow {
+    pub id: i32,
+    pub provider_user_id: String,
+    pub role: String,
+    pub module_id: Option<String>,
+    pub granted_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "admin_role_grants"]
+pub struct NewAdminRoleGrantRow<'a> {
+    pub provider_user_id: &'a str,
+    pub role: &'a str,
+    pub module_id: Option<&'a str>,
+    pub granted_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
+#[table_name = "audit_log_entries"]
+pub struct AuditLogEntryRow {
+    pub id: i32,
+    pub provider_user_id: String,
+    pub action: String,
+    pub module_ids: String,
+    pub resource: Option<String>,
+    pub diff: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditLogEntryRow> for AuditLogEntry {
+    fn from(row: AuditLogEntryRow) -> Self {
+        AuditLogEntry {
+            id: row.id,
+            provider_user_id: row.provider_user_id,
+            action: row.action,
+            module_ids: split_module_ids(&row.module_ids),
+            resource: row.resource,
+            diff: row.diff,
+            created_at: row.created_at.timestamp(),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "audit_log_entries"]
+pub struct NewAuditLogEntryRow<'a> {
+    pub provider_user_id: &'a str,
+    pub action: &'a str,
+    pub module_ids: String,
+    pub resource: Option<&'a str>,
+    pub diff: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
+#[table_name = "guest_bans"]
+pub struct GuestBanRow {
+    pub id: i32,
+    pub provider_user_id: String,
+    pub reason: String,
+    pub banned_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<GuestBanRow> for GuestBan {
+    fn from(row: GuestBanRow) -> Self {
+        GuestBan {
+            provider_user_id: row.provider_user_id,
+            reason: row.reason,
+            banned_until: row
+                .banned_until
+                .map(|banned_until| banned_until.timestamp()),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "guest_bans"]
+pub struct NewGuestBanRow<'a> {
+    pub provider_user_id: &'a str,
+    pub reason: &'a str,
+    pub banned_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
@@ -1,5 +1,32 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    admin_role_grants (id) {
+        id -> Int4,
+        #[max_length = 255]
+        provider_user_id -> Varchar,
+        #[max_length = 32]
+        role -> Varchar,
+        #[max_length = 64]
+        module_id -> Nullable<Varchar>,
+        granted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    audit_log_entries (id) {
+        id -> Int4,
+        #[max_length = 255]
+        provider_user_id -> Varchar,
+        #[max_length = 64]
+        action -> Varchar,
+        module_ids -> Text,
+        resource -> Nullable<Text>,
+        diff -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     found_secrets (id) {
         id -> Int4,
@@ -10,11 +37,57 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    guest_bans (id) {
+        id -> Int4,
+        #[max_length = 255]
+        provider_user_id -> Varchar,
+        reason -> Text,
+        banned_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    guest_identities (id) {
+        id -> Int4,
+        persisted_guest_state_id -> Int4,
+        #[max_length = 255]
+        provider_user_id -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    guest_module_data (id) {
+        id -> Int4,
+        persisted_guest_state_id -> Int4,
+        #[max_length = 64]
+        module_id -> Varchar,
+        #[max_length = 64]
+        key -> Varchar,
+        value -> Jsonb,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    leaderboard_entries (id) {
+        id -> Int4,
+        persisted_guest_state_id -> Int4,
+        #[max_length = 64]
+        module_id -> Varchar,
+        #[max_length = 64]
+        leaderboard_id -> Varchar,
+        period_start -> Timestamp,
+        score -> Float8,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     persisted_guest_states (id) {
         id -> Int4,
-        #[max_length = 20]
-        twitch_id -> Varchar,
         #[max_length = 15]
         display_name -> Varchar,
         is_observer -> Bool,
@@ -28,9 +101,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    unlocked_achievements (id) {
+        id -> Int4,
+        persisted_guest_state_id -> Int4,
+        #[max_length = 64]
+        module_id -> Varchar,
+        #[max_length = 64]
+        achievement_id -> Varchar,
+        unlocked_at -> Timestamp,
+    }
+}
+
 diesel::joinable!(found_secrets -> persisted_guest_states (persisted_guest_state_id));
+diesel::joinable!(guest_identities -> persisted_guest_states (persisted_guest_state_id));
+diesel::joinable!(guest_module_data -> persisted_guest_states (persisted_guest_state_id));
+diesel::joinable!(leaderboard_entries -> persisted_guest_states (persisted_guest_state_id));
+diesel::joinable!(unlocked_achievements -> persisted_guest_states (persisted_guest_state_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    admin_role_grants,
+    audit_log_entries,
     found_secrets,
+    guest_bans,
+    guest_identities,
+    guest_module_data,
+    leaderboard_entries,
     persisted_guest_states,
+    unlocked_achievements,
 );
@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use flume::{Receiver, RecvTimeoutError, Sender};
+use log::{debug, error, warn};
+
+use crate::core::audit_log::{AuditLogEntry, AuditLogQuery, NewAuditLogEntry};
+use crate::core::blueprint::def::{Achievement, LeaderboardId, ModuleId};
+use crate::core::guest::{ActorId, AdminRoleGrant, GuestBan};
+use crate::core::leaderboard::LeaderboardEntry;
+use crate::core::metrics::SERVER_METRICS;
+use crate::login::anonymous_login::ANONYMOUS_PROVIDER_ID_PREFIX;
+use crate::persistence_module::backend::PersistenceBackend;
+use crate::persistence_module::models::{
+    FoundSecret, NewGuestModuleData, PersistedGuest, UnlockedAchievement, UpdatePersistedGuestState,
+};
+use crate::persistence_module::PersistenceError;
+
+const MODULE_DATA_BATCH_WINDOW: Duration = Duration::from_millis(250);
+const MODULE_DATA_MAX_BATCH_SIZE: usize = 256;
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum PersistenceJob {
+    LoadGuest {
+        guest_id: ActorId,
+        provider_id: String,
+        display_name: String,
+    },
+    LinkGuest {
+        guest_id: ActorId,
+        anonymous_provider_id: String,
+        provider_id: String,
+        display_name: String,
+    },
+    UpdateGuestState(UpdatePersistedGuestState),
+    AddSecretFound {
+        guest_id: ActorId,
+        persisted_guest_state_id: i32,
+        name: String,
+    },
+    WriteModuleData(Vec<NewGuestModuleData>),
+    UnlockAchievement {
+        guest_id: ActorId,
+        persisted_guest_state_id: i32,
+        module_id: ModuleId,
+        achievement: Achievement,
+    },
+    LoadLeaderboard {
+        module_id: ModuleId,
+        leaderboard_id: LeaderboardId,
+        period_start: NaiveDateTime,
+    },
+    UpsertLeaderboardEntry {
+        module_id: ModuleId,
+        leaderboard_id: LeaderboardId,
+        period_start: NaiveDateTime,
+        entry: LeaderboardEntry,
+    },
+    GrantAdminRole {
+        admin_id: ActorId,
+        grant: AdminRoleGrant,
+    },
+    RevokeAdminRole {
+        admin_id: ActorId,
+        grant: AdminRoleGrant,
+    },
+    BanGuest {
+        admin_id: ActorId,
+        ban: GuestBan,
+    },
+    UnbanGuest {
+        admin_id: ActorId,
+        provider_user_id: String,
+    },
+    AppendAuditLog(NewAuditLogEntry),
+    QueryAuditLog {
+        admin_id: ActorId,
+        query: AuditLogQuery,
+    },
+}
+
+#[derive(Debug)]
+pub enum PersistenceResult {
+    GuestLoaded(ActorId, PersistedGuest),
+    /// The anonymous guest was linked to an account since, its guest token is no good anymore.
+    AnonymousGuestLinked(ActorId),
+    SecretFound(ActorId, FoundSecret),
+    AchievementUnlocked(ActorId, Achievement, UnlockedAchievement),
+    LeaderboardLoaded(
+        ModuleId,
+        LeaderboardId,
+        NaiveDateTime,
+        Vec<LeaderboardEntry>,
+    ),
+    AuditLogLoaded(ActorId, Vec<AuditLogEntry>),
+    Failed(PersistenceJob, PersistenceError),
+}
+
+pub struct PersistenceWorker {
+    backend: Arc<dyn PersistenceBackend>,
+    result_sender: Sender<PersistenceResult>,
+    pending_module_data: HashMap<(i32, String, String), NewGuestModuleData>,
+    flush_module_data_at: Option<Instant>,
+}
+
+impl PersistenceWorker {
+    pub fn spawn(
+        backend: Arc<dyn PersistenceBackend>,
+        job_receiver: Receiver<PersistenceJob>,
+        result_sender: Sender<PersistenceResult>,
+    ) {
+        let mut worker = PersistenceWorker {
+            backend,
+            result_sender,
+            pending_module_data: HashMap::new(),
+            flush_module_data_at: None,
+        };
+        thread::spawn(move || {
+            worker.run(job_receiver);
+            debug!("Persistence worker unwound properly.");
+        });
+    }
+
+    fn run(&mut self, job_receiver: Receiver<PersistenceJob>) {
+        loop {
+            let job = match self.flush_module_data_at {
+                Some(deadline) => job_receiver.recv_deadline(deadline),
+                None => job_receiver
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match job {
+                Ok(PersistenceJob::WriteModuleData(writes)) => {
+                    for write in writes {
+                        self.pending_module_data.insert(
+                            (
+                                write.persisted_guest_state_id,
+                                write.module_id.clone(),
+                                write.key.clone(),
+                            ),
+                            write,
+                        );
+                    }
+                    self.flush_module_data_at
+                        .get_or_insert_with(|| Instant::now() + MODULE_DATA_BATCH_WINDOW);
+                    if self.pending_module_data.len() >= MODULE_DATA_MAX_BATCH_SIZE {
+                        self.flush_module_data();
+                    }
+                }
+                Ok(job) => {
+                    // A guest that comes back right away has to see what it just wrote.
+                    if let PersistenceJob::LoadGuest { .. } | PersistenceJob::LinkGuest { .. } = job
+                    {
+                        self.flush_module_data();
+                    }
+                    self.execute(job);
+                }
+                Err(RecvTimeoutError::Timeout) => self.flush_module_data(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush_module_data();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn flush_module_data(&mut self) {
+        self.flush_module_data_at = None;
+        if self.pending_module_data.is_empty() {
+            return;
+        }
+        let writes: Vec<NewGuestModuleData> = self
+            .pending_module_data
+            .drain()
+            .map(|(_, write)| write)
+            .collect();
+        match self.with_retries(|backend| backend.write_guest_module_data(&writes)) {
+            Ok(written) => debug!("Persisted {} guest module data entries.", written),
+            Err(err) => self.send_result(PersistenceResult::Failed(
+                PersistenceJob::WriteModuleData(writes),
+                err,
+            )),
+        }
+    }
+
+    fn execute(&self, job: PersistenceJob) {
+        let result = match &job {
+            PersistenceJob::LoadGuest {
+                guest_id,
+                provider_id,
+                display_name,
+            } => self
+                .with_retries(|backend| {
+                    if provider_id.starts_with(ANONYMOUS_PROVIDER_ID_PREFIX)
+                        && backend
+                            .get_provider_ids_of_guest(provider_id)?
+                            .iter()
+                            .any(|id| !id.starts_with(ANONYMOUS_PROVIDER_ID_PREFIX))
+                    {
+                        return Ok(None);
+                    }
+                    backend
+                        .lazy_get_persisted_guest_by_provider_id(provider_id, display_name)
+                        .map(Some)
+                })
+                .map(|persisted_guest| {
+                    Some(match persisted_guest {
+                        Some(persisted_guest) => {
+                            PersistenceResult::GuestLoaded(*guest_id, persisted_guest)
+                        }
+                        None => PersistenceResult::AnonymousGuestLinked(*guest_id),
+                    })
+                }),
+            PersistenceJob::LinkGuest {
+                guest_id,
+                anonymous_provider_id,
+                provider_id,
+                display_name,
+            } => self
+                .with_retries(|backend| {
+                    backend.link_provider_identity(anonymous_provider_id, provider_id, display_name)
+                })
+                .map(|persisted_guest| {
+                    Some(PersistenceResult::GuestLoaded(*guest_id, persisted_guest))
+                }),
+            PersistenceJob::UpdateGuestState(update_persisted_guest_state) => self
+                .with_retries(|backend| {
+                    backend.update_persisted_guest_state(update_persisted_guest_state.clone())
+                })
+                .map(|_| None),
+            PersistenceJob::AddSecretFound {
+                guest_id,
+                persisted_guest_state_id,
+                name,
+            } => self
+                .with_retries(|backend| {
+                    backend.add_secret_found(name.clone(), *persisted_guest_state_id)
+                })
+                .map(|secret| Some(PersistenceResult::SecretFound(*guest_id, secret))),
+            PersistenceJob::WriteModuleData(writes) => self
+                .with_retries(|backend| backend.write_guest_module_data(writes))
+                .map(|_| None),
+            PersistenceJob::UnlockAchievement {
+                guest_id,
+                persisted_guest_state_id,
+                module_id,
+                achievement,
+            } => self
+                .with_retries(|backend| {
+                    backend.add_achievement_unlocked(
+                        module_id.clone(),
+                        achievement.id.clone(),
+                        *persisted_guest_state_id,
+                    )
+                })
+                .map(|unlocked_achievement| {
+                    Some(PersistenceResult::AchievementUnlocked(
+                        *guest_id,
+                        achievement.clone(),
+                        unlocked_achievement,
+                    ))
+                }),
+            PersistenceJob::LoadLeaderboard {
+                module_id,
+                leaderboard_id,
+                period_start,
+            } => self
+                .with_retries(|backend| {
+                    backend.get_leaderboard_entries(module_id, leaderboard_id, *period_start)
+                })
+                .map(|entries| {
+                    Some(PersistenceResult::LeaderboardLoaded(
+                        module_id.clone(),
+                        leaderboard_id.clone(),
+                        *period_start,
+                        entries,
+                    ))
+                }),
+            PersistenceJob::UpsertLeaderboardEntry {
+                module_id,
+                leaderboard_id,
+                period_start,
+                entry,
+            } => self
+                .with_retries(|backend| {
+                    backend.upsert_leaderboard_entry(
+                        module_id.clone(),
+                        leaderboard_id.clone(),
+                        *period_start,
+                        entry,
+                    )
+                })
+                .map(|_| None),
+            PersistenceJob::GrantAdminRole { grant, .. } => self
+                .with_retries(|backend| backend.add_admin_role_grant(grant))
+                .map(|_| None),
+            PersistenceJob::RevokeAdminRole { grant, .. } => self
+                .with_retries(|backend| backend.remove_admin_role_grant(grant))
+                .map(|_| None),
+            PersistenceJob::BanGuest { ban, .. } => self
+                .with_retries(|backend| backend.add_guest_ban(ban))
+                .map(|_| None),
+            PersistenceJob::UnbanGuest {
+                provider_user_id, ..
+            } => self
+                .with_retries(|backend| backend.remove_guest_ban(provider_user_id))
+                .map(|_| None),
+            PersistenceJob::AppendAuditLog(entry) => self
+                .with_retries(|backend| backend.append_audit_log_entry(entry))
+                .map(|_| None),
+            PersistenceJob::QueryAuditLog { admin_id, query } => self
+                .with_retries(|backend| backend.get_audit_log_entries(query))
+                .map(|entries| Some(PersistenceResult::AuditLogLoaded(*admin_id, entries))),
+        };
+
+        match result {
+            Ok(Some(result)) => self.send_result(result),
+            Ok(None) => (),
+            Err(err) => self.send_result(PersistenceResult::Failed(job, err)),
+        }
+    }
+
+    fn with_retries<T, F: Fn(&dyn PersistenceBackend) -> Result<T, PersistenceError>>(
+        &self,
+        operation: F,
+    ) -> Result<T, PersistenceError> {
+        let started_at = Instant::now();
+        let mut attempt = 1;
+        let result = loop {
+            match operation(self.backend.as_ref()) {
+                Err(err) if err.is_transient() && attempt < MAX_ATTEMPTS => {
+                    warn!(
+                        "Persistence attempt {} of {} failed, retrying. {:?}",
+                        attempt, MAX_ATTEMPTS, err
+                    );
+                    thread::sleep(RETRY_DELAY * attempt);
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+        SERVER_METRICS.observe_persistence_latency(started_at.elapsed());
+        result
+    }
+
+    fn send_result(&self, result: PersistenceResult) {
+        if let Err(err) = self.result_sender.send(result) {
+            error!(
+                "Could not send persistence result, conductor is gone. {:?}",
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flume::unbounded;
+
+    use crate::persistence_module::memory_backend::MemoryPersistenceBackend;
+
+    use super::*;
+
+    #[test]
+    fn test_results_and_failures_come_back_through_the_channel() {
+        let (job_sender, job_receiver) = unbounded();
+        let (result_sender, result_receiver) = unbounded();
+        PersistenceWorker::spawn(
+            Arc::new(MemoryPersistenceBackend::new()),
+            job_receiver,
+            result_sender,
+        );
+
+        job_sender
+            .send(PersistenceJob::LoadGuest {
+                guest_id: 7,
+                provider_id: "1234".into(),
+                display_name: "Tester".into(),
+            })
+            .unwrap();
+        let persisted_guest_state_id = match result_receiver.recv().unwrap() {
+            PersistenceResult::GuestLoaded(7, persisted_guest) => persisted_guest.info.id,
+            other => panic!("Unexpected result {:?}", other),
+        };
+
+        for job in [
+            PersistenceJob::LoadGuest {
+                guest_id: 8,
+                provider_id: "anonymous:a".into(),
+                display_name: "Guest-a".into(),
+            },
+            PersistenceJob::LinkGuest {
+                guest_id: 8,
+                anonymous_provider_id: "anonymous:a".into(),
+                provider_id: "5678".into(),
+                display_name: "Linked".into(),
+            },
+            PersistenceJob::LoadGuest {
+                guest_id: 8,
+                provider_id: "anonymous:a".into(),
+                display_name: "Guest-a".into(),
+            },
+        ] {
+            job_sender.send(job).unwrap();
+        }
+        assert!(matches!(
+            result_receiver.recv().unwrap(),
+            PersistenceResult::GuestLoaded(8, _)
+        ));
+        assert!(matches!(
+            result_receiver.recv().unwrap(),
+            PersistenceResult::GuestLoaded(8, _)
+        ));
+        assert!(matches!(
+            result_receiver.recv().unwrap(),
+            PersistenceResult::AnonymousGuestLinked(8)
+        ));
+
+        for _ in 0..2 {
+            job_sender
+                .send(PersistenceJob::AddSecretFound {
+                    guest_id: 7,
+                    persisted_guest_state_id,
+                    name: "cave".into(),
+                })
+                .unwrap();
+        }
+        assert!(matches!(
+            result_receiver.recv().unwrap(),
+            PersistenceResult::SecretFound(7, _)
+        ));
+        assert!(matches!(
+            result_receiver.recv().unwrap(),
+            PersistenceResult::Failed(PersistenceJob::AddSecretFound { guest_id: 7, .. }, _)
+        ));
+    }
+}
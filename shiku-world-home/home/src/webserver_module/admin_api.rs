@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flume::Sender;
+use log::error;
+use serde::Deserialize;
+use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
+use warp::reply::Response;
+use warp::{Filter, Reply};
+
+use crate::core::blueprint::def::{GameMap, MapUpdate, ModuleId, ResourcePath, Tileset};
+use crate::core::blueprint::scene::def::{GameNodeKind, Scene, Script};
+use crate::core::guest::ProviderUserId;
+use crate::core::module::{AdminToSystemEvent, CommunicationEvent};
+use crate::core::module_system::game_instance::GameInstanceId;
+use crate::core::module_system::world::WorldId;
+
+const MAX_BODY_SIZE: u64 = 16 * 1024 * 1024;
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maps bearer tokens to the admin they act as, configured as
+/// `ADMIN_API_TOKENS=token:provider_user_id,other_token:other_provider_user_id`.
+#[derive(Debug, Default)]
+pub struct AdminApiTokens {
+    tokens: HashMap<String, ProviderUserId>,
+}
+
+impl AdminApiTokens {
+    pub fn from_env() -> AdminApiTokens {
+        AdminApiTokens::parse(&env::var("ADMIN_API_TOKENS").unwrap_or_default())
+    }
+
+    fn parse(tokens: &str) -> AdminApiTokens {
+        AdminApiTokens {
+            tokens: tokens
+                .split(',')
+                .filter_map(|entry| entry.trim().split_once(':'))
+                .filter(|(token, provider_user_id)| {
+                    !token.is_empty() && !provider_user_id.is_empty()
+                })
+                .map(|(token, provider_user_id)| (token.to_string(), provider_user_id.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Every configured token is compared in full, so the time taken does not give away how
+    /// much of a guessed token was right.
+    fn provider_user_id(&self, authorization: Option<String>) -> Option<ProviderUserId> {
+        let authorization = authorization?;
+        let token = authorization.strip_prefix("Bearer ")?.trim();
+        self.tokens
+            .iter()
+            .fold(None, |found, (configured_token, provider_user_id)| {
+                if constant_time_eq(configured_token.as_bytes(), token.as_bytes()) {
+                    Some(provider_user_id.clone())
+                } else {
+                    found
+                }
+            })
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[derive(Debug)]
+pub enum AdminApiResponse {
+    Handled(Vec<CommunicationEvent>),
+    NotAllowed(String),
+    Failed(String),
+}
+
+/// An admin event coming in over http, the conductor handles it like one from the admin
+/// websocket and answers with everything the handler sent back right away.
+#[derive(Debug)]
+pub struct AdminApiRequest {
+    pub provider_user_id: ProviderUserId,
+    pub event: AdminToSystemEvent,
+    pub responder: Sender<AdminApiResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResourceQuery {
+    pub path: ResourcePath,
+}
+
+/// Inspecting and controlling worlds streams updates, that only works over the websocket.
+fn requires_connection(event: &AdminToSystemEvent) -> bool {
+    matches!(
+        event,
+        AdminToSystemEvent::ProviderLoggedIn(_)
+            | AdminToSystemEvent::StartInspectingWorld(..)
+            | AdminToSystemEvent::StopInspectingWorld(..)
+            | AdminToSystemEvent::WorldInitialized(..)
+            | AdminToSystemEvent::ControlInput(..)
+            | AdminToSystemEvent::Ping
+    )
+}
+
+fn json_body<T: for<'de> Deserialize<'de> + Send>(
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(MAX_BODY_SIZE).and(warp::body::json())
+}
+
+fn admin_events() -> BoxedFilter<(AdminToSystemEvent,)> {
+    let event = warp::path!("events").and(warp::post()).and(json_body());
+
+    let modules = warp::path!("modules")
+        .and(warp::get())
+        .map(|| AdminToSystemEvent::LoadEditorData);
+
+    let resource = warp::path!("resources")
+        .and(warp::get())
+        .and(warp::query::<ResourceQuery>())
+        .map(|query: ResourceQuery| AdminToSystemEvent::GetResource(query.path));
+
+    let open_instance = warp::path!("modules" / ModuleId / "instances")
+        .and(warp::post())
+        .map(AdminToSystemEvent::OpenInstance);
+
+    let reset_world = warp::path!(
+        "modules" / ModuleId / "instances" / GameInstanceId / "worlds" / WorldId / "reset"
+    )
+    .and(warp::post())
+    .map(AdminToSystemEvent::ResetGameWorld);
+
+    let create_scene = warp::path!("modules" / ModuleId / "scenes")
+        .and(warp::post())
+        .and(json_body::<Scene>())
+        .map(AdminToSystemEvent::CreateScene);
+
+    let create_script = warp::path!("modules" / ModuleId / "scripts")
+        .and(warp::post())
+        .and(json_body::<Script>())
+        .map(AdminToSystemEvent::CreateScript);
+
+    let create_map = warp::path!("modules" / ModuleId / "maps")
+        .and(warp::post())
+        .and(json_body::<GameMap>())
+        .map(AdminToSystemEvent::CreateMap);
+
+    let create_tileset = warp::path!("modules" / ModuleId / "tilesets")
+        .and(warp::post())
+        .and(json_body::<Tileset>())
+        .map(AdminToSystemEvent::CreateTileset);
+
+    let overwrite_scene_root = warp::path!("scenes")
+        .and(warp::put())
+        .and(warp::query::<ResourceQuery>())
+        .and(json_body::<GameNodeKind>())
+        .map(|query: ResourceQuery, root_node| {
+            AdminToSystemEvent::OverwriteSceneRoot(query.path, root_node)
+        });
+
+    let update_script = warp::path!("scripts")
+        .and(warp::put())
+        .and(json_body::<Script>())
+        .map(AdminToSystemEvent::UpdateScript);
+
+    let update_map = warp::path!("maps")
+        .and(warp::put())
+        .and(json_body::<MapUpdate>())
+        .map(AdminToSystemEvent::UpdateMap);
+
+    let set_tileset = warp::path!("tilesets")
+        .and(warp::put())
+        .and(json_body::<Tileset>())
+        .map(AdminToSystemEvent::SetTileset);
+
+    event
+        .or(modules)
+        .unify()
+        .or(resource)
+        .unify()
+        .or(open_instance)
+        .unify()
+        .or(reset_world)
+        .unify()
+        .or(create_scene)
+        .unify()
+        .or(create_script)
+        .unify()
+        .or(create_map)
+        .unify()
+        .or(create_tileset)
+        .unify()
+        .or(overwrite_scene_root)
+        .unify()
+        .or(update_script)
+        .unify()
+        .or(update_map)
+        .unify()
+        .or(set_tileset)
+        .unify()
+        .boxed()
+}
+
+pub fn admin_api(
+    tokens: AdminApiTokens,
+    admin_api_sender: Sender<AdminApiRequest>,
+) -> BoxedFilter<(Response,)> {
+    let tokens = Arc::new(tokens);
+    warp::path("admin")
+        .and(warp::header::optional::<String>("authorization"))
+        .map(move |authorization| tokens.provider_user_id(authorization))
+        .and(admin_events())
+        .and_then(move |provider_user_id, event| {
+            dispatch(admin_api_sender.clone(), provider_user_id, event)
+        })
+        .boxed()
+}
+
+fn error_reply(message: &str, status: StatusCode) -> Response {
+    warp::reply::with_status(warp::reply::json(&message), status).into_response()
+}
+
+async fn dispatch(
+    admin_api_sender: Sender<AdminApiRequest>,
+    provider_user_id: Option<ProviderUserId>,
+    event: AdminToSystemEvent,
+) -> Result<Response, warp::Rejection> {
+    let Some(provider_user_id) = provider_user_id else {
+        return Ok(error_reply(
+            "Missing or unknown bearer token.",
+            StatusCode::UNAUTHORIZED,
+        ));
+    };
+    if requires_connection(&event) {
+        return Ok(error_reply(
+            "This event needs an admin websocket connection.",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let (responder, response_receiver) = flume::bounded(1);
+    if let Err(err) = admin_api_sender.send(AdminApiRequest {
+        provider_user_id,
+        event,
+        responder,
+    }) {
+        error!(
+            "Conductor does not take admin api requests anymore {:?}",
+            err
+        );
+        return Ok(error_reply(
+            "Server is shutting down.",
+            StatusCode::SERVICE_UNAVAILABLE,
+        ));
+    }
+
+    Ok(
+        match tokio::time::timeout(RESPONSE_TIMEOUT, response_receiver.recv_async()).await {
+            Ok(Ok(AdminApiResponse::Handled(events))) => warp::reply::json(&events).into_response(),
+            Ok(Ok(AdminApiResponse::NotAllowed(reason))) => {
+                error_reply(&reason, StatusCode::FORBIDDEN)
+            }
+            Ok(Ok(AdminApiResponse::Failed(reason))) => {
+                error_reply(&reason, StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            Ok(Err(_)) | Err(_) => error_reply(
+                "Conductor did not answer in time.",
+                StatusCode::GATEWAY_TIMEOUT,
+            ),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_resolve_to_the_configured_admin() {
+        let tokens = AdminApiTokens::parse("ci-secret:local:shiku, broken, :nobody");
+
+        assert_eq!(
+            tokens.provider_user_id(Some("Bearer ci-secret".into())),
+            Some("local:shiku".to_string())
+        );
+        assert_eq!(tokens.provider_user_id(Some("ci-secret".into())), None);
+        assert_eq!(
+            tokens.provider_user_id(Some("Bearer ci-secre".into())),
+            None
+        );
+        assert_eq!(tokens.provider_user_id(Some("Bearer ".into())), None);
+        assert_eq!(tokens.provider_user_id(None), None);
+        assert_eq!(tokens.tokens.len(), 1);
+    }
+}
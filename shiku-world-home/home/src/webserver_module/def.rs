@@ -1,19 +1,12 @@
-use std::sync::Arc;
-use tokio::sync::Mutex;
-
 use serde::Deserialize;
 
-pub struct WebServerModule {
-    pub door_statuses: Arc<Mutex<DoorStatuses>>,
-}
+use crate::core::server_gate::ServerGates;
 
-pub struct DoorStatuses {
-    pub main_door_status: bool,
-    pub back_door_status: bool,
+pub struct WebServerModule {
+    pub gates: ServerGates,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct DoorStatusUpdate {
-    pub password: String,
-    pub status: bool,
+pub struct LeaderboardQuery {
+    pub limit: Option<usize>,
 }
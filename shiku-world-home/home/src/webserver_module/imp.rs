@@ -2,11 +2,21 @@ use std::convert::Infallible;
 use std::env;
 use std::sync::Arc;
 
-use tokio::sync::Mutex;
+use chrono::Utc;
+use flume::Sender;
+use warp::http::StatusCode;
 use warp::Filter;
 
 use crate::core::get_out_dir;
-use crate::webserver_module::def::{DoorStatuses, WebServerModule};
+use crate::core::health::{HealthReport, SERVER_HEALTH};
+use crate::core::leaderboard::{
+    lock_leaderboards, LeaderboardMap, DEFAULT_LEADERBOARD_QUERY_SIZE, MAX_LEADERBOARD_QUERY_SIZE,
+};
+use crate::core::metrics::SERVER_METRICS;
+use crate::core::server_gate::{lock_gates, GateStatus, ServerGates};
+use crate::persistence_module::backend::PersistenceBackend;
+use crate::webserver_module::admin_api::{admin_api, AdminApiRequest, AdminApiTokens};
+use crate::webserver_module::def::{LeaderboardQuery, WebServerModule};
 use crate::SystemModule;
 
 impl SystemModule for WebServerModule {
@@ -16,7 +26,12 @@ impl SystemModule for WebServerModule {
 }
 
 impl WebServerModule {
-    pub fn new() -> WebServerModule {
+    pub fn new(
+        leaderboards: LeaderboardMap,
+        persistence_backend: Arc<dyn PersistenceBackend>,
+        admin_api_sender: Sender<AdminApiRequest>,
+        gates: ServerGates,
+    ) -> WebServerModule {
         let mut cors = warp::cors().allow_methods(vec!["GET", "POST", "DELETE"]);
 
         for cors_origin in env::var("RESOURCE_SERVER_CORS").unwrap().split('|') {
@@ -25,50 +40,153 @@ impl WebServerModule {
 
         let hello = warp::path("resources")
             .and(warp::fs::dir(get_out_dir().join("shared")))
-            .with(cors);
+            .with(cors.clone());
 
-        let door_statuses = Arc::new(Mutex::new(DoorStatuses {
-            main_door_status: false,
-            back_door_status: false,
-        }));
+        let leaderboard_get = warp::path!("leaderboards" / String / String)
+            .and(warp::get())
+            .and(warp::query::<LeaderboardQuery>())
+            .and_then(move |module_id, leaderboard_id, query| {
+                WebServerModule::return_leaderboard(
+                    leaderboards.clone(),
+                    module_id,
+                    leaderboard_id,
+                    query,
+                )
+            })
+            .with(cors.clone());
 
-        let main_door_status_get = {
-            let door_statuses = door_statuses.clone();
-            warp::path("main-door-status")
+        let gates_get = {
+            let gates = gates.clone();
+            warp::path!("gates")
                 .and(warp::get())
-                .and_then(move || WebServerModule::return_main_door_status(door_statuses.clone()))
+                .and_then(move || WebServerModule::return_gates(gates.clone()))
+                .with(cors.clone())
         };
 
-        let back_door_status_get = {
-            let door_statuses = door_statuses.clone();
-            warp::path("back-door-status")
+        let gate_get = {
+            let gates = gates.clone();
+            warp::path!("gates" / String)
                 .and(warp::get())
-                .and_then(move || WebServerModule::return_main_door_status(door_statuses.clone()))
+                .and_then(move |name| WebServerModule::return_gate(gates.clone(), name))
+                .with(cors)
         };
 
+        let healthz_get = warp::path("healthz")
+            .and(warp::get())
+            .and_then(WebServerModule::return_liveness);
+
+        let readyz_get = warp::path("readyz")
+            .and(warp::get())
+            .and_then(move || WebServerModule::return_readiness(persistence_backend.clone()));
+
+        let metrics_get = warp::path("metrics")
+            .and(warp::get())
+            .and_then(WebServerModule::return_metrics);
+
+        let admin_api = admin_api(AdminApiTokens::from_env(), admin_api_sender);
+
         tokio::spawn(async move {
-            warp::serve(hello.or(main_door_status_get).or(back_door_status_get))
-                .run(([0, 0, 0, 0], 3030))
-                .await;
+            warp::serve(
+                hello
+                    .or(gates_get)
+                    .or(gate_get)
+                    .or(leaderboard_get)
+                    .or(metrics_get)
+                    .or(healthz_get)
+                    .or(readyz_get)
+                    .or(admin_api),
+            )
+            .run(([0, 0, 0, 0], 3030))
+            .await;
         });
 
-        WebServerModule { door_statuses }
+        WebServerModule { gates }
     }
 
-    pub async fn set_main_door_status(&mut self, status: bool) {
-        let mut statuses = self.door_statuses.lock().await;
-        statuses.main_door_status = status;
+    pub async fn return_gates(gates: ServerGates) -> Result<impl warp::Reply, Infallible> {
+        let now = Utc::now().time();
+        let statuses: Vec<GateStatus> = lock_gates(&gates)
+            .iter()
+            .map(|gate| gate.status(now))
+            .collect();
+        Ok(warp::reply::json(&statuses))
     }
 
-    pub async fn set_back_door_status(&mut self, status: bool) {
-        let mut statuses = self.door_statuses.lock().await;
-        statuses.back_door_status = status;
+    pub async fn return_gate(
+        gates: ServerGates,
+        name: String,
+    ) -> Result<impl warp::Reply, Infallible> {
+        match lock_gates(&gates).iter().find(|gate| gate.name == name) {
+            Some(gate) => Ok(warp::reply::with_status(
+                warp::reply::json(&gate.status(Utc::now().time())),
+                StatusCode::OK,
+            )),
+            None => Ok(warp::reply::with_status(
+                warp::reply::json(&"Gate not found"),
+                StatusCode::NOT_FOUND,
+            )),
+        }
+    }
+
+    pub async fn return_liveness() -> Result<impl warp::Reply, Infallible> {
+        Ok(Self::reply_with_health_report(SERVER_HEALTH.liveness()))
     }
 
-    pub async fn return_main_door_status(
-        door_statuses: Arc<Mutex<DoorStatuses>>,
+    pub async fn return_readiness(
+        persistence_backend: Arc<dyn PersistenceBackend>,
     ) -> Result<impl warp::Reply, Infallible> {
-        let lock = door_statuses.lock().await;
-        Ok(warp::reply::html(format!("{}", lock.main_door_status)))
+        let persistence = tokio::task::spawn_blocking(move || persistence_backend.ping())
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|ping| ping.map_err(|err| err.to_string()));
+        Ok(Self::reply_with_health_report(
+            SERVER_HEALTH.readiness(persistence),
+        ))
+    }
+
+    fn reply_with_health_report(health_report: HealthReport) -> impl warp::Reply {
+        let status = if health_report.healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        warp::reply::with_status(warp::reply::json(&health_report), status)
+    }
+
+    pub async fn return_metrics() -> Result<impl warp::Reply, Infallible> {
+        Ok(warp::reply::with_header(
+            SERVER_METRICS.render(),
+            "content-type",
+            "text/plain; version=0.0.4",
+        ))
+    }
+
+    pub async fn return_leaderboard(
+        leaderboards: LeaderboardMap,
+        module_id: String,
+        leaderboard_id: String,
+        query: LeaderboardQuery,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_LEADERBOARD_QUERY_SIZE)
+            .min(MAX_LEADERBOARD_QUERY_SIZE);
+        let mut leaderboards = lock_leaderboards(&leaderboards);
+        match leaderboards
+            .get_mut(&module_id)
+            .and_then(|module_leaderboards| module_leaderboards.get_mut(&leaderboard_id))
+        {
+            Some(leaderboard) => {
+                leaderboard.roll_over(Utc::now().naive_utc());
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&leaderboard.snapshot(limit)),
+                    StatusCode::OK,
+                ))
+            }
+            None => Ok(warp::reply::with_status(
+                warp::reply::json(&"Leaderboard not found"),
+                StatusCode::NOT_FOUND,
+            )),
+        }
     }
 }
@@ -1,3 +1,4 @@
+pub mod admin_api;
 pub mod def;
 pub mod error;
 pub mod imp;
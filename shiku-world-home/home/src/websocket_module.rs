@@ -1,43 +1,268 @@
 use crate::core::module::{ModuleName, ModuleState, SystemModule};
-use flume::{unbounded, Receiver, RecvError, Sender};
+use flume::{bounded, unbounded, Receiver, RecvError, Sender, TrySendError};
 use futures::stream::{SplitSink, SplitStream};
-use futures::{future, SinkExt, StreamExt, TryStreamExt};
+use futures::{SinkExt, StreamExt};
 use log::debug;
 use log::error;
 use log::trace;
 use std::borrow::Cow;
+use std::env;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use snowflake::SnowflakeIdBucket;
 use std::collections::HashMap;
 use std::vec::Drain;
+use thiserror::Error;
 use ts_rs::TS;
 
-use crate::core::guest::SessionId;
+use crate::core::guest::SessionToken;
+use crate::core::health::SERVER_HEALTH;
 use crate::core::Snowflake;
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::WebSocketStream;
 use tungstenite::protocol::frame::coding::CloseCode;
-use tungstenite::protocol::CloseFrame;
+use tungstenite::protocol::{CloseFrame, WebSocketConfig};
 use tungstenite::{Error as WsError, Message};
 
 #[derive(TS, Debug, Serialize, Deserialize, Clone)]
 #[ts(export)]
 pub struct Ticket {
-    pub session_id: Option<SessionId>,
+    pub session_token: Option<SessionToken>,
     pub admin_login: Option<bool>,
+    pub protocol: Option<WireProtocol>,
+    pub resume: Option<bool>,
+    pub protocol_version: Option<u32>,
+}
+
+/// Bump whenever the ticket or any event changes shape. `test_bindings_match_protocol_version`
+/// fingerprints the binding files of every type the protocol reaches and fails until the version
+/// and fingerprint are updated.
+pub const PROTOCOL_VERSION: u32 = 3;
+pub const PROTOCOL_FEATURES: [&str; 6] = [
+    "message_pack",
+    "position_snapshots",
+    "interest_management",
+    "client_prediction",
+    "time_sync",
+    "session_resume",
+];
+
+/// Sent with `ConnectionReady`, so clients know what the server speaks.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[ts(export)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub features: Vec<String>,
+}
+
+impl ProtocolInfo {
+    pub fn current() -> ProtocolInfo {
+        ProtocolInfo {
+            version: PROTOCOL_VERSION,
+            features: PROTOCOL_FEATURES
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// How events are put on the wire after the ticket, which itself is always JSON.
+/// `MessagePack` uses binary frames and keeps the field names, so the decoded events have
+/// the same shape as the generated TS types.
+#[derive(TS, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[ts(export)]
+pub enum WireProtocol {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Ticket {
+    /// The editor always talks JSON.
+    pub fn wire_protocol(&self) -> WireProtocol {
+        if self.admin_login == Some(true) {
+            return WireProtocol::Json;
+        }
+        self.protocol.unwrap_or_default()
+    }
+
+    /// Close reason for clients that would not understand the server, `None` if compatible.
+    pub fn incompatibility(&self) -> Option<String> {
+        match self.protocol_version {
+            Some(PROTOCOL_VERSION) => None,
+            Some(version) => Some(format!(
+                "Client speaks protocol {}, server speaks {}. Please reload.",
+                version, PROTOCOL_VERSION
+            )),
+            None => Some(format!(
+                "Client is older than protocol {}. Please reload.",
+                PROTOCOL_VERSION
+            )),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WireError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+    #[error("Only text and binary frames carry events")]
+    UnsupportedFrame,
+}
+
+pub fn encode_message<T: Serialize>(
+    protocol: WireProtocol,
+    event: &T,
+) -> Result<Message, WireError> {
+    Ok(match protocol {
+        WireProtocol::Json => Message::Text(serde_json::to_string(event)?),
+        WireProtocol::MessagePack => Message::Binary(rmp_serde::to_vec_named(event)?),
+    })
+}
+
+/// Decodes by frame type, so a client may always fall back to JSON text frames.
+pub fn decode_message<T: DeserializeOwned>(message: &Message) -> Result<T, WireError> {
+    match message {
+        Message::Text(text) => Ok(serde_json::from_str(text)?),
+        Message::Binary(bytes) => Ok(rmp_serde::from_slice(bytes)?),
+        _ => Err(WireError::UnsupportedFrame),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WebsocketLimits {
+    pub max_messages_per_second: u32,
+    pub max_frame_size: usize,
+    pub max_pending_outbound: usize,
+    pub max_strikes: u32,
+}
+
+impl Default for WebsocketLimits {
+    fn default() -> Self {
+        WebsocketLimits {
+            max_messages_per_second: 120,
+            max_frame_size: 64 * 1024,
+            max_pending_outbound: 1024,
+            max_strikes: 20,
+        }
+    }
+}
+
+impl WebsocketLimits {
+    pub fn from_env() -> WebsocketLimits {
+        fn var_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+        let defaults = WebsocketLimits::default();
+
+        WebsocketLimits {
+            max_messages_per_second: var_or(
+                "WS_MAX_MESSAGES_PER_SECOND",
+                defaults.max_messages_per_second,
+            ),
+            max_frame_size: var_or("WS_MAX_FRAME_SIZE", defaults.max_frame_size),
+            max_pending_outbound: var_or("WS_MAX_PENDING_OUTBOUND", defaults.max_pending_outbound),
+            max_strikes: var_or("WS_MAX_STRIKES", defaults.max_strikes),
+        }
+    }
+
+    /// Lets tungstenite refuse oversized frames before they are buffered.
+    fn websocket_config(&self) -> WebSocketConfig {
+        WebSocketConfig {
+            max_frame_size: Some(self.max_frame_size),
+            max_message_size: Some(self.max_frame_size),
+            ..WebSocketConfig::default()
+        }
+    }
+}
+
+pub struct WebsocketMetrics {
+    pub dropped_rate_limited: AtomicU64,
+    pub dropped_oversized: AtomicU64,
+    pub dropped_outbound: AtomicU64,
+    pub closed_for_abuse: AtomicU64,
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+}
+
+pub static WEBSOCKET_METRICS: WebsocketMetrics = WebsocketMetrics {
+    dropped_rate_limited: AtomicU64::new(0),
+    dropped_oversized: AtomicU64::new(0),
+    dropped_outbound: AtomicU64::new(0),
+    closed_for_abuse: AtomicU64::new(0),
+    bytes_in: AtomicU64::new(0),
+    bytes_out: AtomicU64::new(0),
+};
+
+/// Token bucket that holds one second worth of messages.
+struct MessageRateLimiter {
+    messages_per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl MessageRateLimiter {
+    fn new(messages_per_second: u32, now: Instant) -> MessageRateLimiter {
+        MessageRateLimiter {
+            messages_per_second: messages_per_second as f64,
+            tokens: messages_per_second as f64,
+            refilled_at: now,
+        }
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.messages_per_second).min(self.messages_per_second);
+        self.refilled_at = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Strikes are shared between the reading task and the module, every dropped message
+/// counts and the connection is closed once there are too many.
+fn add_strike(strikes: &AtomicU32, limits: &WebsocketLimits) -> bool {
+    strikes.fetch_add(1, Ordering::Relaxed) + 1 >= limits.max_strikes
+}
+
+fn policy_violation(reason: &'static str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code: CloseCode::Policy,
+        reason: reason.into(),
+    }))
 }
 
 #[derive(Debug)]
 pub struct WSConnection {
     pub connection_id: Snowflake,
     pub ticket: Option<Ticket>,
+    pub protocol: WireProtocol,
     sender: Sender<Message>,
     receiver: Receiver<Message>,
+    strikes: Arc<AtomicU32>,
 }
 
 pub struct WebsocketModule {
     module_state: ModuleState,
+    limits: WebsocketLimits,
     pub connections: HashMap<Snowflake, WSConnection>,
     new_connection_receiver: Option<Receiver<WSConnection>>,
     new_connections: Vec<Snowflake>,
@@ -45,9 +270,10 @@ pub struct WebsocketModule {
 }
 
 impl WebsocketModule {
-    pub fn new() -> WebsocketModule {
+    pub fn new(limits: WebsocketLimits) -> WebsocketModule {
         WebsocketModule {
             module_state: ModuleState::Stopped,
+            limits,
             connections: HashMap::new(),
             new_connection_receiver: None,
             new_connections: Vec::new(),
@@ -64,13 +290,24 @@ impl WebsocketModule {
         if let Some(connection) = self.connections.get_mut(connection_id) {
             if let Err(err) = connection
                 .sender
-                .send(Message::Close(Some(CloseFrame { code, reason })))
+                .try_send(Message::Close(Some(CloseFrame { code, reason })))
             {
-                error!("Could not send close message! {:?}", err);
+                error!(
+                    "Could not send close message, dropping connection! {:?}",
+                    err
+                );
+                self.drop_connection(connection_id);
             }
         }
     }
 
+    /// Without its channels both tasks of the connection stop and the socket is closed.
+    fn drop_connection(&mut self, connection_id: &Snowflake) {
+        if self.connections.remove(connection_id).is_some() {
+            self.lost_connections.push(*connection_id);
+        }
+    }
+
     pub fn handle_new_ws_connections(&mut self) -> Vec<(Snowflake, Ticket)> {
         if let Some(receiver) = &self.new_connection_receiver {
             for guest_connection in receiver.drain() {
@@ -96,6 +333,7 @@ impl WebsocketModule {
                     for message in connection.receiver.drain() {
                         match serde_json::from_str::<Ticket>(message.to_string().as_str()) {
                             Ok(ticket) => {
+                                connection.protocol = ticket.wire_protocol();
                                 connection.ticket = Some(ticket);
                                 return true;
                             }
@@ -113,8 +351,18 @@ impl WebsocketModule {
         drained_cons
             .into_iter()
             .filter_map(|connection_id| {
-                if let Some(c) = connections.get(&connection_id) {
+                if let Some(c) = connections.get_mut(&connection_id) {
                     if let Some(ticket) = &c.ticket {
+                        if let Some(reason) = ticket.incompatibility() {
+                            debug!("Rejecting connection {}: {}", connection_id, reason);
+                            if let Err(err) = c.sender.try_send(Message::Close(Some(CloseFrame {
+                                code: CloseCode::Policy,
+                                reason: reason.into(),
+                            }))) {
+                                error!("Could not send close message {:?}", err);
+                            }
+                            return None;
+                        }
                         return Some((connection_id, ticket.clone()));
                     }
                 }
@@ -123,7 +371,7 @@ impl WebsocketModule {
             .collect()
     }
 
-    pub fn drain_lost_connections(&mut self) -> Drain<Snowflake> {
+    pub fn drain_lost_connections(&mut self) -> Drain<'_, Snowflake> {
         self.lost_connections.drain(..)
     }
 
@@ -139,11 +387,36 @@ impl WebsocketModule {
         }
     }
 
-    pub fn send_event(&mut self, ws_connection_id: &Snowflake, event: String) {
+    pub fn send_event<T: Serialize>(
+        &mut self,
+        ws_connection_id: &Snowflake,
+        event: &T,
+    ) -> Result<(), WireError> {
         trace!("Sending event to {}", ws_connection_id);
         if let Some(connection) = self.connections.get_mut(ws_connection_id) {
-            match connection.sender.try_send(Message::from(event)) {
-                Ok(_) => {}
+            let message = encode_message(connection.protocol, event)?;
+            let message_len = message.len() as u64;
+            match connection.sender.try_send(message) {
+                Ok(_) => {
+                    WEBSOCKET_METRICS
+                        .bytes_out
+                        .fetch_add(message_len, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full(_)) => {
+                    WEBSOCKET_METRICS
+                        .dropped_outbound
+                        .fetch_add(1, Ordering::Relaxed);
+                    if add_strike(&connection.strikes, &self.limits) {
+                        debug!(
+                            "Connection {} does not keep up, closing it.",
+                            ws_connection_id
+                        );
+                        WEBSOCKET_METRICS
+                            .closed_for_abuse
+                            .fetch_add(1, Ordering::Relaxed);
+                        self.drop_connection(ws_connection_id);
+                    }
+                }
                 Err(err) => {
                     error!("{:?}", err);
                 }
@@ -154,6 +427,7 @@ impl WebsocketModule {
                 ws_connection_id
             );
         }
+        Ok(())
     }
 
     pub fn drain_events(&mut self, ws_connection_id: &Snowflake) -> Vec<Message> {
@@ -172,12 +446,12 @@ impl SystemModule for WebsocketModule {
         debug!("Starting websocket module");
         self.module_state = ModuleState::Starting;
         let (connection_sender, connection_receiver) = unbounded();
-        spawn_websocket_server(connection_sender);
+        spawn_websocket_server(connection_sender, self.limits);
         self.new_connection_receiver = Some(connection_receiver);
     }
 }
 
-fn spawn_websocket_server(connection_sender: Sender<WSConnection>) {
+fn spawn_websocket_server(connection_sender: Sender<WSConnection>, limits: WebsocketLimits) {
     debug!("spawn_websocket_server");
     tokio::spawn(async move {
         debug!("TCP Thread spawned.");
@@ -188,12 +462,15 @@ fn spawn_websocket_server(connection_sender: Sender<WSConnection>) {
 
         match server_result {
             Ok(server) => {
+                SERVER_HEALTH
+                    .websocket_listening
+                    .store(true, Ordering::Relaxed);
                 let mut connection_id_generator = SnowflakeIdBucket::new(1, 8);
 
                 while let Ok((stream, _)) = server.accept().await {
                     debug!("New connection!");
                     let guest_connection_result =
-                        setup_ws_connection(connection_id_generator.get_id(), stream).await;
+                        setup_ws_connection(connection_id_generator.get_id(), stream, limits).await;
                     match guest_connection_result {
                         Ok(guest_connection) => {
                             match connection_sender.send_async(guest_connection).await {
@@ -211,6 +488,9 @@ fn spawn_websocket_server(connection_sender: Sender<WSConnection>) {
                     }
                 }
                 debug!("no more connections...?");
+                SERVER_HEALTH
+                    .websocket_listening
+                    .store(false, Ordering::Relaxed);
             }
             Err(err) => {
                 error!("Error while accepting connection {:?}", err);
@@ -222,48 +502,98 @@ fn spawn_websocket_server(connection_sender: Sender<WSConnection>) {
 async fn setup_ws_connection(
     connection_id: Snowflake,
     stream: TcpStream,
+    limits: WebsocketLimits,
 ) -> Result<WSConnection, WsError> {
     debug!("Setting up ws connection");
-    let websocket_stream = tokio_tungstenite::accept_async(stream).await?;
-    let (ws_out_sender, ws_out_receiver) = unbounded();
-    let (ws_in_sender, ws_in_receiver) = unbounded();
+    let websocket_stream =
+        tokio_tungstenite::accept_async_with_config(stream, Some(limits.websocket_config()))
+            .await?;
+    let (ws_out_sender, ws_out_receiver) = bounded(limits.max_pending_outbound);
+    let (ws_in_sender, ws_in_receiver) = bounded(limits.max_messages_per_second as usize);
+    let strikes = Arc::new(AtomicU32::new(0));
 
     let (outgoing, incoming) = websocket_stream.split();
     setup_sending_messages_to_websocket(outgoing, ws_out_receiver);
-    setup_reading_messages_from_websocket(incoming, ws_in_sender);
+    setup_reading_messages_from_websocket(
+        incoming,
+        ws_in_sender,
+        ws_out_sender.clone(),
+        strikes.clone(),
+        limits,
+    );
 
     Ok(WSConnection {
         connection_id,
         ticket: None,
+        protocol: WireProtocol::Json,
         sender: ws_out_sender,
         receiver: ws_in_receiver,
+        strikes,
     })
 }
 
+/// The inbox is bounded as well, a client that sends faster than the conductor drains
+/// simply is not read from until there is room again.
 fn setup_reading_messages_from_websocket(
-    incoming: SplitStream<WebSocketStream<TcpStream>>,
+    mut incoming: SplitStream<WebSocketStream<TcpStream>>,
     ws_in_sender: Sender<Message>,
+    ws_out_sender: Sender<Message>,
+    strikes: Arc<AtomicU32>,
+    limits: WebsocketLimits,
 ) {
     tokio::spawn(async move {
-        let for_each_result = incoming
-            .try_for_each(|msg| {
-                if msg.is_binary() || msg.is_text() {
-                    match ws_in_sender.send(msg) {
-                        Ok(()) => (),
-                        Err(err) => {
-                            error!("Error sending message to inbox {:?}", err);
-                        }
+        let mut rate_limiter =
+            MessageRateLimiter::new(limits.max_messages_per_second, Instant::now());
+        while let Some(msg) = incoming.next().await {
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(WsError::Capacity(err)) => {
+                    debug!("Refused oversized message {:?}", err);
+                    WEBSOCKET_METRICS
+                        .dropped_oversized
+                        .fetch_add(1, Ordering::Relaxed);
+                    if let Err(err) = ws_out_sender.try_send(policy_violation("Message too big")) {
+                        debug!("Could not send close message {:?}", err);
                     }
+                    return;
                 }
-                future::ok(())
-            })
-            .await;
-        match for_each_result {
-            Ok(()) => {
-                debug!("WS Reading thread unwound properly.");
+                Err(err) => {
+                    error!("Error while unwinding read stream {:?}", err);
+                    return;
+                }
+            };
+            if !(msg.is_binary() || msg.is_text()) {
+                continue;
+            }
+            WEBSOCKET_METRICS
+                .bytes_in
+                .fetch_add(msg.len() as u64, Ordering::Relaxed);
+
+            // Tungstenite refuses oversized messages while reading, this is a safety net that
+            // keeps the strikes and metrics in one place.
+            let dropped_counter = if msg.len() > limits.max_frame_size {
+                &WEBSOCKET_METRICS.dropped_oversized
+            } else if !rate_limiter.try_acquire(Instant::now()) {
+                &WEBSOCKET_METRICS.dropped_rate_limited
+            } else {
+                if let Err(err) = ws_in_sender.send_async(msg).await {
+                    debug!("Inbox is gone, stop reading {:?}", err);
+                    return;
+                }
+                continue;
+            };
+            dropped_counter.fetch_add(1, Ordering::Relaxed);
+            if add_strike(&strikes, &limits) {
+                WEBSOCKET_METRICS
+                    .closed_for_abuse
+                    .fetch_add(1, Ordering::Relaxed);
+                if let Err(err) = ws_out_sender.try_send(policy_violation("Too many messages")) {
+                    debug!("Could not send close message {:?}", err);
+                }
+                return;
             }
-            Err(err) => error!("Error while unwinding read stream {:?}", err),
         }
+        debug!("WS Reading thread unwound properly.");
     });
 }
 
@@ -291,3 +621,277 @@ fn setup_sending_messages_to_websocket(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::conductor_module::session_resume::SessionResume;
+    use crate::core::audit_log::{AuditLogEntry, AuditLogQuery};
+    use crate::core::blueprint::character_animation::{
+        CharacterAnimation, CharacterAnimationFrame, CharacterAnimationState, CharacterDirection,
+    };
+    use crate::core::blueprint::def::{
+        Achievement, BlueprintResource, CharAnimationToTilesetMap, Chunk, Conductor,
+        FileBrowserResult, GameMap, GidMap, IOPoint, Image, LayerKind, LeaderboardAggregation,
+        LeaderboardDefinition, LeaderboardResetPeriod, LeaderboardSortOrder, MapUpdate, Module,
+        ModuleUpdate, ResourceKind, SimpleAnimationFrame, TerrainParams, Tile, Tileset,
+    };
+    use crate::core::blueprint::ecs::def::{Entity, EntityUpdate, EntityUpdateKind};
+    use crate::core::blueprint::ecs::game_node_script::ScopeCacheValue;
+    use crate::core::blueprint::scene::def::{
+        AutoStepProps, Collider, ColliderKind, ColliderShape, CollisionShape, GameNode,
+        GameNodeKind, KinematicCharacterControllerProps, Node2D, Node2DDud, Node2DKind, Render,
+        RenderKind, RigidBody, RigidBodyType, Scene, Script, Transform,
+    };
+    use crate::core::entity::render::CameraSettings;
+    use crate::core::guest::{AdminRole, AdminRoleGrant, GuestBan, LoginProvider};
+    use crate::core::module::{
+        AdminToSystemEvent, CommunicationEvent, EditorEvent, GameSystemToGuestEvent, GuestInput,
+        GuestTo, GuestToModuleEvent, GuestToSystemEvent, MouseInputSchema, ProviderLoggedIn,
+        SceneNodeUpdate, SignalToMedium, TilesetUpdate, ToastAlertLevel,
+    };
+    use crate::core::module_system::position_snapshot::PositionSnapshot;
+    use crate::core::server_gate::{GateWindow, ServerGate};
+    use crate::core::stable_hash;
+    use crate::core::time_sync::{FrameStamp, TimeSyncPing, TimeSyncPong};
+    use crate::resource_module::def::{
+        LoadResource, LoadResourceKind, ResourceBundle, ResourceEvent,
+    };
+
+    /// The binding file of every listed type, along with what it needs to be complete.
+    macro_rules! exported_bindings {
+        ($($binding:ty),* $(,)?) => {
+            vec![$((
+                TypeId::of::<$binding>(),
+                <$binding as TS>::name(),
+                <$binding as TS>::export_to_string().unwrap(),
+                <$binding as TS>::dependencies(),
+            )),*]
+        };
+    }
+
+    #[test]
+    fn test_rate_limiter_refills_over_time() {
+        let start = Instant::now();
+        let mut rate_limiter = MessageRateLimiter::new(2, start);
+
+        assert!(rate_limiter.try_acquire(start));
+        assert!(rate_limiter.try_acquire(start));
+        assert!(!rate_limiter.try_acquire(start));
+        assert!(rate_limiter.try_acquire(start + Duration::from_millis(500)));
+        assert!(!rate_limiter.try_acquire(start + Duration::from_millis(500)));
+        assert!(rate_limiter.try_acquire(start + Duration::from_secs(10)));
+        assert!(rate_limiter.try_acquire(start + Duration::from_secs(10)));
+        assert!(!rate_limiter.try_acquire(start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_connections_strike_out() {
+        let limits = WebsocketLimits {
+            max_strikes: 2,
+            ..WebsocketLimits::default()
+        };
+        let strikes = AtomicU32::new(0);
+
+        assert!(!add_strike(&strikes, &limits));
+        assert!(add_strike(&strikes, &limits));
+    }
+
+    #[test]
+    fn test_oversized_frames_are_refused_while_reading() {
+        use std::io::Cursor;
+        use tungstenite::protocol::Role;
+        use tungstenite::WebSocket;
+
+        let limits = WebsocketLimits {
+            max_frame_size: 16,
+            ..WebsocketLimits::default()
+        };
+        let mut client = WebSocket::from_raw_socket(Cursor::new(Vec::new()), Role::Client, None);
+        client.send(Message::Text("small".into())).unwrap();
+        client.send(Message::Text("x".repeat(17))).unwrap();
+
+        let mut server = WebSocket::from_raw_socket(
+            Cursor::new(client.get_ref().get_ref().clone()),
+            Role::Server,
+            Some(limits.websocket_config()),
+        );
+        assert_eq!(server.read().unwrap(), Message::Text("small".into()));
+        assert!(matches!(server.read(), Err(WsError::Capacity(_))));
+    }
+
+    #[test]
+    fn test_message_pack_decodes_like_json() {
+        let ticket = Ticket {
+            session_token: Some("token".into()),
+            admin_login: None,
+            protocol: Some(WireProtocol::MessagePack),
+            resume: None,
+            protocol_version: Some(PROTOCOL_VERSION),
+        };
+
+        let json = encode_message(WireProtocol::Json, &ticket).unwrap();
+        let binary = encode_message(ticket.wire_protocol(), &ticket).unwrap();
+
+        assert!(json.is_text());
+        assert!(binary.is_binary());
+        assert_eq!(
+            decode_message::<serde_json::Value>(&json).unwrap(),
+            decode_message::<serde_json::Value>(&binary).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_editor_tickets_stay_on_json() {
+        let ticket: Ticket = serde_json::from_str(
+            r#"{"session_token":null,"admin_login":true,"protocol":"MessagePack"}"#,
+        )
+        .unwrap();
+        assert_eq!(ticket.wire_protocol(), WireProtocol::Json);
+
+        let ticket: Ticket =
+            serde_json::from_str(r#"{"session_token":null,"admin_login":null}"#).unwrap();
+        assert_eq!(ticket.wire_protocol(), WireProtocol::Json);
+    }
+
+    #[test]
+    fn test_tickets_without_the_current_version_are_incompatible() {
+        let ticket = |protocol_version| Ticket {
+            session_token: None,
+            admin_login: None,
+            protocol: None,
+            resume: None,
+            protocol_version,
+        };
+
+        assert_eq!(ticket(Some(PROTOCOL_VERSION)).incompatibility(), None);
+        assert!(ticket(Some(PROTOCOL_VERSION + 1))
+            .incompatibility()
+            .is_some());
+        assert!(ticket(None).incompatibility().is_some());
+    }
+
+    #[test]
+    fn test_bindings_match_protocol_version() {
+        // 1 and 2 were fingerprinted from the top level declarations only.
+        const PROTOCOL_FINGERPRINTS: [(u32, u64); 3] = [
+            (1, 0xe2cab98d3d678f82),
+            (2, 0xa5114a2a1199b96b),
+            (3, 0x88c7b4f4b031732),
+        ];
+
+        let bindings = exported_bindings![
+            Ticket,
+            WireProtocol,
+            ProtocolInfo,
+            CommunicationEvent,
+            EditorEvent,
+            GameSystemToGuestEvent,
+            GuestTo,
+            GuestToSystemEvent,
+            GuestToModuleEvent,
+            AdminToSystemEvent,
+            GuestInput,
+            PositionSnapshot,
+            FrameStamp,
+            TimeSyncPing,
+            TimeSyncPong,
+            SessionResume,
+            MouseInputSchema,
+            ProviderLoggedIn,
+            SceneNodeUpdate,
+            SignalToMedium,
+            TilesetUpdate,
+            ToastAlertLevel,
+            LoginProvider,
+            AdminRole,
+            AdminRoleGrant,
+            GuestBan,
+            AuditLogEntry,
+            AuditLogQuery,
+            GateWindow,
+            ServerGate,
+            CameraSettings,
+            LoadResource,
+            LoadResourceKind,
+            ResourceBundle,
+            ResourceEvent,
+            Achievement,
+            BlueprintResource,
+            CharAnimationToTilesetMap,
+            Chunk,
+            Conductor,
+            FileBrowserResult,
+            GameMap,
+            GidMap,
+            IOPoint,
+            Image,
+            LayerKind,
+            LeaderboardAggregation,
+            LeaderboardDefinition,
+            LeaderboardResetPeriod,
+            LeaderboardSortOrder,
+            MapUpdate,
+            Module,
+            ModuleUpdate,
+            ResourceKind,
+            SimpleAnimationFrame,
+            TerrainParams,
+            Tile,
+            Tileset,
+            CharacterAnimation,
+            CharacterAnimationFrame,
+            CharacterAnimationState,
+            CharacterDirection,
+            Entity,
+            EntityUpdate,
+            EntityUpdateKind,
+            ScopeCacheValue,
+            AutoStepProps,
+            Collider,
+            ColliderKind,
+            ColliderShape,
+            CollisionShape,
+            GameNode<Node2D>,
+            GameNodeKind,
+            KinematicCharacterControllerProps,
+            Node2D,
+            Node2DDud,
+            Node2DKind,
+            Render,
+            RenderKind,
+            RigidBody,
+            RigidBodyType,
+            Scene,
+            Script,
+            Transform,
+        ];
+        let fingerprinted: HashSet<TypeId> =
+            bindings.iter().map(|(type_id, ..)| *type_id).collect();
+        for (_, name, _, dependencies) in &bindings {
+            for dependency in dependencies {
+                assert!(
+                    fingerprinted.contains(&dependency.type_id),
+                    "{} depends on {}, add it to the fingerprinted bindings.",
+                    name,
+                    dependency.ts_name
+                );
+            }
+        }
+        let mut files: Vec<&String> = bindings.iter().map(|(_, _, file, _)| file).collect();
+        files.sort();
+        let fingerprint = stable_hash(files.into_iter().flat_map(|file| file.bytes()));
+
+        assert_eq!(
+            PROTOCOL_FINGERPRINTS.last(),
+            Some(&(PROTOCOL_VERSION, fingerprint)),
+            "The bindings changed, bump PROTOCOL_VERSION and add ({}, {:#x}).",
+            PROTOCOL_VERSION + 1,
+            fingerprint
+        );
+    }
+}
@@ -1,4 +1,5 @@
 import { login } from "../menu/twitch";
+import { login as anonymous_login } from "../menu/anonymous";
 import { CommunicationState } from "../communication";
 import { GameInstanceMap } from "@/client/game-instance";
 import { RenderSystem } from "@/client/renderer";
@@ -21,6 +22,8 @@ export const setup_medium_api = (
   window.medium = {
     twitch_login: (communication_state: CommunicationState) =>
       login(communication_state),
+    anonymous_login: (communication_state: CommunicationState) =>
+      anonymous_login(communication_state),
     communication_state: communication_state,
     is_instance_ready: (instance_id: string, world_id: string) => {
       return !!instances[instance_id] && !!instances[instance_id][world_id];
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AdminRole } from "./AdminRole";
+
+export interface AdminRoleGrant { provider_user_id: string, role: AdminRole, module_id: string | null, }
\ No newline at end of file
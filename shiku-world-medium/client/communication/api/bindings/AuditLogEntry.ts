@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface AuditLogEntry { id: number, provider_user_id: string, action: string, module_ids: Array<string>, resource: string | null, diff: string, created_at: bigint, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface AuditLogQuery { module_id: string | null, provider_user_id: string | null, before_id: number | null, limit: number | null, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuditLogEntry } from "./AuditLogEntry";
 import type { CharacterAnimation } from "../blueprints/CharacterAnimation";
 import type { Conductor } from "../blueprints/Conductor";
 import type { FileBrowserResult } from "../blueprints/FileBrowserResult";
@@ -10,4 +11,4 @@ import type { SceneNodeUpdate } from "./SceneNodeUpdate";
 import type { Script } from "../blueprints/Script";
 import type { Tileset } from "../blueprints/Tileset";
 
-export type EditorEvent = { Modules: Array<Module> } | { ModuleInstances: Array<[string, Array<string>]> } | { CreatedModule: [string, Module] } | { DeletedModule: string } | { UpdatedModule: [string, Module] } | { CreatedScript: Script } | { SetScript: Script } | { DeletedScript: Script } | { CreatedMap: GameMap } | { SetMap: GameMap } | { UpdatedMap: MapUpdate } | { DeletedMap: GameMap } | { CreatedScene: Scene } | { SetScene: Scene } | { UpdateScene: SceneNodeUpdate } | { DeletedScene: Scene } | { CreatedTileset: Tileset } | { SetTileset: Tileset } | { DeletedTileset: Tileset } | { CreatedCharacterAnimation: CharacterAnimation } | { SetCharacterAnimation: CharacterAnimation } | { DeletedCharacterAnimation: CharacterAnimation } | { DirectoryInfo: FileBrowserResult } | { UpdatedConductor: Conductor } | { ModuleInstanceOpened: [string, string] } | { ModuleInstanceClosed: [string, string] } | { MainDoorStatus: boolean };
\ No newline at end of file
+export type EditorEvent = { Modules: Array<Module> } | { ModuleInstances: Array<[string, Array<string>]> } | { CreatedModule: [string, Module] } | { DeletedModule: string } | { UpdatedModule: [string, Module] } | { CreatedScript: Script } | { SetScript: Script } | { DeletedScript: Script } | { CreatedMap: GameMap } | { SetMap: GameMap } | { UpdatedMap: MapUpdate } | { DeletedMap: GameMap } | { CreatedScene: Scene } | { SetScene: Scene } | { UpdateScene: SceneNodeUpdate } | { DeletedScene: Scene } | { CreatedTileset: Tileset } | { SetTileset: Tileset } | { DeletedTileset: Tileset } | { CreatedCharacterAnimation: CharacterAnimation } | { SetCharacterAnimation: CharacterAnimation } | { DeletedCharacterAnimation: CharacterAnimation } | { DirectoryInfo: FileBrowserResult } | { UpdatedConductor: Conductor } | { ModuleInstanceOpened: [string, string] } | { ModuleInstanceClosed: [string, string] } | { AuditLog: Array<AuditLogEntry> } | { RoundTripTimes: Array<[string, number]> };
\ No newline at end of file
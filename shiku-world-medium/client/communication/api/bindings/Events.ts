@@ -4,11 +4,14 @@ import type { EditorEvent } from "./EditorEvent";
 import type { GameSystemToGuestEvent } from "./GameSystemToGuestEvent";
 import type { GidMap } from "../blueprints/GidMap";
 import type { LayerKind } from "../blueprints/LayerKind";
+import type { ProtocolInfo } from "./ProtocolInfo";
 import type { ResourceBundle } from "./ResourceBundle";
 import type { ResourceEvent } from "./ResourceEvent";
+import type { SessionResume } from "./SessionResume";
 import type { SignalToMedium } from "./SignalToMedium";
 import type { TerrainParams } from "../blueprints/TerrainParams";
 import type { Tileset } from "../blueprints/Tileset";
+import type { TimeSyncPong } from "./TimeSyncPong";
 import type { ToastAlertLevel } from "./ToastAlertLevel";
 
-export type CommunicationEvent = { ResourceEvent: [string, ResourceEvent] } | { PrepareGame: [string, string, string | null, ResourceBundle, TerrainParams, Array<[LayerKind, number, number]>, Array<Tileset>, GidMap, CharAnimationToTilesetMap] } | { UnloadGame: [string, string, string | null] } | { GameSystemEvent: [string, string, string | null, GameSystemToGuestEvent] } | { ConnectionReady: [string, boolean] } | { Signal: SignalToMedium } | { Toast: [ToastAlertLevel, string] } | { ShowGlobalMessage: string } | "AlreadyConnected" | { EditorEvent: EditorEvent };
\ No newline at end of file
+export type CommunicationEvent = { ResourceEvent: [string, ResourceEvent] } | { PrepareGame: [string, string, string | null, ResourceBundle, TerrainParams, Array<[LayerKind, number, number]>, Array<Tileset>, GidMap, CharAnimationToTilesetMap] } | { UnloadGame: [string, string, string | null] } | { GameSystemEvent: [string, string, string | null, GameSystemToGuestEvent] } | { ConnectionReady: [string, boolean, ProtocolInfo] } | { Signal: SignalToMedium } | { Toast: [ToastAlertLevel, string] } | { ShowGlobalMessage: string } | "AlreadyConnected" | { SessionResumed: SessionResume } | { Pong: TimeSyncPong } | { EditorEvent: EditorEvent };
\ No newline at end of file
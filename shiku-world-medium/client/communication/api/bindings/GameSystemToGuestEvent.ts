@@ -4,10 +4,12 @@ import type { CharAnimationToTilesetMap } from "../blueprints/CharAnimationToTil
 import type { Chunk } from "../blueprints/Chunk";
 import type { Entity } from "../blueprints/Entity";
 import type { EntityUpdate } from "../blueprints/EntityUpdate";
+import type { FrameStamp } from "./FrameStamp";
 import type { GameNodeKind } from "../blueprints/GameNodeKind";
 import type { GidMap } from "../blueprints/GidMap";
 import type { LayerKind } from "../blueprints/LayerKind";
 import type { MouseInputSchema } from "./MouseInputSchema";
+import type { PositionSnapshot } from "./PositionSnapshot";
 import type { Scene } from "../blueprints/Scene";
 
-export type GameSystemToGuestEvent = { OpenMenu: string } | { CloseMenu: string } | { UpdateDataStore: string } | { ShowTerrain: Array<[LayerKind, Array<Chunk>]> } | { SetParallax: Array<[LayerKind, number, number]> } | { ShowTerrainCollisionLines: Array<Array<[number, number]>> } | { ShowScene: Scene } | { UpdateModuleMaps: [GidMap, CharAnimationToTilesetMap] } | { UpdateEntity: EntityUpdate } | { RemoveEntity: Entity } | { AddEntity: [Entity, GameNodeKind] } | { SetMouseInputSchema: MouseInputSchema } | { SetCamera: [string, CameraSettings] } | { PositionEvent: Array<[Entity, number, number, number]> };
\ No newline at end of file
+export type GameSystemToGuestEvent = { Frame: FrameStamp } | { OpenMenu: string } | { CloseMenu: string } | { UpdateDataStore: string } | { ShowTerrain: Array<[LayerKind, Array<Chunk>]> } | { HideTerrain: Array<[LayerKind, Array<[number, number]>]> } | { SetParallax: Array<[LayerKind, number, number]> } | { ShowTerrainCollisionLines: Array<Array<[number, number]>> } | { ShowScene: Scene } | { UpdateModuleMaps: [GidMap, CharAnimationToTilesetMap] } | { UpdateEntity: EntityUpdate } | { RemoveEntity: Entity } | { AddEntity: [Entity, GameNodeKind] } | { SetMouseInputSchema: MouseInputSchema } | { SetCamera: [string, CameraSettings] } | { PositionEvent: [number, Array<[Entity, number, number, number]>] } | { PositionSnapshot: PositionSnapshot };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface GuestBan { provider_user_id: string, reason: string, banned_until: bigint | null, }
\ No newline at end of file
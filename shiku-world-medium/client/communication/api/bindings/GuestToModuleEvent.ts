@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { GuestInput } from "./GuestInput";
 
-export type GuestToModuleEvent = { ControlInput: GuestInput } | "GameSetupDone" | { WantToChangeModule: string | null };
\ No newline at end of file
+export type GuestToModuleEvent = { ControlInput: GuestInput } | { PositionSnapshotAck: number } | "GameSetupDone" | { WantToChangeModule: string | null };
\ No newline at end of file
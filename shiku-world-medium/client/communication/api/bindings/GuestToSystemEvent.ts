@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ProviderLoggedIn } from "./ProviderLoggedIn";
+import type { TimeSyncPing } from "./TimeSyncPing";
 
-export type GuestToSystemEvent = { ProviderLoggedIn: ProviderLoggedIn } | "Ping";
\ No newline at end of file
+export type GuestToSystemEvent = { ProviderLoggedIn: ProviderLoggedIn } | { Ping: TimeSyncPing } | { PongEcho: number };
\ No newline at end of file
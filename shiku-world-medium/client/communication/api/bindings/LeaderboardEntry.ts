@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface LeaderboardEntry { persisted_guest_state_id: number, display_name: string, score: number, updated_at: bigint, }
\ No newline at end of file
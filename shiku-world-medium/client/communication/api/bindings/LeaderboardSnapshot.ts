@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LeaderboardEntry } from "./LeaderboardEntry";
+
+export interface LeaderboardSnapshot { id: string, title: string, period_start: bigint, entries: Array<LeaderboardEntry>, }
\ No newline at end of file
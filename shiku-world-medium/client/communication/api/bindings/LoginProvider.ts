@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type LoginProvider = "Twitch" | "Google";
\ No newline at end of file
+export type LoginProvider = "Twitch" | "Google" | "Oidc" | "Local" | "Anonymous";
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LeaderboardSnapshot } from "./LeaderboardSnapshot";
 import type { MediumDataStorageGuestInfo } from "./MediumDataStorageGuestInfo";
 
-export interface MediumDataStorage { current_guest_info: MediumDataStorageGuestInfo | null, }
\ No newline at end of file
+export interface MediumDataStorage { current_guest_info: MediumDataStorageGuestInfo | null, leaderboards: Record<string, LeaderboardSnapshot>, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AchievementEntry } from "./AchievementEntry";
 import type { SecretFoundEntry } from "./SecretFoundEntry";
 
-export interface MediumDataStorageGuestInfo { guests_online: number, guest_name: string, times_joined: number, secrets_found_count: number, secrets_found_map: Record<string, SecretFoundEntry>, }
\ No newline at end of file
+export interface MediumDataStorageGuestInfo { guests_online: number, guest_name: string, times_joined: number, secrets_found_count: number, secrets_found_map: Record<string, SecretFoundEntry>, achievements_unlocked_count: number, achievements_unlocked_map: Record<string, Record<string, AchievementEntry>>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Entity } from "../blueprints/Entity";
+
+export interface PositionSnapshot { id: number, tick: number, base: number | null, last_processed_input: number | null, position_precision: number, rotation_precision: number, entities: Array<[Entity, number, number, number]>, }
\ No newline at end of file
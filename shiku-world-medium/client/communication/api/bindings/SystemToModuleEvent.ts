@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type SystemToModuleEvent = { Disconnected: bigint } | { Reconnected: bigint };
\ No newline at end of file
+export type SystemToModuleEvent = { Disconnected: bigint } | { Reconnected: bigint } | { Resync: bigint } | { RoundTripTime: [bigint, number] };
\ No newline at end of file
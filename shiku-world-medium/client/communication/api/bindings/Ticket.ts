@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { WireProtocol } from "./WireProtocol";
 
-export interface Ticket { session_id: string | null, admin_login: boolean | null, }
\ No newline at end of file
+export interface Ticket { session_token: string | null, admin_login: boolean | null, protocol: WireProtocol | null, resume: boolean | null, protocol_version: number | null, }
\ No newline at end of file
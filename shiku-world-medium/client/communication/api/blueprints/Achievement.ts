@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Achievement { id: string, title: string, description: string, icon: string | null, hidden: boolean, }
\ No newline at end of file
@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { BlueprintResource } from "./BlueprintResource";
 import type { GidMap } from "./GidMap";
+import type { ServerGate } from "./ServerGate";
 
-export interface Conductor { module_connection_map: Record<string, [string, string]>, resources: Array<BlueprintResource>, gid_map: GidMap, }
\ No newline at end of file
+export interface Conductor { module_connection_map: Record<string, [string, string]>, resources: Array<BlueprintResource>, gid_map: GidMap, gates: Array<ServerGate>, }
\ No newline at end of file
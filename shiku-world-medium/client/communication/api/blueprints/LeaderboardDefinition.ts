@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LeaderboardAggregation } from "./LeaderboardAggregation";
+import type { LeaderboardResetPeriod } from "./LeaderboardResetPeriod";
+import type { LeaderboardSortOrder } from "./LeaderboardSortOrder";
+
+export interface LeaderboardDefinition { id: string, title: string, sort_order: LeaderboardSortOrder, aggregation: LeaderboardAggregation, reset_period: LeaderboardResetPeriod, }
\ No newline at end of file
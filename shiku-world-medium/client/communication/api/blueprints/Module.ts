@@ -1,7 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Achievement } from "./Achievement";
 import type { BlueprintResource } from "./BlueprintResource";
 import type { CharAnimationToTilesetMap } from "./CharAnimationToTilesetMap";
 import type { GidMap } from "./GidMap";
 import type { IOPoint } from "./IOPoint";
+import type { LeaderboardDefinition } from "./LeaderboardDefinition";
 
-export interface Module { id: string, name: string, resources: Array<BlueprintResource>, main_map: string | null, gid_map: GidMap, char_animation_to_tileset_map: CharAnimationToTilesetMap, insert_points: Array<IOPoint>, exit_points: Array<IOPoint>, max_guests: number, min_guests: number, close_after_full: boolean, }
\ No newline at end of file
+export interface Module { id: string, name: string, resources: Array<BlueprintResource>, main_map: string | null, gid_map: GidMap, char_animation_to_tileset_map: CharAnimationToTilesetMap, insert_points: Array<IOPoint>, exit_points: Array<IOPoint>, max_guests: number, min_guests: number, close_after_full: boolean, achievements: Array<Achievement>, leaderboards: Array<LeaderboardDefinition>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GateWindow } from "./GateWindow";
+
+export interface ServerGate { name: string, open: boolean, schedule: Array<GateWindow>, allowlist: Array<string>, refused_message: string | null, }
\ No newline at end of file
@@ -8,4 +8,6 @@ export interface CommunicationState {
   inbox: CommunicationEvent[];
   is_connection_open: boolean;
   is_connection_ready: boolean;
+  round_trip_time: number | null;
+  server_time_offset: number;
 }
@@ -0,0 +1,3 @@
+// Has to match PROTOCOL_VERSION in the server's websocket_module.rs, bump both when the
+// bindings change.
+export const PROTOCOL_VERSION = 3;
@@ -7,37 +7,111 @@ import { GuestTo } from "@/client/communication/api/bindings/GuestTo";
 import { GuestToSystemEvent } from "@/client/communication/api/bindings/GuestToSystemEvent";
 import { AdminToSystemEvent } from "@/client/communication/api/bindings/AdminToSystemEvent";
 import { is_admin } from "@/client/is_admin";
+import { TimeSyncPong } from "@/client/communication/api/bindings/TimeSyncPong";
+import { PROTOCOL_VERSION } from "@/client/communication/protocol";
+import { WireProtocol } from "@/client/communication/api/bindings/WireProtocol";
+import { decode, encode } from "@msgpack/msgpack";
+
+const TIME_SYNC_SMOOTHING = 0.2;
+// The server keeps a lost guest for 30 seconds, resuming has to happen within that.
+const MAX_RESUME_ATTEMPTS = 10;
+const RESUME_RETRY_DELAY = 2500;
+// Close code of a connection that dropped without a close frame.
+const ABNORMAL_CLOSURE = 1006;
+// Close code the server rejects incompatible clients with, the reason says why.
+const POLICY_VIOLATION = 1008;
+
+let resume_attempts = 0;
+
+// The editor stays on JSON, the game gets the smaller binary frames.
+export const wire_protocol: WireProtocol = is_admin ? "Json" : "MessagePack";
 
 export function setup_communication_system(): CommunicationState {
-  const ws_connection = new WebSocket(Config.getWsSocketUrl());
   const communication_state: CommunicationState = {
     is_connection_open: false,
     is_connection_ready: false,
+    round_trip_time: null,
+    server_time_offset: 0,
     inbox: [],
-    ws_connection,
+    ws_connection: new WebSocket(Config.getWsSocketUrl()),
   };
 
+  setup_connection_handlers(communication_state, false);
+
+  return communication_state;
+}
+
+function setup_connection_handlers(
+  communication_state: CommunicationState,
+  is_resuming: boolean,
+) {
+  const ws_connection = communication_state.ws_connection;
+  ws_connection.binaryType = "arraybuffer";
+
   ws_connection.onopen = () => {
     communication_state.is_connection_open = true;
+    if (is_resuming) {
+      send_ticket(
+        {
+          session_token: get_session_token(),
+          admin_login: is_admin,
+          protocol: wire_protocol,
+          resume: true,
+          protocol_version: PROTOCOL_VERSION,
+        },
+        communication_state,
+      );
+    }
   };
   ws_connection.onclose = (close_event) => {
+    communication_state.is_connection_open = false;
+    if (
+      !is_admin &&
+      communication_state.is_connection_ready &&
+      close_event.code === ABNORMAL_CLOSURE &&
+      resume_attempts < MAX_RESUME_ATTEMPTS
+    ) {
+      resume_attempts += 1;
+      setTimeout(() => {
+        communication_state.ws_connection = new WebSocket(
+          Config.getWsSocketUrl(),
+        );
+        setup_connection_handlers(communication_state, true);
+      }, RESUME_RETRY_DELAY);
+      return;
+    }
     const message = document.createElement("div");
     if (close_event.reason === "Logged in elsewhere") {
       message.innerHTML =
         "You seem to have logged in somewhere else, please login again if you want to use this device.";
+    } else if (close_event.code === POLICY_VIOLATION) {
+      message.innerText = close_event.reason;
     } else {
       message.innerHTML = "Connection to server closed, please try and reload.";
     }
-    communication_state.is_connection_open = false;
     document.body.prepend(message);
     document.querySelector("canvas")?.remove();
   };
 
   ws_connection.onmessage = (message: MessageEvent) => {
+    resume_attempts = 0;
     try {
-      communication_state.inbox.push(
-        JSON.parse(message.data) as CommunicationEvent,
-      );
+      const communication_event = (
+        message.data instanceof ArrayBuffer
+          ? decode(message.data)
+          : JSON.parse(message.data)
+      ) as CommunicationEvent;
+      // Echoed right away, the server times the round trip until it arrives.
+      if (
+        typeof communication_event === "object" &&
+        "Pong" in communication_event
+      ) {
+        send_system_event(
+          { PongEcho: communication_event.Pong.server_time },
+          communication_state,
+        );
+      }
+      communication_state.inbox.push(communication_event);
     } catch (e) {
       console.error(e);
     }
@@ -46,13 +120,15 @@ export function setup_communication_system(): CommunicationState {
   ws_connection.onerror = (event) => {
     console.error(event);
     communication_state.is_connection_open = false;
-    const message = document.createElement("div");
-    message.innerHTML =
-      "Connection to server encountered error, please try and reload.";
-    document.body.prepend(message);
   };
+}
 
-  return communication_state;
+function get_session_token(): string | null {
+  try {
+    return sessionStorage.getItem("session_token");
+  } catch (e) {
+    return null;
+  }
 }
 
 let last_message_send = Date.now();
@@ -72,24 +148,67 @@ export function check_for_connection_ready(
     if ("ConnectionReady" in communication) {
       communication_state.is_connection_ready = true;
       try {
-        sessionStorage.setItem("session_id", communication.ConnectionReady[0]);
+        sessionStorage.setItem(
+          "session_token",
+          communication.ConnectionReady[0],
+        );
       } catch (e) {
         console.error(
           "Seems like you block local storage or something, you'll have to login on every reload.",
         );
       }
 
+      if (!is_admin) {
+        send_time_sync_ping(communication_state);
+      }
       setInterval(() => {
-        if (Date.now() - last_message_send > 10000) {
-          is_admin
-            ? send_admin_event("Ping", communication_state)
-            : send_system_event("Ping", communication_state);
+        if (!is_admin) {
+          send_time_sync_ping(communication_state);
+        } else if (Date.now() - last_message_send > 10000) {
+          send_admin_event("Ping", communication_state);
         }
       }, 10000);
     }
   }
 }
 
+export function send_time_sync_ping(communication_state: CommunicationState) {
+  send_system_event(
+    {
+      Ping: {
+        client_time: Date.now(),
+      },
+    },
+    communication_state,
+  );
+}
+
+export function handle_time_sync_pong(
+  pong: TimeSyncPong,
+  communication_state: CommunicationState,
+) {
+  const now = Date.now();
+  const round_trip_time = now - pong.client_time;
+  const server_time_offset = pong.server_time + round_trip_time / 2 - now;
+  if (communication_state.round_trip_time === null) {
+    communication_state.round_trip_time = round_trip_time;
+    communication_state.server_time_offset = server_time_offset;
+    return;
+  }
+  communication_state.round_trip_time +=
+    (round_trip_time - communication_state.round_trip_time) *
+    TIME_SYNC_SMOOTHING;
+  communication_state.server_time_offset +=
+    (server_time_offset - communication_state.server_time_offset) *
+    TIME_SYNC_SMOOTHING;
+}
+
+export function estimate_server_time(
+  communication_state: CommunicationState,
+): number {
+  return Date.now() + communication_state.server_time_offset;
+}
+
 export function send_system_event(
   input: GuestToSystemEvent,
   communication_state: CommunicationState,
@@ -116,7 +235,9 @@ export function send_event<T>(
   communication_state: CommunicationState,
 ) {
   if (communication_state.is_connection_open) {
-    communication_state.ws_connection.send(JSON.stringify(input));
+    communication_state.ws_connection.send(
+      wire_protocol === "MessagePack" ? encode(input) : JSON.stringify(input),
+    );
     last_message_send = Date.now();
   }
 }
@@ -15,6 +15,13 @@ import { LayerKind } from "@/editor/blueprints/LayerKind";
 import { update_grid } from "@/client/renderer/grid";
 import { Container, Graphics } from "pixi.js";
 import { RENDER_SCALE } from "@/shared/index";
+import { PositionSnapshot } from "@/client/communication/api/bindings/PositionSnapshot";
+import { Entity } from "@/client/communication/api/blueprints/Entity";
+import { CommunicationState } from "@/client/communication";
+import { send_module_event } from "@/client/communication/setup_communication_system";
+import { FrameStamp } from "@/client/communication/api/bindings/FrameStamp";
+
+const MAX_KEPT_POSITION_SNAPSHOTS = 128;
 
 const collision_graphic_colors = [
   "#FFD400",
@@ -34,6 +41,10 @@ export class GameInstance {
   terrain_manager: TerrainManager;
   layer_map_keys: LayerKind[];
   collision_lines: Container;
+  position_snapshots: Map<number, Map<Entity, [number, number, number]>> =
+    new Map();
+  applied_positions: Map<Entity, [number, number, number]> = new Map();
+  current_frame: FrameStamp | null = null;
 
   constructor(
     public id: string,
@@ -78,6 +89,7 @@ export class GameInstance {
     game_system_event: GameSystemToGuestEvent,
     menu_system: MenuSystem,
     resource_manager: ResourceManager,
+    communication_state: CommunicationState,
   ) {
     match(game_system_event)
       .with({ SetCamera: P.select() }, ([entity_id, camera_settings]) => {
@@ -147,6 +159,17 @@ export class GameInstance {
           }
         }
       })
+      .with({ HideTerrain: P.select() }, (layers) => {
+        for (const [layer_kind, chunk_positions] of layers) {
+          for (const chunk_position of chunk_positions) {
+            this.terrain_manager.remove_chunk(
+              this.renderer,
+              layer_kind,
+              chunk_position,
+            );
+          }
+        }
+      })
       .with({ UpdateDataStore: P.select() }, (store_update) => {
         try {
           const update = JSON.parse(store_update) as MediumDataStorage;
@@ -189,7 +212,10 @@ export class GameInstance {
           this.renderer.layer_map[layer_kind].y_pscaling = y;
         }
       })
-      .with({ PositionEvent: P.select() }, (entities) => {
+      .with({ Frame: P.select() }, (frame) => {
+        this.current_frame = frame;
+      })
+      .with({ PositionEvent: P.select() }, ([_tick, entities]) => {
         for (const [entity, x, y, r] of entities) {
           window.medium_gui.game_instances.apply_entity_update_for_instance(
             this.id,
@@ -204,6 +230,14 @@ export class GameInstance {
           );
         }
       })
+      .with({ PositionSnapshot: P.select() }, (snapshot) => {
+        if (this.apply_position_snapshot(snapshot, resource_manager)) {
+          send_module_event(
+            { PositionSnapshotAck: snapshot.id },
+            communication_state,
+          );
+        }
+      })
       .with({ ShowTerrainCollisionLines: P.select() }, (lines) => {
         this.draw_terrain_collisions(lines);
       })
@@ -221,6 +255,68 @@ export class GameInstance {
 
   destroy() {}
 
+  reset_world() {
+    this.terrain_manager.remove_all_chunks_for_module(this.renderer);
+    this.renderer.layer_map.ObjectsBelow.removeChildren();
+    this.position_snapshots.clear();
+    this.applied_positions.clear();
+    this.current_frame = null;
+  }
+
+  apply_position_snapshot(
+    snapshot: PositionSnapshot,
+    resource_manager: ResourceManager,
+  ): boolean {
+    const base =
+      snapshot.base === null
+        ? new Map<Entity, [number, number, number]>()
+        : this.position_snapshots.get(snapshot.base);
+    if (!base) {
+      return false;
+    }
+    const state = new Map(base);
+    for (const [entity, x, y, r] of snapshot.entities) {
+      const [base_x, base_y, base_r] = base.get(entity) ?? [0, 0, 0];
+      state.set(entity, [base_x + x, base_y + y, base_r + r]);
+    }
+    this.position_snapshots.set(snapshot.id, state);
+    for (const snapshot_id of this.position_snapshots.keys()) {
+      if (this.position_snapshots.size <= MAX_KEPT_POSITION_SNAPSHOTS) {
+        break;
+      }
+      this.position_snapshots.delete(snapshot_id);
+    }
+
+    for (const [entity, [x, y, r]] of state) {
+      const applied = this.applied_positions.get(entity);
+      if (
+        applied &&
+        applied[0] === x &&
+        applied[1] === y &&
+        applied[2] === r
+      ) {
+        continue;
+      }
+      this.applied_positions.set(entity, [x, y, r]);
+      window.medium_gui.game_instances.apply_entity_update_for_instance(
+        this.id,
+        this.world_id,
+        {
+          id: entity,
+          kind: {
+            PositionRotation: [
+              x * snapshot.position_precision,
+              y * snapshot.position_precision,
+              r * snapshot.rotation_precision,
+            ],
+          },
+        },
+        resource_manager,
+      );
+    }
+    return true;
+  }
+
   draw_terrain_collisions(lines: [number, number][][]) {
     this.collision_lines.removeChildren();
     let i = 0;
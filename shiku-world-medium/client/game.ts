@@ -1,10 +1,12 @@
 import { create_game_renderer } from "./renderer/create_game_renderer";
 import {
   check_for_connection_ready,
+  handle_time_sync_pong,
   send_admin_event,
   send_module_event,
   send_ticket,
   setup_communication_system,
+  wire_protocol,
 } from "./communication/setup_communication_system";
 import { MenuSystem } from "./menu";
 import { create_guest_input } from "./input/create_guest_input";
@@ -26,6 +28,8 @@ import { create_resource_manager } from "@/client/resources/create_resource_mana
 import { is_admin } from "@/client/is_admin";
 import { handle_editor_event } from "@/client/handle-editor-event";
 import { init_grid, toggle_grid } from "@/client/renderer/grid";
+import { PROTOCOL_VERSION } from "@/client/communication/protocol";
+import { store_guest_token } from "@/client/menu/anonymous";
 
 export async function start_medium() {
   const signal_broadcast_channel = new BroadcastChannel(signal_channel_name);
@@ -106,10 +110,22 @@ export async function start_medium() {
     for (const communication_event of communication_system.inbox) {
       match(communication_event)
         .with("AlreadyConnected", () => {})
+        .with({ SessionResumed: P.select() }, (session_resume) => {
+          if (session_resume === "Resync") {
+            for (const instances_per_world of Object.values(instances)) {
+              for (const instance of Object.values(instances_per_world)) {
+                instance.reset_world();
+              }
+            }
+          }
+        })
+        .with({ Pong: P.select() }, (pong) => {
+          handle_time_sync_pong(pong, communication_system);
+        })
         .with({ EditorEvent: P.select() }, handle_editor_event)
         .with(
           { ConnectionReady: P.select() },
-          ([_session_id, should_login]) => {
+          ([_session_token, should_login]) => {
             console.log("Connection ready", should_login);
             if (should_login) {
               menu_system.activate("login-menu");
@@ -235,11 +251,16 @@ export async function start_medium() {
                 game_system_event,
                 menu_system,
                 lazy_get_resource_manager(module_id),
+                communication_system,
               );
             }
           },
         )
         .with({ Signal: P.select() }, (signal_to_guest) => {
+          if (typeof signal_to_guest === "object") {
+            store_guest_token(signal_to_guest.GuestToken);
+            return;
+          }
           if (signal_to_guest === "LoginSuccess") {
             menu_system.deactivate("login-menu");
             if (is_admin) {
@@ -301,10 +322,10 @@ export async function start_medium() {
 
   const interval_handle = setInterval(() => {
     if (communication_system.is_connection_open) {
-      let session_id = null;
+      let session_token = null;
 
       try {
-        session_id = sessionStorage.getItem("session_id");
+        session_token = sessionStorage.getItem("session_token");
       } catch (e) {
         console.error(
           "Seems like you block local storage or something, you'll have to login on every reload.",
@@ -323,8 +344,11 @@ export async function start_medium() {
 
       send_ticket(
         {
-          session_id,
+          session_token,
           admin_login: is_admin,
+          protocol: wire_protocol,
+          resume: null,
+          protocol_version: PROTOCOL_VERSION,
         },
         communication_system,
       );
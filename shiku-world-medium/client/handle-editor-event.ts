@@ -3,9 +3,6 @@ import { EditorEvent } from "@/client/communication/api/bindings/EditorEvent";
 
 export function handle_editor_event(event: EditorEvent) {
   match(event)
-    .with({ MainDoorStatus: P.select() }, (status) => {
-      window.medium_gui.editor.set_main_door_status(status);
-    })
     .with({ Modules: P.select() }, (modules) => {
       window.medium_gui.resources.set_modules(modules);
     })
@@ -96,5 +93,11 @@ export function handle_editor_event(event: EditorEvent) {
         );
       },
     )
+    .with({ AuditLog: P.select() }, (entries) => {
+      console.table(entries);
+    })
+    .with({ RoundTripTimes: P.select() }, (round_trip_times) => {
+      console.table(round_trip_times);
+    })
     .exhaustive();
 }
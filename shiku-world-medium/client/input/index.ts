@@ -22,6 +22,8 @@ export enum Button {
   Exit = "Exit",
 }
 
+let next_input_sequence = 0;
+
 export function create_guest_input_event(
   guest_input_state: GuestInputState
 ): GuestInput {
@@ -52,5 +54,6 @@ export function create_guest_input_event(
     action_2: guest_input_state.button_pressed_map[Button.Action2]
       ? guest_input_state.button_pressed_map[Button.Action2]
       : false,
+    sequence: next_input_sequence++,
   };
 }
@@ -0,0 +1,44 @@
+import { CommunicationState, signal_channel_name } from "../communication";
+import { SignalToMedium } from "../communication/api/bindings/SignalToMedium";
+import { send_system_event } from "../communication/setup_communication_system";
+
+const guest_token_key = "guest_token";
+
+export function store_guest_token(guest_token: string) {
+  localStorage.setItem(guest_token_key, guest_token);
+}
+
+export function login(communication_state: CommunicationState): Promise<void> {
+  const signal_channel = new BroadcastChannel(signal_channel_name);
+
+  return new Promise((resolve, reject) => {
+    try {
+      signal_channel.onmessage = async (message) => {
+        const signal = message.data as SignalToMedium;
+        if (signal === "LoginSuccess") {
+          resolve();
+        }
+
+        if (signal === "LoginFailed") {
+          reject();
+        }
+
+        signal_channel.close();
+      };
+
+      send_system_event(
+        {
+          ProviderLoggedIn: {
+            login_provider: "Anonymous",
+            auth_code: localStorage.getItem(guest_token_key),
+            access_token: null,
+          },
+        },
+        communication_state,
+      );
+    } catch (e) {
+      console.error(e);
+      reject(e);
+    }
+  });
+}
@@ -65,6 +65,31 @@ export class TerrainManager {
         renderer.layer_map[layer].removeChild(chunk.container);
       }
     }
+    this._chunk_map.clear();
+  }
+
+  remove_chunk(
+    renderer: InstanceRendering,
+    layer_kind: LayerKind,
+    [chunk_x, chunk_y]: [number, number],
+  ) {
+    const chunk_map = this._chunk_map.get(layer_kind);
+    const chunk_key = cantor_pair(chunk_x, chunk_y);
+    const chunk_map_entry = chunk_map?.get(chunk_key);
+    if (!chunk_map || !chunk_map_entry) {
+      return;
+    }
+    renderer.layer_map[layer_kind].removeChild(chunk_map_entry.container);
+    chunk_map.delete(chunk_key);
+    const tile_key_prefix = `${layer_kind}_${chunk_x}_${chunk_y}_`;
+    for (const tile_key of Object.keys(this._animations)) {
+      if (tile_key.startsWith(tile_key_prefix)) {
+        delete this._animations[tile_key];
+      }
+    }
+    this._active_animations = this._active_animations.filter(
+      (tile_effect) => tile_effect.sprite.parent !== chunk_map_entry.container,
+    );
   }
 
   update_effects() {
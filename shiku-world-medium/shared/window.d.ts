@@ -1,5 +1,6 @@
 import { InputPlugin } from "@/client/plugins";
 import { login } from "@/client/menu/twitch";
+import { login as anonymous_login } from "@/client/menu/anonymous";
 import { CommunicationState } from "@/client/communication";
 import { use_ui_store } from "@/editor/stores/ui";
 import { use_config_store } from "@/editor/stores/config";
@@ -25,6 +26,7 @@ declare global {
     register_input_plugin: (plugin: InputPlugin) => void;
     medium: {
       twitch_login: typeof login;
+      anonymous_login: typeof anonymous_login;
       communication_state: CommunicationState;
       is_instance_ready: (instance_id: string, world_id: string) => boolean;
       get_resource_manager: (module_id: string) => ResourceManager | undefined;
@@ -35,4 +35,5 @@ export const use_medium_api = (): typeof window.medium =>
         set_camera_iso: (_a: string, _b: string, _c: Isometry) => {},
         set_camera_zoom: (_a: string, _b: string, _c: number) => {},
         twitch_login: (_: CommunicationState) => Promise.resolve(),
+        anonymous_login: (_: CommunicationState) => Promise.resolve(),
       };
@@ -1,5 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Achievement } from "./Achievement";
 import type { BlueprintResource } from "./BlueprintResource";
 import type { IOPoint } from "./IOPoint";
+import type { LeaderboardDefinition } from "./LeaderboardDefinition";
 
-export interface ModuleUpdate { name: string | null, resources: Array<BlueprintResource> | null, insert_points: Array<IOPoint> | null, exit_points: Array<IOPoint> | null, main_map: string | null | null, max_guests: number | null, min_guests: number | null, }
\ No newline at end of file
+export interface ModuleUpdate { name: string | null, resources: Array<BlueprintResource> | null, insert_points: Array<IOPoint> | null, exit_points: Array<IOPoint> | null, main_map: string | null | null, max_guests: number | null, min_guests: number | null, achievements: Array<Achievement> | null, leaderboards: Array<LeaderboardDefinition> | null, }
\ No newline at end of file
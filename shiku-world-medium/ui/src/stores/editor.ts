@@ -22,7 +22,6 @@ export type InspectorComponent =
 
 export interface EditorStore {
   editor_open: boolean;
-  main_door_status: boolean;
   selected_module_id: string;
   current_main_instance_id: string;
   current_map_path: string;
@@ -63,7 +62,6 @@ export const use_editor_store = defineStore(
       active_component: "nothing",
       editor_open: false,
       module_instance_map: {},
-      main_door_status: false,
       tile_brush: [[0]],
       selected_module_id: "",
       selected_tileset_path: "",
@@ -200,8 +198,8 @@ export const use_editor_store = defineStore(
       set_current_main_instance_id(id: string) {
         state.current_main_instance_id = id;
       },
-      set_main_door_status(status: boolean) {
-        state.main_door_status = status;
+      set_gate_open_server(name: string, open: boolean) {
+        send_admin_event({ SetGateOpen: [name, open] });
       },
 
       open_game_instance_server(module_id: string) {
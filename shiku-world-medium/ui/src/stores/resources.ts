@@ -336,6 +336,8 @@ export const use_resources_store = defineStore("resources", () => {
             resources: null,
             insert_points: null,
             main_map: null,
+            achievements: null,
+            leaderboards: null,
             ...module_update,
           },
         ],
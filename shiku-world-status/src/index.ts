@@ -18,24 +18,37 @@ fastify.get('/', async (_request, reply) => {
   return reply.sendFile('./index.html', __dirname);
 });
 
-type DoorStatusCheck =
-  | { type: 'open' }
-  | { type: 'lightsOn' }
+interface GateStatus {
+  name: string;
+  open: boolean;
+  schedule: Array<{ opens_at: string; closes_at: string }>;
+  message: string | null;
+}
+
+type GateStatusCheck =
+  | { type: 'open'; gate: GateStatus }
+  | { type: 'lightsOn'; gate: GateStatus }
   | { type: 'lightsOut' }
+  | { type: 'unknownGate' }
   | { type: 'urlNotConfigured' }
   | { type: 'unknownError'; error: Error };
 
-async function getDoorStatus(
-  statusUrl: string | undefined
-): Promise<DoorStatusCheck> {
+async function getGateStatus(
+  gatesUrl: string | undefined,
+  name: string
+): Promise<GateStatusCheck> {
   try {
-    if (!statusUrl) {
+    if (!gatesUrl) {
       return { type: 'urlNotConfigured' };
     }
-    return (await axios.get<boolean>(statusUrl)).data
-      ? { type: 'open' }
-      : { type: 'lightsOn' };
+    const gate = (
+      await axios.get<GateStatus>(`${gatesUrl}/${encodeURIComponent(name)}`)
+    ).data;
+    return gate.open ? { type: 'open', gate } : { type: 'lightsOn', gate };
   } catch (e) {
+    if (axios.isAxiosError(e) && e.response?.status === 404) {
+      return { type: 'unknownGate' };
+    }
     if (e instanceof Error) {
       if (e.message.includes('ECONNREFUSED')) {
         return { type: 'lightsOut' };
@@ -49,13 +62,14 @@ async function getDoorStatus(
     };
   }
 }
-fastify.get('/main-door-status', async (request, reply) => {
-  return reply.send(await getDoorStatus(process.env.GET_MAIN_DOOR_STATUS_URL));
-});
-
-fastify.get('/back-door-status', async (request, reply) => {
-  return reply.send(await getDoorStatus(process.env.GET_BACK_DOOR_STATUS_URL));
-});
+fastify.get<{ Params: { name: string } }>(
+  '/gates/:name',
+  async (request, reply) => {
+    return reply.send(
+      await getGateStatus(process.env.GET_GATES_URL, request.params.name)
+    );
+  }
+);
 
 fastify.listen({ host: '0.0.0.0', port: 3000 }, (err, _address) => {
   if (err) throw err;